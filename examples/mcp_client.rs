@@ -5,6 +5,34 @@ fn main() {
     println!("Ethereum MCP Server Example Client");
     println!("===================================");
 
+    // Example 0: Lifecycle handshake (required before any tool call)
+    println!("\n0. Initialize Handshake Example:");
+    let initialize_request = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": {
+                "name": "example-client",
+                "version": "0.1.0"
+            }
+        }
+    });
+    println!(
+        "Request: {}",
+        serde_json::to_string_pretty(&initialize_request).unwrap()
+    );
+    let initialized_notification = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/initialized"
+    });
+    println!(
+        "Notification: {}",
+        serde_json::to_string_pretty(&initialized_notification).unwrap()
+    );
+
     // Example 1: Query ETH balance
     println!("\n1. Query ETH Balance Example:");
     let balance_request = json!({
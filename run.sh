@@ -5,13 +5,11 @@
 echo "🚀 启动 Ethereum MCP Server"
 echo "================================"
 
-export PRIVATE_KEY="0xee47965684a23f4c2c4447ad7ff164cc0f7539cfcd313700fb353d25ea479e1a"
-
 # 检查是否设置了私钥
 if [ -z "$PRIVATE_KEY" ]; then
-    echo "❌ 错误：未设置 PRIVATE_KEY 环境变量"
+    echo "ℹ️  未设置 PRIVATE_KEY 环境变量，将以只读模式启动（仅支持余额和价格查询）"
     echo ""
-    echo "请按以下方式之一设置私钥："
+    echo "如需签名交易，请按以下方式之一设置私钥："
     echo ""
     echo "方法 1 - 设置环境变量："
     echo "  export PRIVATE_KEY=0x你的私钥"
@@ -26,14 +24,13 @@ if [ -z "$PRIVATE_KEY" ]; then
     echo ""
     echo "⚠️  注意：请确保私钥以 0x 开头且长度为 66 个字符"
     echo "⚠️  私钥用于签名交易，请确保安全存储"
-    exit 1
-fi
-
-# 验证私钥格式
-if [[ ! $PRIVATE_KEY =~ ^0x[0-9a-fA-F]{64}$ ]]; then
+    echo ""
+elif [[ ! $PRIVATE_KEY =~ ^0x[0-9a-fA-F]{64}$ ]]; then
     echo "❌ 错误：私钥格式不正确"
     echo "请确保私钥以 0x 开头且长度为 66 个字符（包括 0x 前缀）"
     exit 1
+else
+    echo "🔑 私钥已设置（前8位: ${PRIVATE_KEY:0:10}...）"
 fi
 
 # 设置默认 RPC URL（如果未设置）
@@ -44,7 +41,6 @@ else
     echo "📡 使用 RPC: $ETHEREUM_RPC_URL"
 fi
 
-echo "🔑 私钥已设置（前8位: ${PRIVATE_KEY:0:10}...）"
 echo ""
 
 # 编译并运行
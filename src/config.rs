@@ -0,0 +1,603 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use ethers::types::{Address, H256};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tracing::Level;
+use url::Url;
+
+/// 未通过 --config 指定时尝试加载的配置文件
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// 命令行参数，优先级高于环境变量和配置文件
+#[derive(Debug, Default, Parser)]
+#[command(version, about = "MCP server for Ethereum balance queries and token swaps")]
+pub struct Cli {
+    /// 配置文件路径（默认读取当前目录下的 config.toml，不存在时使用默认配置）
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+
+    /// Ethereum RPC URL
+    #[arg(long)]
+    pub rpc_url: Option<String>,
+
+    /// MCP 传输方式
+    #[arg(long, value_enum)]
+    pub transport: Option<TransportKind>,
+
+    /// HTTP 传输监听地址
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// HTTP 传输监听端口
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// 日志级别
+    #[arg(long, value_enum)]
+    pub log_level: Option<LogLevel>,
+}
+
+/// 服务器配置，对应 config.toml 的结构
+///
+/// 加载优先级：命令行参数 > 环境变量 > 配置文件 > 默认值。
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub ethereum: EthereumConfig,
+    pub mcp: McpConfig,
+    pub logging: LoggingConfig,
+    /// 签名后端，未配置时使用 [ethereum] private_key（若有）
+    pub signer: Option<SignerConfig>,
+    /// 链相关设置，键为链名称（如 mainnet、arbitrum）
+    pub chains: BTreeMap<String, ChainSettings>,
+    pub quorum: QuorumConfig,
+    pub execution: ExecutionConfig,
+    pub swap: SwapConfig,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EthereumConfig {
+    /// 未设置时自动选择可用的公共 RPC
+    pub rpc_url: Option<Url>,
+    /// 备用 RPC，rpc_url 不可用时按健康度依次尝试
+    pub fallback_rpc_urls: Vec<Url>,
+    /// 主网是否把内置公共 RPC 加入备用池（仅在配置的 RPC 都不可用时使用）
+    pub public_fallback: bool,
+    pub private_key: Option<String>,
+}
+
+impl Default for EthereumConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: None,
+            fallback_rpc_urls: Vec::new(),
+            public_fallback: true,
+            private_key: None,
+        }
+    }
+}
+
+/// 非私钥形式的签名后端
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SignerConfig {
+    /// 加密 JSON keystore，未指定 password_file 时在终端提示输入密码
+    Keystore {
+        path: PathBuf,
+        #[serde(default)]
+        password_file: Option<PathBuf>,
+    },
+    /// BIP-39 助记词，优先读取 MNEMONIC 环境变量，其次读取 phrase_file
+    Mnemonic {
+        #[serde(default)]
+        phrase_file: Option<PathBuf>,
+        #[serde(default = "default_derivation_path")]
+        derivation_path: String,
+    },
+    /// 外部签名进程（如 Clef），endpoint 为 IPC socket 路径或 http(s) URL
+    External {
+        endpoint: String,
+        #[serde(default)]
+        address: Option<Address>,
+    },
+}
+
+fn default_derivation_path() -> String {
+    "m/44'/60'/0'/0/0".to_string()
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpConfig {
+    pub transport: TransportKind,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            transport: TransportKind::Stdio,
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl McpConfig {
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        format!("{}:{}", self.host, self.port)
+            .parse()
+            .with_context(|| format!("无效的监听地址 {}:{}", self.host, self.port))
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub level: LogLevel,
+}
+
+/// 仲裁读取：余额和价格查询使用的 eth_getBalance / eth_call 同时发给
+/// `providers` 个 RPC（固定在同一区块），至少 `threshold` 个结果一致才返回
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuorumConfig {
+    pub enabled: bool,
+    pub providers: usize,
+    pub threshold: usize,
+}
+
+impl Default for QuorumConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            providers: 3,
+            threshold: 2,
+        }
+    }
+}
+
+/// 交易广播设置，默认关闭：未开启时只返回已签名交易，不会发送上链
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionConfig {
+    pub enabled: bool,
+    /// 广播后等待的确认数
+    pub confirmations: usize,
+    /// 等待确认的超时时间（秒）
+    pub timeout_secs: u64,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            confirmations: 1,
+            timeout_secs: 300,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SwapConfig {
+    /// 授权额度不足时生成的 approve 交易额度，可被工具参数 approval 覆盖
+    pub approval: ApprovalMode,
+    /// 固定输入兑换使用的执行路由，可被工具参数 router 覆盖
+    pub router: SwapRouterKind,
+    /// EIP-1559 费用档位，可被工具参数 gas_speed 覆盖
+    pub gas_speed: GasSpeed,
+}
+
+/// 兑换交易发往的路由合约
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwapRouterKind {
+    /// V2 Router02 / V3 SwapRouter（拆单走 SwapRouter02），对各路由单独 approve
+    #[default]
+    Legacy,
+    /// Universal Router，代币只需授权给 Permit2，每笔兑换附带 Permit2 签名
+    Universal,
+}
+
+impl SwapRouterKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Legacy => "legacy",
+            Self::Universal => "universal",
+        }
+    }
+}
+
+impl FromStr for SwapRouterKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "legacy" => Ok(Self::Legacy),
+            "universal" => Ok(Self::Universal),
+            _ => bail!("未知的路由类型: {}（可选 legacy 或 universal）", value),
+        }
+    }
+}
+
+/// EIP-1559 费用档位，分别取 eth_feeHistory 中第 10 / 50 / 90 百分位的小费
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GasSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl GasSpeed {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Slow => "slow",
+            Self::Normal => "normal",
+            Self::Fast => "fast",
+        }
+    }
+}
+
+impl FromStr for GasSpeed {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "slow" => Ok(Self::Slow),
+            "normal" => Ok(Self::Normal),
+            "fast" => Ok(Self::Fast),
+            _ => bail!("未知的 gas 档位: {}（可选 slow、normal 或 fast）", value),
+        }
+    }
+}
+
+/// approve 交易的授权额度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalMode {
+    /// 只授权本次兑换所需的数量
+    #[default]
+    Exact,
+    /// 授权 uint256 最大值，之后的兑换不再需要授权
+    Unlimited,
+}
+
+impl ApprovalMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Unlimited => "unlimited",
+        }
+    }
+}
+
+impl FromStr for ApprovalMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "unlimited" => Ok(Self::Unlimited),
+            _ => bail!("未知的授权方式: {}（可选 exact 或 unlimited）", value),
+        }
+    }
+}
+
+/// 单条链的设置，未填写的合约地址使用内置默认值
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSettings {
+    pub chain_id: u64,
+    #[serde(default)]
+    pub rpc_url: Option<Url>,
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<Url>,
+    #[serde(default)]
+    pub routers: RouterSettings,
+    /// 额外的代币符号映射，如 `PEPE = "0x..."`
+    #[serde(default)]
+    pub tokens: BTreeMap<String, Address>,
+    /// 额外参与报价的 Curve 池子
+    #[serde(default)]
+    pub curve_pools: Vec<CurvePoolSettings>,
+    /// 额外参与报价的 Balancer V2 池子
+    #[serde(default)]
+    pub balancer_pools: Vec<BalancerPoolSettings>,
+}
+
+/// Curve 池子：`coins` 按池子中的序号排列
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CurvePoolSettings {
+    pub address: Address,
+    pub coins: Vec<Address>,
+}
+
+/// Balancer V2 池子：Vault 中的 pool ID 及其包含的代币
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalancerPoolSettings {
+    pub id: H256,
+    pub tokens: Vec<Address>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RouterSettings {
+    pub uniswap_v2_router: Option<Address>,
+    pub uniswap_v2_factory: Option<Address>,
+    pub uniswap_v3_router: Option<Address>,
+    pub uniswap_v3_quoter: Option<Address>,
+    pub uniswap_v3_quoter_v2: Option<Address>,
+    pub uniswap_v3_factory: Option<Address>,
+    pub swap_router02: Option<Address>,
+    pub universal_router: Option<Address>,
+    pub uniswap_v4_pool_manager: Option<Address>,
+    pub uniswap_v4_quoter: Option<Address>,
+    pub uniswap_v4_state_view: Option<Address>,
+    /// 额外参与 V4 报价的 hooks 合约（无 hooks 的池子总是参与）
+    pub uniswap_v4_hooks: Vec<Address>,
+    pub sushiswap_router: Option<Address>,
+    pub sushiswap_factory: Option<Address>,
+    pub balancer_vault: Option<Address>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    #[default]
+    Stdio,
+    Http,
+}
+
+impl FromStr for TransportKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| anyhow!("未知的传输方式: {}（可选 stdio 或 http）", value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| anyhow!("未知的日志级别: {}（可选 trace/debug/info/warn/error）", value))
+    }
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => Level::TRACE,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Error => Level::ERROR,
+        }
+    }
+}
+
+impl Config {
+    /// 按 命令行 > 环境变量 > 配置文件 > 默认值 的优先级加载配置
+    pub fn load(cli: &Cli) -> Result<Self> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env(|key| std::env::var(key).ok())?;
+        config.apply_cli(cli)?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("读取配置文件 {} 失败", path.display()))?;
+        Self::from_toml(&content).with_context(|| format!("配置文件 {} 无效", path.display()))
+    }
+
+    /// 解析 TOML 配置，未知字段和非法取值会带上行列号报错
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content).map_err(|e| anyhow!("{}", e))?;
+        ensure_unique_chain_ids(&config.chains)?;
+        if config.quorum.threshold == 0 || config.quorum.threshold > config.quorum.providers {
+            bail!(
+                "[quorum] threshold 应在 1 到 providers ({}) 之间，当前为 {}",
+                config.quorum.providers,
+                config.quorum.threshold
+            );
+        }
+        Ok(config)
+    }
+
+    fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(rpc_url) = lookup("ETHEREUM_RPC_URL") {
+            self.ethereum.rpc_url = Some(parse_env("ETHEREUM_RPC_URL", &rpc_url)?);
+        }
+        // 空的 PRIVATE_KEY 视为未设置（只读模式）
+        if let Some(private_key) = lookup("PRIVATE_KEY").filter(|key| !key.trim().is_empty()) {
+            self.ethereum.private_key = Some(private_key);
+        }
+        if let Some(transport) = lookup("MCP_TRANSPORT") {
+            self.mcp.transport = parse_env("MCP_TRANSPORT", &transport)?;
+        }
+        if let Some(host) = lookup("MCP_HOST") {
+            self.mcp.host = host;
+        }
+        if let Some(port) = lookup("MCP_PORT") {
+            self.mcp.port = parse_env("MCP_PORT", &port)?;
+        }
+        if let Some(level) = lookup("LOG_LEVEL") {
+            self.logging.level = parse_env("LOG_LEVEL", &level)?;
+        }
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) -> Result<()> {
+        if let Some(rpc_url) = &cli.rpc_url {
+            self.ethereum.rpc_url =
+                Some(Url::parse(rpc_url).with_context(|| format!("无效的 --rpc-url: {}", rpc_url))?);
+        }
+        if let Some(transport) = cli.transport {
+            self.mcp.transport = transport;
+        }
+        if let Some(host) = &cli.host {
+            self.mcp.host = host.clone();
+        }
+        if let Some(port) = cli.port {
+            self.mcp.port = port;
+        }
+        if let Some(level) = cli.log_level {
+            self.logging.level = level;
+        }
+        Ok(())
+    }
+}
+
+fn parse_env<T>(key: &str, value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| anyhow!("环境变量 {}={} 无效: {}", key, value, e))
+}
+
+fn ensure_unique_chain_ids(chains: &BTreeMap<String, ChainSettings>) -> Result<()> {
+    let mut seen: BTreeMap<u64, &str> = BTreeMap::new();
+    for (name, chain) in chains {
+        if let Some(other) = seen.insert(chain.chain_id, name) {
+            bail!("链 {} 与 {} 使用了相同的 chain_id {}", name, other, chain.chain_id);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_example_config() {
+        let config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        assert_eq!(config.mcp.port, 8080);
+        assert!(!config.execution.enabled);
+        assert_eq!(config.swap.approval, ApprovalMode::Exact);
+        assert_eq!(config.logging.level, LogLevel::Info);
+    }
+
+    #[test]
+    fn unknown_key_reports_line() {
+        let err = Config::from_toml("[mcp]\nport = 8080\nprot = 1\n").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("line 3"), "{message}");
+        assert!(message.contains("prot"), "{message}");
+    }
+
+    #[test]
+    fn invalid_value_reports_line() {
+        let err = Config::from_toml("[logging]\nlevel = \"loud\"\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn parses_signer_section() {
+        let config = Config::from_toml(
+            "[signer]\ntype = \"keystore\"\npath = \"/tmp/key.json\"\n",
+        )
+        .unwrap();
+        assert!(matches!(config.signer, Some(SignerConfig::Keystore { .. })));
+
+        let config = Config::from_toml("[signer]\ntype = \"mnemonic\"\n").unwrap();
+        match config.signer {
+            Some(SignerConfig::Mnemonic {
+                derivation_path, ..
+            }) => assert_eq!(derivation_path, "m/44'/60'/0'/0/0"),
+            other => panic!("unexpected signer: {other:?}"),
+        }
+
+        assert!(Config::from_toml("[signer]\ntype = \"ledger\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_approval_mode() {
+        let config =
+            Config::from_toml("[swap]\napproval = \"unlimited\"\nrouter = \"universal\"\ngas_speed = \"fast\"\n").unwrap();
+        assert_eq!(config.swap.approval, ApprovalMode::Unlimited);
+        assert_eq!(config.swap.router, SwapRouterKind::Universal);
+        assert_eq!(config.swap.gas_speed, GasSpeed::Fast);
+        assert!(Config::from_toml("[swap]\napproval = \"infinite\"\n").is_err());
+    }
+
+    #[test]
+    fn cli_overrides_env_overrides_file() {
+        let mut config = Config::from_toml("[mcp]\nhost = \"0.0.0.0\"\nport = 1000\n").unwrap();
+        config
+            .apply_env(|key| match key {
+                "MCP_PORT" => Some("2000".to_string()),
+                "MCP_TRANSPORT" => Some("http".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        let cli = Cli {
+            port: Some(3000),
+            ..Default::default()
+        };
+        config.apply_cli(&cli).unwrap();
+
+        assert_eq!(config.mcp.host, "0.0.0.0");
+        assert_eq!(config.mcp.transport, TransportKind::Http);
+        assert_eq!(config.mcp.port, 3000);
+    }
+
+    #[test]
+    fn invalid_env_value_is_rejected() {
+        let mut config = Config::default();
+        let err = config
+            .apply_env(|key| (key == "MCP_PORT").then(|| "eighty".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("MCP_PORT"), "{err}");
+    }
+}
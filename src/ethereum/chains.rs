@@ -0,0 +1,542 @@
+use crate::config::ChainSettings;
+use anyhow::{bail, Result};
+use ethers::types::{Address, H256};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+
+/// Permit2 在所有链上的地址相同
+pub const PERMIT2: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+/// 多跳路由依次尝试的中间代币符号（包装原生代币总是第一个）
+const INTERMEDIATE_SYMBOLS: &[&str] = &["USDC", "USDT", "DAI", "WBTC"];
+
+/// Uniswap V2（及其分叉）部署地址
+#[derive(Debug, Clone, Copy)]
+pub struct UniswapV2Deployment {
+    pub router: Address,
+    pub factory: Address,
+}
+
+/// Uniswap V3 部署地址（SwapRouter + Quoter）
+#[derive(Debug, Clone, Copy)]
+pub struct UniswapV3Deployment {
+    pub router: Address,
+    pub quoter: Address,
+    /// QuoterV2，支持按编码路径报价多跳路由
+    pub quoter_v2: Address,
+    /// UniswapV3Factory，用于定位池子并在本地模拟报价；未配置时只用 QuoterV2
+    pub factory: Option<Address>,
+}
+
+/// Uniswap V4 部署地址：所有池子共用的 PoolManager、V4Quoter 和读取池子状态的 StateView
+#[derive(Debug, Clone)]
+pub struct UniswapV4Deployment {
+    pub pool_manager: Address,
+    pub quoter: Address,
+    pub state_view: Address,
+    /// 除无 hooks 池子外额外尝试的 hooks 合约
+    pub hooks: Vec<Address>,
+}
+
+/// Curve 池子，`coins` 按池子中的序号排列
+#[derive(Debug, Clone)]
+pub struct CurvePool {
+    pub address: Address,
+    pub coins: Vec<Address>,
+}
+
+/// Balancer V2 池子
+#[derive(Debug, Clone)]
+pub struct BalancerPool {
+    pub id: H256,
+    pub tokens: Vec<Address>,
+}
+
+/// Balancer V2 部署：所有池子共用的 Vault 和参与报价的池子
+#[derive(Debug, Clone)]
+pub struct BalancerDeployment {
+    pub vault: Address,
+    pub pools: Vec<BalancerPool>,
+}
+
+/// 单条链的协议地址和代币注册表
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct ChainInfo {
+    pub chain_id: u64,
+    pub name: String,
+    /// 原生代币符号，如 ETH、POL
+    pub native_symbol: String,
+    /// 包装后的原生代币（WETH / WPOL）
+    pub wrapped_native: Address,
+    /// 计价稳定币（USD 报价使用）
+    pub stable_token: Address,
+    pub stable_decimals: u8,
+    pub uniswap_v2: Option<UniswapV2Deployment>,
+    pub uniswap_v3: Option<UniswapV3Deployment>,
+    /// V4 池子只能经 Universal Router 执行，未配置 Universal Router 时不参与报价
+    pub uniswap_v4: Option<UniswapV4Deployment>,
+    /// SushiSwap V2，接口与 Uniswap V2 相同
+    pub sushiswap: Option<UniswapV2Deployment>,
+    /// 只支持 `int128` 序号的经典 StableSwap 池子（`get_dy` / `exchange`）
+    pub curve_pools: Vec<CurvePool>,
+    pub balancer: Option<BalancerDeployment>,
+    /// SwapRouter02，可在一次 multicall 中同时执行 V2 和 V3 兑换（拆单路由使用）
+    pub swap_router02: Option<Address>,
+    /// Universal Router，配合 Permit2 使用
+    pub universal_router: Option<Address>,
+    /// 已知代币，键为大写符号
+    pub tokens: HashMap<String, Address>,
+}
+
+#[allow(dead_code)]
+impl ChainInfo {
+    pub fn uniswap_v2(&self) -> Result<UniswapV2Deployment> {
+        match self.uniswap_v2 {
+            Some(deployment) => Ok(deployment),
+            None => bail!("链 {} 未配置 Uniswap V2 部署", self.name),
+        }
+    }
+
+    pub fn uniswap_v3(&self) -> Result<UniswapV3Deployment> {
+        match self.uniswap_v3 {
+            Some(deployment) => Ok(deployment),
+            None => bail!("链 {} 未配置 Uniswap V3 部署", self.name),
+        }
+    }
+
+    pub fn sushiswap(&self) -> Result<UniswapV2Deployment> {
+        match self.sushiswap {
+            Some(deployment) => Ok(deployment),
+            None => bail!("链 {} 未配置 SushiSwap 部署", self.name),
+        }
+    }
+
+    pub fn balancer(&self) -> Result<&BalancerDeployment> {
+        match &self.balancer {
+            Some(deployment) => Ok(deployment),
+            None => bail!("链 {} 未配置 Balancer V2 部署", self.name),
+        }
+    }
+
+    pub fn uniswap_v4(&self) -> Result<&UniswapV4Deployment> {
+        match &self.uniswap_v4 {
+            Some(deployment) => Ok(deployment),
+            None => bail!("链 {} 未配置 Uniswap V4 部署", self.name),
+        }
+    }
+
+    pub fn resolve_token(&self, symbol: &str) -> Option<Address> {
+        self.tokens.get(&symbol.to_ascii_uppercase()).copied()
+    }
+
+    /// 多跳路由的候选中间代币：包装原生代币和主流稳定币 / WBTC
+    pub fn intermediate_tokens(&self) -> Vec<Address> {
+        let mut tokens = vec![self.wrapped_native];
+        for symbol in INTERMEDIATE_SYMBOLS {
+            if let Some(token) = self.resolve_token(symbol) {
+                if !tokens.contains(&token) {
+                    tokens.push(token);
+                }
+            }
+        }
+        tokens
+    }
+
+    /// 用配置文件中的设置覆盖内置地址
+    fn apply_settings(&mut self, settings: &ChainSettings) {
+        let routers = &settings.routers;
+        match (&mut self.uniswap_v2, routers.uniswap_v2_router, routers.uniswap_v2_factory) {
+            (Some(deployment), router, factory) => {
+                deployment.router = router.unwrap_or(deployment.router);
+                deployment.factory = factory.unwrap_or(deployment.factory);
+            }
+            (None, Some(router), Some(factory)) => {
+                self.uniswap_v2 = Some(UniswapV2Deployment { router, factory });
+            }
+            _ => {}
+        }
+        match (
+            &mut self.uniswap_v3,
+            routers.uniswap_v3_router,
+            routers.uniswap_v3_quoter,
+            routers.uniswap_v3_quoter_v2,
+        ) {
+            (Some(deployment), router, quoter, quoter_v2) => {
+                deployment.router = router.unwrap_or(deployment.router);
+                deployment.quoter = quoter.unwrap_or(deployment.quoter);
+                deployment.quoter_v2 = quoter_v2.unwrap_or(deployment.quoter_v2);
+                deployment.factory = routers.uniswap_v3_factory.or(deployment.factory);
+            }
+            (None, Some(router), Some(quoter), Some(quoter_v2)) => {
+                self.uniswap_v3 = Some(UniswapV3Deployment {
+                    router,
+                    quoter,
+                    quoter_v2,
+                    factory: routers.uniswap_v3_factory,
+                });
+            }
+            _ => {}
+        }
+        match (
+            &mut self.uniswap_v4,
+            routers.uniswap_v4_pool_manager,
+            routers.uniswap_v4_quoter,
+            routers.uniswap_v4_state_view,
+        ) {
+            (Some(deployment), pool_manager, quoter, state_view) => {
+                deployment.pool_manager = pool_manager.unwrap_or(deployment.pool_manager);
+                deployment.quoter = quoter.unwrap_or(deployment.quoter);
+                deployment.state_view = state_view.unwrap_or(deployment.state_view);
+                deployment.hooks.extend(routers.uniswap_v4_hooks.iter().copied());
+            }
+            (None, Some(pool_manager), Some(quoter), Some(state_view)) => {
+                self.uniswap_v4 = Some(UniswapV4Deployment {
+                    pool_manager,
+                    quoter,
+                    state_view,
+                    hooks: routers.uniswap_v4_hooks.clone(),
+                });
+            }
+            _ => {}
+        }
+        match (&mut self.sushiswap, routers.sushiswap_router, routers.sushiswap_factory) {
+            (Some(deployment), router, factory) => {
+                deployment.router = router.unwrap_or(deployment.router);
+                deployment.factory = factory.unwrap_or(deployment.factory);
+            }
+            (None, Some(router), Some(factory)) => {
+                self.sushiswap = Some(UniswapV2Deployment { router, factory });
+            }
+            _ => {}
+        }
+        self.curve_pools
+            .extend(settings.curve_pools.iter().map(|pool| CurvePool {
+                address: pool.address,
+                coins: pool.coins.clone(),
+            }));
+        let balancer_pools = settings.balancer_pools.iter().map(|pool| BalancerPool {
+            id: pool.id,
+            tokens: pool.tokens.clone(),
+        });
+        match (&mut self.balancer, routers.balancer_vault) {
+            (Some(deployment), vault) => {
+                deployment.vault = vault.unwrap_or(deployment.vault);
+                deployment.pools.extend(balancer_pools);
+            }
+            (None, Some(vault)) => {
+                self.balancer = Some(BalancerDeployment {
+                    vault,
+                    pools: balancer_pools.collect(),
+                });
+            }
+            _ => {}
+        }
+        self.swap_router02 = routers.swap_router02.or(self.swap_router02);
+        self.universal_router = routers.universal_router.or(self.universal_router);
+        for (symbol, address) in &settings.tokens {
+            self.tokens.insert(symbol.to_ascii_uppercase(), *address);
+        }
+    }
+}
+
+/// 按 chain_id 查找内置链信息，并应用配置文件中同一 chain_id 的覆盖设置
+pub fn resolve_chain(
+    chain_id: u64,
+    settings: &BTreeMap<String, ChainSettings>,
+) -> Result<ChainInfo> {
+    let Some(builtin) = BUILTIN_CHAINS.iter().find(|chain| chain.chain_id == chain_id) else {
+        let supported: Vec<String> = BUILTIN_CHAINS
+            .iter()
+            .map(|chain| format!("{} ({})", chain.name, chain.chain_id))
+            .collect();
+        bail!(
+            "不支持的链 ID {}，当前支持: {}",
+            chain_id,
+            supported.join(", ")
+        );
+    };
+
+    let mut chain = builtin.clone();
+    if let Some((name, overrides)) = settings
+        .iter()
+        .find(|(_, chain_settings)| chain_settings.chain_id == chain_id)
+    {
+        chain.name = name.clone();
+        chain.apply_settings(overrides);
+    }
+    Ok(chain)
+}
+
+fn address(value: &str) -> Address {
+    Address::from_str(value).expect("invalid builtin address")
+}
+
+fn tokens(entries: &[(&str, &str)]) -> HashMap<String, Address> {
+    entries
+        .iter()
+        .map(|(symbol, value)| (symbol.to_string(), address(value)))
+        .collect()
+}
+
+static BUILTIN_CHAINS: Lazy<Vec<ChainInfo>> = Lazy::new(|| {
+    // 主网部署的 V3 SwapRouter / Quoter / QuoterV2 / Factory 在 Arbitrum、Optimism、Polygon 上地址相同
+    let legacy_v3 = UniswapV3Deployment {
+        router: address("0xE592427A0AEce92De3Edee1F18E0157C05861564"),
+        quoter: address("0xb27308f9F90D607463bb33eA1BeBb41C27CE5AB6"),
+        quoter_v2: address("0x61fFE014bA17989E743c5F6cB21bF9697530B21e"),
+        factory: Some(address("0x1F98431c8aD98523631AE4a59f267346ea31F984")),
+    };
+
+    let swap_router02 = Some(address("0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45"));
+
+    // SushiSwap V2 在 Arbitrum 和 Polygon 上地址相同
+    let sushiswap_l2 = Some(UniswapV2Deployment {
+        router: address("0x1b02dA8Cb0d097eB8D57A175b88c7D8b47997506"),
+        factory: address("0xc35DADB65012eC5796536bD9864eD8773aBc74C4"),
+    });
+
+    // Balancer V2 Vault 在各链上地址相同，内置池子只有主网的 80BAL-20WETH，其他池子在配置中添加
+    let balancer = |pools: Vec<BalancerPool>| {
+        Some(BalancerDeployment {
+            vault: address("0xBA12222222228d8Ba445958a75a0704d566BF2C8"),
+            pools,
+        })
+    };
+
+    let uniswap_v4 = |pool_manager: &str, quoter: &str, state_view: &str| {
+        Some(UniswapV4Deployment {
+            pool_manager: address(pool_manager),
+            quoter: address(quoter),
+            state_view: address(state_view),
+            hooks: Vec::new(),
+        })
+    };
+
+    vec![
+        ChainInfo {
+            chain_id: 1,
+            name: "mainnet".to_string(),
+            native_symbol: "ETH".to_string(),
+            wrapped_native: address("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+            stable_token: address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+            stable_decimals: 6,
+            uniswap_v2: Some(UniswapV2Deployment {
+                router: address("0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"),
+                factory: address("0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+            }),
+            uniswap_v3: Some(legacy_v3),
+            uniswap_v4: uniswap_v4(
+                "0x000000000004444c5dc75cB358380D2e3dE08A90",
+                "0x52F0E24D1c21C8A0cB1e5a5dD6198556BD9E1203",
+                "0x7fFE42C4a5DEeA5b0feC41C94C136Cf115597227",
+            ),
+            sushiswap: Some(UniswapV2Deployment {
+                router: address("0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F"),
+                factory: address("0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"),
+            }),
+            // 3pool：DAI / USDC / USDT
+            curve_pools: vec![CurvePool {
+                address: address("0xbEbc44782C7dB0a1A60Cb6fe97d0b483032FF1C7"),
+                coins: vec![
+                    address("0x6B175474E89094C44Da98b954EedeAC495271d0F"),
+                    address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+                    address("0xdAC17F958D2ee523a2206206994597C13D831ec7"),
+                ],
+            }],
+            balancer: balancer(vec![BalancerPool {
+                id: H256::from_str(
+                    "0x5c6ee304399dbdb9c8ef030ab642b10820db8f56000200000000000000000014",
+                )
+                .expect("invalid builtin pool id"),
+                tokens: vec![
+                    address("0xba100000625a3754423978a60c9317c58a424e3D"),
+                    address("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                ],
+            }]),
+            swap_router02,
+            universal_router: Some(address("0x66a9893cC07D91D95644AEDD05D03f95e1dBA8Af")),
+            tokens: tokens(&[
+                ("USDC", "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+                ("USDT", "0xdAC17F958D2ee523a2206206994597C13D831ec7"),
+                ("DAI", "0x6B175474E89094C44Da98b954EedeAC495271d0F"),
+                ("WETH", "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                ("WBTC", "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
+            ]),
+        },
+        ChainInfo {
+            chain_id: 10,
+            name: "optimism".to_string(),
+            native_symbol: "ETH".to_string(),
+            wrapped_native: address("0x4200000000000000000000000000000000000006"),
+            stable_token: address("0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+            stable_decimals: 6,
+            uniswap_v2: Some(UniswapV2Deployment {
+                router: address("0x4A7b5Da61326A6379179b40d00F57E5bbDC962c2"),
+                factory: address("0x0c3c1c532F1e39EdF36BE9Fe0bE1410313E074Bf"),
+            }),
+            uniswap_v3: Some(legacy_v3),
+            uniswap_v4: uniswap_v4(
+                "0x9a13F98Cb987694C9F086b1F5eB990EeA8264Ec3",
+                "0x1f3131A13296FB91C90870043742C3CDBFF1A8d7",
+                "0xc18a3169788F4F75A170290584ECA6395C75Ecdb",
+            ),
+            sushiswap: None,
+            curve_pools: Vec::new(),
+            balancer: balancer(Vec::new()),
+            swap_router02,
+            universal_router: Some(address("0x851116D9223fabED8E56C0E6b8Ad0c31d98B3507")),
+            tokens: tokens(&[
+                ("USDC", "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+                ("USDT", "0x94b008aA00579c1307B0EF2c499aD98a8ce58e58"),
+                ("DAI", "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+                ("WETH", "0x4200000000000000000000000000000000000006"),
+                ("WBTC", "0x68f180fcCe6836688e9084f035309E29Bf0A2095"),
+            ]),
+        },
+        ChainInfo {
+            chain_id: 137,
+            name: "polygon".to_string(),
+            native_symbol: "POL".to_string(),
+            wrapped_native: address("0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"),
+            stable_token: address("0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+            stable_decimals: 6,
+            uniswap_v2: Some(UniswapV2Deployment {
+                router: address("0xedf6066a2b290C185783862C7F4776A2C8077AD1"),
+                factory: address("0x9e5A52f57b3038F1B8EeE45F28b3C1967e22799C"),
+            }),
+            uniswap_v3: Some(legacy_v3),
+            // V4 和 Universal Router 未内置，可在 [chains.<name>.routers] 中配置
+            uniswap_v4: None,
+            sushiswap: sushiswap_l2,
+            curve_pools: Vec::new(),
+            balancer: balancer(Vec::new()),
+            swap_router02,
+            universal_router: None,
+            tokens: tokens(&[
+                ("USDC", "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+                ("USDT", "0xc2132D05D31c914a87C6611C10748AEb04B58e8F"),
+                ("DAI", "0x8f3Cf7ad23Cd3CaDbD9735AFf958023239c6A063"),
+                ("WETH", "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619"),
+                ("WBTC", "0x1BFD67037B42Cf73acF2047067bd4F2C47D9BfD6"),
+                ("WPOL", "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"),
+            ]),
+        },
+        ChainInfo {
+            chain_id: 8453,
+            name: "base".to_string(),
+            native_symbol: "ETH".to_string(),
+            wrapped_native: address("0x4200000000000000000000000000000000000006"),
+            stable_token: address("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+            stable_decimals: 6,
+            uniswap_v2: Some(UniswapV2Deployment {
+                router: address("0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24"),
+                factory: address("0x8909Dc15e40173Ff4699343b6eB8132c65e18eC6"),
+            }),
+            // Base 上只有 SwapRouter02 / QuoterV2，接口与旧版 SwapRouter / Quoter 不兼容
+            uniswap_v3: None,
+            uniswap_v4: uniswap_v4(
+                "0x498581fF718922c3f8e6A244956aF099B2652b2b",
+                "0x0d5e0F971ED27FBfF6c2837bf31316121532048D",
+                "0xA3c0c9b65baD0b08107Aa264b0f3dB444b867A71",
+            ),
+            sushiswap: None,
+            curve_pools: Vec::new(),
+            balancer: balancer(Vec::new()),
+            swap_router02: Some(address("0x2626664c2603336E57B271c5C0b26F421741e481")),
+            universal_router: Some(address("0x6fF5693b99212Da76ad316178A184AB56D299b43")),
+            tokens: tokens(&[
+                ("USDC", "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+                ("DAI", "0x50c5725949A6F0c72E6C4a641F24049A917DB0Cb"),
+                ("WETH", "0x4200000000000000000000000000000000000006"),
+            ]),
+        },
+        ChainInfo {
+            chain_id: 42161,
+            name: "arbitrum".to_string(),
+            native_symbol: "ETH".to_string(),
+            wrapped_native: address("0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+            stable_token: address("0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+            stable_decimals: 6,
+            uniswap_v2: Some(UniswapV2Deployment {
+                router: address("0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24"),
+                factory: address("0xf1D7CC64Fb4452F05c498126312eBE29f30Fbcf9"),
+            }),
+            uniswap_v3: Some(legacy_v3),
+            uniswap_v4: uniswap_v4(
+                "0x360E68faCcca8cA495c1B759Fd9EEe466db9FB32",
+                "0x3972C00f7ed4885e145823eb7C655375d275A1C5",
+                "0x76Fd297e2D437cd7f76d50F01AfE6160f86e9990",
+            ),
+            sushiswap: sushiswap_l2,
+            curve_pools: Vec::new(),
+            balancer: balancer(Vec::new()),
+            swap_router02,
+            universal_router: Some(address("0xA51afAFe0263b40EdaEf0Df8781eA9aa03E381a3")),
+            tokens: tokens(&[
+                ("USDC", "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+                ("USDT", "0xFd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9"),
+                ("DAI", "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+                ("WETH", "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+                ("WBTC", "0x2f2a2543B76A4166549F7aaB2e75Bef0aefC5B0f"),
+            ]),
+        },
+    ]
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn unknown_chain_is_rejected() {
+        let err = resolve_chain(56, &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains("56"), "{err}");
+    }
+
+    #[test]
+    fn settings_override_builtin_addresses() {
+        let config = Config::from_toml(
+            r#"
+            [chains.arb]
+            chain_id = 42161
+
+            [chains.arb.routers]
+            uniswap_v2_router = "0x0000000000000000000000000000000000000001"
+            uniswap_v4_hooks = ["0x0000000000000000000000000000000000000002"]
+
+            [chains.arb.tokens]
+            arb = "0x912CE59144191C1204E64559FE8253a0e49E6548"
+
+            [[chains.arb.curve_pools]]
+            address = "0x0000000000000000000000000000000000000003"
+            coins = [
+                "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+                "0xFd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9",
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let chain = resolve_chain(42161, &config.chains).unwrap();
+        assert_eq!(chain.name, "arb");
+        assert_eq!(chain.uniswap_v2().unwrap().router, Address::from_low_u64_be(1));
+        assert_eq!(chain.uniswap_v4().unwrap().hooks, vec![Address::from_low_u64_be(2)]);
+        assert_eq!(chain.curve_pools[0].coins.len(), 2);
+        assert!(chain.resolve_token("ARB").is_some());
+        assert!(chain.resolve_token("usdc").is_some());
+    }
+
+    #[test]
+    fn intermediate_tokens_start_with_wrapped_native() {
+        let chain = resolve_chain(8453, &BTreeMap::new()).unwrap();
+        let tokens = chain.intermediate_tokens();
+        assert_eq!(tokens[0], chain.wrapped_native);
+        // Base 没有内置 USDT / WBTC
+        assert_eq!(tokens.len(), 3);
+    }
+}
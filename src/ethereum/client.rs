@@ -1,23 +1,41 @@
+use crate::config::{ChainSettings, GasSpeed};
+use crate::ethereum::chains::{resolve_chain, ChainInfo, UniswapV2Deployment, PERMIT2};
+use crate::ethereum::dex::adapters_for;
+use crate::ethereum::gas::{
+    apply_fees, suggest_fees, FeeSuggestion, FEE_HISTORY_BLOCKS, FEE_HISTORY_PERCENTILES,
+};
+use crate::ethereum::nonce::NonceManager;
+use crate::ethereum::rpc::{PooledProvider, QuorumLog, RpcPool, RpcProvider};
+use crate::ethereum::signer::TransactionSigner;
 use crate::ethereum::types::{Balance, TokenInfo, TokenPrice};
+use crate::ethereum::universal_router::{
+    encode_swap_commands, permit_single_typed_data, PermitSingle,
+};
+use crate::ethereum::v3_math::{sqrt_price_to_price, MissingState, V3Pool};
 use anyhow::{anyhow, bail, Context, Result};
 use ethers::{
-    contract::abigen,
+    abi::AbiEncode,
+    contract::{abigen, parse_log},
     middleware::Middleware,
-    providers::{Http, Provider},
-    signers::{LocalWallet, Signer},
-    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+    providers::{PendingTransaction, Provider},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes,
+        Eip1559TransactionRequest, Transaction, TransactionReceipt, H256, U256,
+    },
+    utils::keccak256,
 };
-use once_cell::sync::Lazy;
 use rust_decimal::{prelude::FromPrimitive, Decimal};
 use rust_decimal_macros::dec;
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
     str::FromStr,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use futures::future::join_all;
 use tokio::try_join;
-use tracing::info;
+use tracing::{info, warn};
 
 abigen!(
     IERC20,
@@ -25,7 +43,10 @@ abigen!(
         {"type": "function", "name": "balanceOf", "inputs": [{"name": "account", "type": "address"}], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
         {"type": "function", "name": "decimals", "inputs": [], "outputs": [{"name": "", "type": "uint8"}], "stateMutability": "view"},
         {"type": "function", "name": "symbol", "inputs": [], "outputs": [{"name": "", "type": "string"}], "stateMutability": "view"},
-        {"type": "function", "name": "name", "inputs": [], "outputs": [{"name": "", "type": "string"}], "stateMutability": "view"}
+        {"type": "function", "name": "name", "inputs": [], "outputs": [{"name": "", "type": "string"}], "stateMutability": "view"},
+        {"type": "function", "name": "allowance", "inputs": [{"name": "owner", "type": "address"}, {"name": "spender", "type": "address"}], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "approve", "inputs": [{"name": "spender", "type": "address"}, {"name": "amount", "type": "uint256"}], "outputs": [{"name": "", "type": "bool"}], "stateMutability": "nonpayable"},
+        {"type": "event", "name": "Transfer", "inputs": [{"name": "from", "type": "address", "indexed": true}, {"name": "to", "type": "address", "indexed": true}, {"name": "value", "type": "uint256", "indexed": false}], "anonymous": false}
     ]"#
 );
 
@@ -33,7 +54,13 @@ abigen!(
     UniswapV2Router,
     r#"[
         {"type": "function", "name": "getAmountsOut", "inputs": [{"name": "amountIn", "type": "uint256"}, {"name": "path", "type": "address[]"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "view"},
-        {"type": "function", "name": "swapExactTokensForTokens", "inputs": [{"name": "amountIn", "type": "uint256"}, {"name": "amountOutMin", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "nonpayable"}
+        {"type": "function", "name": "swapExactTokensForTokens", "inputs": [{"name": "amountIn", "type": "uint256"}, {"name": "amountOutMin", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "getAmountsIn", "inputs": [{"name": "amountOut", "type": "uint256"}, {"name": "path", "type": "address[]"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "view"},
+        {"type": "function", "name": "swapTokensForExactTokens", "inputs": [{"name": "amountOut", "type": "uint256"}, {"name": "amountInMax", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "swapETHForExactTokens", "inputs": [{"name": "amountOut", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "payable"},
+        {"type": "function", "name": "swapTokensForExactETH", "inputs": [{"name": "amountOut", "type": "uint256"}, {"name": "amountInMax", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "swapExactETHForTokens", "inputs": [{"name": "amountOutMin", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "payable"},
+        {"type": "function", "name": "swapExactTokensForETH", "inputs": [{"name": "amountIn", "type": "uint256"}, {"name": "amountOutMin", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amounts", "type": "uint256[]"}], "stateMutability": "nonpayable"}
     ]"#
 );
 
@@ -44,70 +71,145 @@ abigen!(
     ]"#
 );
 
+abigen!(
+    UniswapV2Pair,
+    r#"[
+        {"type": "function", "name": "getReserves", "inputs": [], "outputs": [{"name": "reserve0", "type": "uint112"}, {"name": "reserve1", "type": "uint112"}, {"name": "blockTimestampLast", "type": "uint32"}], "stateMutability": "view"}
+    ]"#
+);
+
 abigen!(
     UniswapV3Quoter,
     r#"[
-        {"type": "function", "name": "quoteExactInputSingle", "inputs": [{"name": "tokenIn", "type": "address"}, {"name": "tokenOut", "type": "address"}, {"name": "fee", "type": "uint24"}, {"name": "amountIn", "type": "uint256"}, {"name": "sqrtPriceLimitX96", "type": "uint160"}], "outputs": [{"name": "amountOut", "type": "uint256"}], "stateMutability": "nonpayable"}
+        {"type": "function", "name": "quoteExactInputSingle", "inputs": [{"name": "tokenIn", "type": "address"}, {"name": "tokenOut", "type": "address"}, {"name": "fee", "type": "uint24"}, {"name": "amountIn", "type": "uint256"}, {"name": "sqrtPriceLimitX96", "type": "uint160"}], "outputs": [{"name": "amountOut", "type": "uint256"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "quoteExactOutputSingle", "inputs": [{"name": "tokenIn", "type": "address"}, {"name": "tokenOut", "type": "address"}, {"name": "fee", "type": "uint24"}, {"name": "amountOut", "type": "uint256"}, {"name": "sqrtPriceLimitX96", "type": "uint160"}], "outputs": [{"name": "amountIn", "type": "uint256"}], "stateMutability": "nonpayable"}
+    ]"#
+);
+
+abigen!(
+    UniswapV3QuoterV2,
+    r#"[
+        {"type": "function", "name": "quoteExactInput", "inputs": [{"name": "path", "type": "bytes"}, {"name": "amountIn", "type": "uint256"}], "outputs": [{"name": "amountOut", "type": "uint256"}, {"name": "sqrtPriceX96AfterList", "type": "uint160[]"}, {"name": "initializedTicksCrossedList", "type": "uint32[]"}, {"name": "gasEstimate", "type": "uint256"}], "stateMutability": "nonpayable"}
+    ]"#
+);
+
+abigen!(
+    UniswapV3Factory,
+    r#"[
+        {"type": "function", "name": "getPool", "inputs": [{"name": "tokenA", "type": "address"}, {"name": "tokenB", "type": "address"}, {"name": "fee", "type": "uint24"}], "outputs": [{"name": "", "type": "address"}], "stateMutability": "view"}
+    ]"#
+);
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        {"type": "function", "name": "slot0", "inputs": [], "outputs": [{"name": "sqrtPriceX96", "type": "uint160"}, {"name": "tick", "type": "int24"}, {"name": "observationIndex", "type": "uint16"}, {"name": "observationCardinality", "type": "uint16"}, {"name": "observationCardinalityNext", "type": "uint16"}, {"name": "feeProtocol", "type": "uint8"}, {"name": "unlocked", "type": "bool"}], "stateMutability": "view"},
+        {"type": "function", "name": "liquidity", "inputs": [], "outputs": [{"name": "", "type": "uint128"}], "stateMutability": "view"},
+        {"type": "function", "name": "tickSpacing", "inputs": [], "outputs": [{"name": "", "type": "int24"}], "stateMutability": "view"},
+        {"type": "function", "name": "tickBitmap", "inputs": [{"name": "wordPosition", "type": "int16"}], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "ticks", "inputs": [{"name": "tick", "type": "int24"}], "outputs": [{"name": "liquidityGross", "type": "uint128"}, {"name": "liquidityNet", "type": "int128"}, {"name": "feeGrowthOutside0X128", "type": "uint256"}, {"name": "feeGrowthOutside1X128", "type": "uint256"}, {"name": "tickCumulativeOutside", "type": "int56"}, {"name": "secondsPerLiquidityOutsideX128", "type": "uint160"}, {"name": "secondsOutside", "type": "uint32"}, {"name": "initialized", "type": "bool"}], "stateMutability": "view"}
     ]"#
 );
 
 abigen!(
     UniswapV3Router,
     r#"[
-        {"type": "function", "name": "exactInputSingle", "inputs": [{"name": "tokenIn", "type": "address"}, {"name": "tokenOut", "type": "address"}, {"name": "fee", "type": "uint24"}, {"name": "recipient", "type": "address"}, {"name": "deadline", "type": "uint256"}, {"name": "amountIn", "type": "uint256"}, {"name": "amountOutMinimum", "type": "uint256"}, {"name": "sqrtPriceLimitX96", "type": "uint160"}], "outputs": [{"name": "amountOut", "type": "uint256"}], "stateMutability": "payable"}
+        {"type": "function", "name": "exactInput", "inputs": [{"name": "params", "type": "tuple", "internalType": "struct ISwapRouter.ExactInputParams", "components": [{"name": "path", "type": "bytes"}, {"name": "recipient", "type": "address"}, {"name": "deadline", "type": "uint256"}, {"name": "amountIn", "type": "uint256"}, {"name": "amountOutMinimum", "type": "uint256"}]}], "outputs": [{"name": "amountOut", "type": "uint256"}], "stateMutability": "payable"},
+        {"type": "function", "name": "exactOutputSingle", "inputs": [{"name": "params", "type": "tuple", "internalType": "struct ISwapRouter.ExactOutputSingleParams", "components": [{"name": "tokenIn", "type": "address"}, {"name": "tokenOut", "type": "address"}, {"name": "fee", "type": "uint24"}, {"name": "recipient", "type": "address"}, {"name": "deadline", "type": "uint256"}, {"name": "amountOut", "type": "uint256"}, {"name": "amountInMaximum", "type": "uint256"}, {"name": "sqrtPriceLimitX96", "type": "uint160"}]}], "outputs": [{"name": "amountIn", "type": "uint256"}], "stateMutability": "payable"},
+        {"type": "function", "name": "multicall", "inputs": [{"name": "data", "type": "bytes[]"}], "outputs": [{"name": "results", "type": "bytes[]"}], "stateMutability": "payable"},
+        {"type": "function", "name": "refundETH", "inputs": [], "outputs": [], "stateMutability": "payable"},
+        {"type": "function", "name": "unwrapWETH9", "inputs": [{"name": "amountMinimum", "type": "uint256"}, {"name": "recipient", "type": "address"}], "outputs": [], "stateMutability": "payable"}
     ]"#
 );
 
-#[allow(dead_code)]
-static WETH_ADDRESS: Lazy<Address> = Lazy::new(|| {
-    Address::from_str("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2").expect("invalid WETH address")
-});
-#[allow(dead_code)]
-static USDC_ADDRESS: Lazy<Address> = Lazy::new(|| {
-    Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").expect("invalid USDC address")
-});
-#[allow(dead_code)]
-static UNISWAP_V2_ROUTER: Lazy<Address> = Lazy::new(|| {
-    Address::from_str("0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D").expect("invalid V2 router")
-});
-#[allow(dead_code)]
-static UNISWAP_V2_FACTORY: Lazy<Address> = Lazy::new(|| {
-    Address::from_str("0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f").expect("invalid V2 factory")
-});
-#[allow(dead_code)]
-static UNISWAP_V3_QUOTER: Lazy<Address> = Lazy::new(|| {
-    Address::from_str("0xb27308f9F90D607463bb33eA1BeBb41C27CE5AB6").expect("invalid V3 quoter")
-});
-#[allow(dead_code)]
-static UNISWAP_V3_ROUTER: Lazy<Address> = Lazy::new(|| {
-    Address::from_str("0xE592427A0AEce92De3Edee1F18E0157C05861564").expect("invalid V3 router")
-});
+abigen!(
+    SwapRouter02,
+    r#"[
+        {"type": "function", "name": "swapExactTokensForTokens", "inputs": [{"name": "amountIn", "type": "uint256"}, {"name": "amountOutMin", "type": "uint256"}, {"name": "path", "type": "address[]"}, {"name": "to", "type": "address"}], "outputs": [{"name": "amountOut", "type": "uint256"}], "stateMutability": "payable"},
+        {"type": "function", "name": "exactInput", "inputs": [{"name": "params", "type": "tuple", "internalType": "struct IV3SwapRouter.ExactInputParams", "components": [{"name": "path", "type": "bytes"}, {"name": "recipient", "type": "address"}, {"name": "amountIn", "type": "uint256"}, {"name": "amountOutMinimum", "type": "uint256"}]}], "outputs": [{"name": "amountOut", "type": "uint256"}], "stateMutability": "payable"},
+        {"type": "function", "name": "multicall", "inputs": [{"name": "deadline", "type": "uint256"}, {"name": "data", "type": "bytes[]"}], "outputs": [{"name": "results", "type": "bytes[]"}], "stateMutability": "payable"},
+        {"type": "function", "name": "refundETH", "inputs": [], "outputs": [], "stateMutability": "payable"},
+        {"type": "function", "name": "unwrapWETH9", "inputs": [{"name": "amountMinimum", "type": "uint256"}, {"name": "recipient", "type": "address"}], "outputs": [], "stateMutability": "payable"}
+    ]"#
+);
 
-#[allow(dead_code)]
-static TOKEN_SYMBOLS: Lazy<HashMap<&'static str, Address>> = Lazy::new(|| {
-    HashMap::from([
-        ("USDC", *USDC_ADDRESS),
-        (
-            "USDT",
-            Address::from_str("0xdAC17F958D2ee523a2206206994597C13D831ec7").unwrap(),
-        ),
-        (
-            "DAI",
-            Address::from_str("0x6B175474E89094C44Da98b954EedeAC495271d0F").unwrap(),
-        ),
-        ("WETH", *WETH_ADDRESS),
-        (
-            "WBTC",
-            Address::from_str("0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599").unwrap(),
-        ),
-    ])
-});
+abigen!(
+    UniswapV4Quoter,
+    r#"[
+        {"type": "function", "name": "quoteExactInputSingle", "inputs": [{"name": "params", "type": "tuple", "internalType": "struct IV4Quoter.QuoteExactSingleParams", "components": [{"name": "poolKey", "type": "tuple", "internalType": "struct PoolKey", "components": [{"name": "currency0", "type": "address", "internalType": "Currency"}, {"name": "currency1", "type": "address", "internalType": "Currency"}, {"name": "fee", "type": "uint24", "internalType": "uint24"}, {"name": "tickSpacing", "type": "int24", "internalType": "int24"}, {"name": "hooks", "type": "address", "internalType": "contract IHooks"}]}, {"name": "zeroForOne", "type": "bool", "internalType": "bool"}, {"name": "exactAmount", "type": "uint128", "internalType": "uint128"}, {"name": "hookData", "type": "bytes", "internalType": "bytes"}]}], "outputs": [{"name": "amountOut", "type": "uint256"}, {"name": "gasEstimate", "type": "uint256"}], "stateMutability": "nonpayable"}
+    ]"#
+);
+
+abigen!(
+    UniswapV4StateView,
+    r#"[
+        {"type": "function", "name": "getSlot0", "inputs": [{"name": "poolId", "type": "bytes32"}], "outputs": [{"name": "sqrtPriceX96", "type": "uint160"}, {"name": "tick", "type": "int24"}, {"name": "protocolFee", "type": "uint24"}, {"name": "lpFee", "type": "uint24"}], "stateMutability": "view"}
+    ]"#
+);
+
+abigen!(
+    Permit2,
+    r#"[
+        {"type": "function", "name": "allowance", "inputs": [{"name": "user", "type": "address"}, {"name": "token", "type": "address"}, {"name": "spender", "type": "address"}], "outputs": [{"name": "amount", "type": "uint160"}, {"name": "expiration", "type": "uint48"}, {"name": "nonce", "type": "uint48"}], "stateMutability": "view"}
+    ]"#
+);
+
+abigen!(
+    UniversalRouter,
+    r#"[
+        {"type": "function", "name": "execute", "inputs": [{"name": "commands", "type": "bytes"}, {"name": "inputs", "type": "bytes[]"}, {"name": "deadline", "type": "uint256"}], "outputs": [], "stateMutability": "payable"}
+    ]"#
+);
+
+abigen!(
+    CurveStableSwap,
+    r#"[
+        {"type": "function", "name": "fee", "inputs": [], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "get_dy", "inputs": [{"name": "i", "type": "int128"}, {"name": "j", "type": "int128"}, {"name": "dx", "type": "uint256"}], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "exchange", "inputs": [{"name": "i", "type": "int128"}, {"name": "j", "type": "int128"}, {"name": "dx", "type": "uint256"}, {"name": "min_dy", "type": "uint256"}], "outputs": [], "stateMutability": "nonpayable"}
+    ]"#
+);
+
+abigen!(
+    BalancerVault,
+    r#"[
+        {"type": "function", "name": "queryBatchSwap", "inputs": [{"name": "kind", "type": "uint8"}, {"name": "swaps", "type": "tuple[]", "internalType": "struct IVault.BatchSwapStep[]", "components": [{"name": "poolId", "type": "bytes32"}, {"name": "assetInIndex", "type": "uint256"}, {"name": "assetOutIndex", "type": "uint256"}, {"name": "amount", "type": "uint256"}, {"name": "userData", "type": "bytes"}]}, {"name": "assets", "type": "address[]"}, {"name": "funds", "type": "tuple", "internalType": "struct IVault.FundManagement", "components": [{"name": "sender", "type": "address"}, {"name": "fromInternalBalance", "type": "bool"}, {"name": "recipient", "type": "address"}, {"name": "toInternalBalance", "type": "bool"}]}], "outputs": [{"name": "assetDeltas", "type": "int256[]"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "swap", "inputs": [{"name": "singleSwap", "type": "tuple", "internalType": "struct IVault.SingleSwap", "components": [{"name": "poolId", "type": "bytes32"}, {"name": "kind", "type": "uint8"}, {"name": "assetIn", "type": "address"}, {"name": "assetOut", "type": "address"}, {"name": "amount", "type": "uint256"}, {"name": "userData", "type": "bytes"}]}, {"name": "funds", "type": "tuple", "internalType": "struct IVault.FundManagement", "components": [{"name": "sender", "type": "address"}, {"name": "fromInternalBalance", "type": "bool"}, {"name": "recipient", "type": "address"}, {"name": "toInternalBalance", "type": "bool"}]}, {"name": "limit", "type": "uint256"}, {"name": "deadline", "type": "uint256"}], "outputs": [{"name": "amountCalculated", "type": "uint256"}], "stateMutability": "payable"}
+    ]"#
+);
+
+abigen!(
+    BalancerPool,
+    r#"[
+        {"type": "function", "name": "getSwapFeePercentage", "inputs": [], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"}
+    ]"#
+);
+
+abigen!(
+    WETH9,
+    r#"[
+        {"type": "function", "name": "deposit", "inputs": [], "outputs": [], "stateMutability": "payable"},
+        {"type": "function", "name": "withdraw", "inputs": [{"name": "wad", "type": "uint256"}], "outputs": [], "stateMutability": "nonpayable"}
+    ]"#
+);
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SwapProtocol {
     UniswapV2,
     UniswapV3,
+    /// 输入拆分到多条 V2 / V3 路由，经 SwapRouter02 multicall 执行
+    Split,
+    /// 经 Universal Router 执行的一条或多条 V2 / V3 腿，代币通过 Permit2 授权
+    UniversalRouter,
+    /// V4 单例池子，按 PoolKey 定位，只能经 Universal Router 执行
+    UniswapV4,
+    /// Uniswap V2 分叉，使用自己的 factory / router
+    SushiSwap,
+    /// Curve StableSwap 池子，直接调用池子的 `exchange`
+    Curve,
+    /// Balancer V2 池子，经 Vault 的 `swap` 执行
+    BalancerV2,
 }
 
 impl SwapProtocol {
@@ -116,6 +218,43 @@ impl SwapProtocol {
         match self {
             SwapProtocol::UniswapV2 => "UniswapV2",
             SwapProtocol::UniswapV3 => "UniswapV3",
+            SwapProtocol::Split => "Split",
+            SwapProtocol::UniversalRouter => "UniversalRouter",
+            SwapProtocol::UniswapV4 => "UniswapV4",
+            SwapProtocol::SushiSwap => "SushiSwap",
+            SwapProtocol::Curve => "Curve",
+            SwapProtocol::BalancerV2 => "BalancerV2",
+        }
+    }
+}
+
+/// 兑换方向：固定输入数量，或固定输出数量
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SwapMode {
+    #[default]
+    ExactInput,
+    ExactOutput,
+}
+
+impl SwapMode {
+    #[allow(dead_code)]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapMode::ExactInput => "exact_input",
+            SwapMode::ExactOutput => "exact_output",
+        }
+    }
+}
+
+impl FromStr for SwapMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "exact_input" => Ok(Self::ExactInput),
+            "exact_output" => Ok(Self::ExactOutput),
+            _ => bail!("未知的兑换模式: {}（可选 exact_input 或 exact_output）", value),
         }
     }
 }
@@ -124,69 +263,240 @@ impl SwapProtocol {
 #[derive(Debug, Clone)]
 pub struct SwapQuote {
     pub protocol: SwapProtocol,
+    pub mode: SwapMode,
     pub router: Address,
     pub token_in: Address,
     pub token_out: Address,
     pub amount_in: U256,
     pub amount_out: U256,
     pub path: Vec<Address>,
-    pub fee: Option<u32>,
+    /// V3 每一跳的费率，`fees[i]` 对应 `path[i]` → `path[i + 1]`；V2 为空
+    pub fees: Vec<u32>,
+    /// 相对中间价的价格影响（百分比），不含 LP 手续费
+    pub price_impact_pct: Decimal,
+    /// 路径上各池子 LP 手续费合计（百分比）
+    pub lp_fee_pct: Decimal,
+    /// 拆单路由的各条腿，单一路由时为空
+    pub legs: Vec<RouteLeg>,
+    /// V4 / Curve / Balancer 路由所在的池子，V2 / V3 为本地报价用的状态快照
+    pub pool: Option<PoolRef>,
+}
+
+/// 一条具体路由（拆单时为其中一条腿）及其报价
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct RouteLeg {
+    pub protocol: SwapProtocol,
+    pub path: Vec<Address>,
+    pub fees: Vec<u32>,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// V4 / Curve / Balancer 路由所在的池子，V2 / V3 为本地报价用的状态快照
+    pub pool: Option<PoolRef>,
+}
+
+impl SwapQuote {
+    /// 报价经过的各条腿；单一路由时由报价本身构成一条腿
+    pub fn route_legs(&self) -> Vec<RouteLeg> {
+        if !self.legs.is_empty() {
+            return self.legs.clone();
+        }
+        vec![RouteLeg {
+            protocol: self.protocol,
+            path: self.path.clone(),
+            fees: self.fees.clone(),
+            amount_in: self.amount_in,
+            amount_out: self.amount_out,
+            pool: self.pool.clone(),
+        }]
+    }
+}
+
+impl RouteLeg {
+    /// V4 路由的 PoolKey
+    pub fn pool_key(&self) -> Option<&PoolKey> {
+        match &self.pool {
+            Some(PoolRef::UniswapV4(key)) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+/// 仅凭路径无法确定的池子（路由按池子而不是按路由合约报价和执行）
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolRef {
+    /// V2 类路径上各交易对的储备快照，用于本地重新报价
+    UniswapV2 { reserves: Vec<V2Reserves> },
+    /// V3 路径上各跳池子的状态快照，用于本地重新报价
+    UniswapV3 { pools: Vec<V3Pool> },
+    UniswapV4(PoolKey),
+    /// Curve 池子及输入 / 输出代币在池中的序号
+    Curve { pool: Address, i: i128, j: i128 },
+    /// Balancer V2 Vault 中的池子
+    Balancer { pool_id: H256 },
+}
+
+/// 路由相对中间价的价格影响和 LP 手续费（百分比），两者分开统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceImpact {
     pub price_impact_pct: Decimal,
+    pub lp_fee_pct: Decimal,
+}
+
+/// V2 类交易对固定收取 0.3% 手续费
+const V2_FEE: f64 = 0.003;
+
+/// V2 交易对按兑换方向排列的储备
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V2Reserves {
+    pub reserve_in: U256,
+    pub reserve_out: U256,
 }
 
+/// V3 报价尝试的费率档位（0.05%、0.3%、1%）
+const V3_FEE_TIERS: &[u32] = &[500, 3000, 10000];
+
+/// V4 报价尝试的 (费率, tick 间距) 组合，与 V3 各档位的惯例一致
+const V4_POOL_TIERS: &[(u32, i32)] = &[(100, 1), (500, 10), (3000, 60), (10000, 200)];
+
+/// 本地模拟一条 V3 路由时最多补充读取的 bitmap 字和 tick 数，超出后改用 QuoterV2
+const V3_MAX_STATE_READS: usize = 32;
+
+/// 拆单的分配粒度：每份 10%
+const SPLIT_STEPS: u64 = 10;
+/// 参与拆单的候选路由数（按整单输出排序）
+const SPLIT_MAX_ROUTES: usize = 4;
+/// 拆单最多的腿数
+const SPLIT_MAX_LEGS: usize = 3;
+/// 拆单输出至少比最优单一路由多 0.1% 才采用
+const MIN_SPLIT_GAIN_BPS: u64 = 10;
+
+/// 路由比较用的 gas 模型：交易与路由合约的固定开销，加上每跳（或每个池子）的消耗。
+/// 只用于比较候选路由，签名前仍以 eth_estimateGas 为准
+const SWAP_BASE_GAS: u64 = 60_000;
+const V2_HOP_GAS: u64 = 60_000;
+const V3_HOP_GAS: u64 = 80_000;
+const CURVE_SWAP_GAS: u64 = 120_000;
+const BALANCER_SWAP_GAS: u64 = 110_000;
+
+/// 路由比较时把 gas 花费折算为计价代币：固定输入时为输出代币，固定输出时为输入代币
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPricing {
+    /// 预计实际支付的 gas 单价（wei）
+    pub fee_per_gas: U256,
+    /// 1 个原生代币折合的计价代币数量（最小单位）
+    pub output_per_native: U256,
+}
+
+impl GasPricing {
+    /// `gas` 单位 gas 的花费折合的计价代币数量（最小单位）
+    pub fn cost_in_output(&self, gas: u64) -> U256 {
+        self.fee_per_gas * gas * self.output_per_native / U256::exp10(18)
+    }
+}
+
+/// Balancer `SwapKind.GIVEN_IN`
+const BALANCER_GIVEN_IN: u8 = 0;
+
+/// Permit2 授权的有效期
+const PERMIT2_EXPIRATION_SECS: u64 = 30 * 24 * 60 * 60;
+
 #[allow(dead_code)]
+#[derive(Clone)]
 pub struct EthereumClient {
-    provider: Arc<Provider<Http>>,
-    wallet: LocalWallet,
+    provider: Arc<PooledProvider>,
+    /// 未配置签名器时为只读模式，只能查询不能签名
+    signer: Option<Arc<dyn TransactionSigner>>,
     chain_id: u64,
+    /// 当前链的协议地址和代币注册表
+    chain: ChainInfo,
+    /// 签名钱包的本地 nonce 分配，各克隆之间共享
+    nonces: Arc<NonceManager>,
 }
 
 #[allow(dead_code)]
 impl EthereumClient {
-    pub async fn new(rpc_url: String, private_key: String) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(&rpc_url)
-            .context("创建 Provider 失败，请检查 ETHEREUM_RPC_URL")?;
-
-        let chain_id = provider
-            .get_chainid()
+    /// 连接单个 RPC
+    pub async fn new(
+        rpc_url: String,
+        signer: Option<Arc<dyn TransactionSigner>>,
+        chain_settings: &BTreeMap<String, ChainSettings>,
+    ) -> Result<Self> {
+        let pool = RpcPool::connect(vec![RpcProvider::configured(&rpc_url)], |_| vec![], None)
             .await
-            .context("获取链 ID 失败，请确保 RPC 可用")?
-            .as_u64();
-
-        let wallet = LocalWallet::from_str(&private_key)
-            .context("解析 PRIVATE_KEY 失败，请检查格式和 0x 前缀")?
-            .with_chain_id(chain_id);
+            .context("连接 RPC 失败，请检查 ETHEREUM_RPC_URL")?;
+        Self::with_pool(pool, signer, chain_settings)
+    }
 
-        info!(
-            wallet = %format_address(wallet.address()),
-            chain_id,
-            rpc = %rpc_url,
-            "Ethereum client initialized"
-        );
+    /// 使用已探测好的 RPC 连接池
+    pub fn with_pool(
+        pool: RpcPool,
+        signer: Option<Arc<dyn TransactionSigner>>,
+        chain_settings: &BTreeMap<String, ChainSettings>,
+    ) -> Result<Self> {
+        let chain_id = pool.chain_id();
+        let rpc_url = pool.describe();
+        let chain = resolve_chain(chain_id, chain_settings)?;
+
+        match &signer {
+            Some(signer) => info!(
+                wallet = %format_address(signer.address()),
+                signer = signer.kind(),
+                chain = %chain.name,
+                chain_id,
+                rpc = %rpc_url,
+                "Ethereum client initialized"
+            ),
+            None => info!(
+                chain = %chain.name,
+                chain_id,
+                rpc = %rpc_url,
+                "Ethereum client initialized (read-only)"
+            ),
+        }
 
         Ok(Self {
-            provider: Arc::new(provider),
-            wallet,
+            provider: Arc::new(Provider::new(pool)),
+            signer,
             chain_id,
+            chain,
+            nonces: Arc::new(NonceManager::default()),
         })
     }
 
     pub async fn get_eth_balance(&self, address: &str) -> Result<Balance> {
+        let (client, quorum) = self.read_scope().await?;
+        let mut balance = client.fetch_eth_balance(address).await?;
+        balance.quorum = quorum.map(|log| log.report());
+        Ok(balance)
+    }
+
+    async fn fetch_eth_balance(&self, address: &str) -> Result<Balance> {
         let address = Address::from_str(address)?;
         let balance_wei = self.provider.get_balance(address, None).await?;
         let balance_eth = units_to_decimal(balance_wei, 18)?;
-        
+        let symbol = self.chain.native_symbol.clone();
+
         Ok(Balance {
             address: format_address(address),
             token_address: None,
-            symbol: "ETH".to_string(),
+            formatted_balance: format!("{:.6} {}", balance_eth, symbol),
+            symbol,
             balance: balance_eth,
             decimals: 18,
-            formatted_balance: format!("{:.6} ETH", balance_eth),
+            quorum: None,
         })
     }
 
     pub async fn get_erc20_balance(&self, address: &str, token_address: &str) -> Result<Balance> {
+        let (client, quorum) = self.read_scope().await?;
+        let mut balance = client.fetch_erc20_balance(address, token_address).await?;
+        balance.quorum = quorum.map(|log| log.report());
+        Ok(balance)
+    }
+
+    async fn fetch_erc20_balance(&self, address: &str, token_address: &str) -> Result<Balance> {
         let address = Address::from_str(address)?;
         let token_address = Address::from_str(token_address)?;
         let token_info = self.get_token_info(token_address).await?;
@@ -208,6 +518,7 @@ impl EthereumClient {
             balance,
             decimals: token_info.decimals,
             formatted_balance: format!("{:.6} {}", balance, token_info.symbol),
+            quorum: None,
         })
     }
 
@@ -215,8 +526,8 @@ impl EthereumClient {
         if token_address == Address::zero() {
             return Ok(TokenInfo {
                 address: format_address(Address::zero()),
-                symbol: "ETH".to_string(),
-                name: "Ethereum".to_string(),
+                symbol: self.chain.native_symbol.clone(),
+                name: self.chain.native_symbol.clone(),
                 decimals: 18,
                 is_native: true,
             });
@@ -249,6 +560,20 @@ impl EthereumClient {
         token_address: Option<&str>,
         symbol: Option<&str>,
         quote_currency: &str,
+    ) -> Result<TokenPrice> {
+        let (client, quorum) = self.read_scope().await?;
+        let mut price = client
+            .fetch_token_price(token_address, symbol, quote_currency)
+            .await?;
+        price.quorum = quorum.map(|log| log.report());
+        Ok(price)
+    }
+
+    async fn fetch_token_price(
+        &self,
+        token_address: Option<&str>,
+        symbol: Option<&str>,
+        quote_currency: &str,
     ) -> Result<TokenPrice> {
         let quote_currency = quote_currency.to_uppercase();
         let token_address = match (token_address, symbol) {
@@ -265,8 +590,10 @@ impl EthereumClient {
         let token_info = self.get_token_info(token_address).await?;
 
         let price = match quote_currency.as_str() {
-            "ETH" => self.get_price_in_eth(token_address, &token_info).await?,
             "USD" => self.get_price_in_usd(token_address, &token_info).await?,
+            currency if currency == "ETH" || currency == self.chain.native_symbol => {
+                self.get_price_in_eth(token_address, &token_info).await?
+            }
             other => bail!("暂不支持的报价币种: {other}"),
         };
 
@@ -276,57 +603,182 @@ impl EthereumClient {
             price,
             quote_currency,
             timestamp: current_timestamp()?,
+            quorum: None,
         })
     }
 
+    /// 固定输入报价；给出 `gas_pricing` 时按扣除 gas 后的净输出选择路由，否则按输出选择。
+    /// 面向用户的兑换报价，会尝试拆单路由
     pub async fn quote_best_swap(
         &self,
         token_in: Address,
         token_in_decimals: u8,
         token_out: Address,
-        token_out_decimals: u8,
         amount_in: U256,
+        gas_pricing: Option<GasPricing>,
+    ) -> Result<SwapQuote> {
+        self.quote_exact_input(token_in, token_out, amount_in, gas_pricing, Some(token_in_decimals))
+            .await
+    }
+
+    /// 固定输入的单一路由报价，不做拆单搜索；用于价格查询和 gas 折算等内部报价
+    pub(crate) async fn quote_single_route(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<SwapQuote> {
+        self.quote_exact_input(token_in, token_out, amount_in, None, None).await
+    }
+
+    /// `split_decimals` 为拆单搜索所需的输入代币精度，为空时只比较单一路由
+    async fn quote_exact_input(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        gas_pricing: Option<GasPricing>,
+        split_decimals: Option<u8>,
+    ) -> Result<SwapQuote> {
+        if token_in == token_out {
+            bail!("输入与输出代币相同，无需交换");
+        }
+
+        // 原生代币（0x0）按包装代币报价，执行时由路由合约负责 wrap / unwrap
+        let (routed_in, routed_out) = (self.routable(token_in), self.routable(token_out));
+        if routed_in == routed_out {
+            bail!("原生代币与包装代币之间请使用 wrap_eth / unwrap_weth，无需兑换");
+        }
+
+        // 各适配器接收用户传入的地址，自行决定原生代币按包装代币还是直接报价
+        let adapters = adapters_for(&self.chain);
+        let quoted = join_all(
+            adapters
+                .iter()
+                .map(|adapter| adapter.quote_routes(self, token_in, token_out, amount_in)),
+        )
+        .await;
+        let mut routes = Vec::new();
+        for (adapter, result) in adapters.iter().zip(quoted) {
+            match result {
+                Ok(adapter_routes) => routes.extend(adapter_routes),
+                Err(err) => warn!(protocol = adapter.protocol().as_str(), "报价失败: {:#}", err),
+            }
+        }
+
+        // 多跳路由输出更多时 gas 也更贵，扣除 gas 后未必更优
+        let net_output = |amount_out: U256, gas: u64| match gas_pricing {
+            Some(pricing) => amount_out.saturating_sub(pricing.cost_in_output(gas)),
+            None => amount_out,
+        };
+        let best = routes
+            .iter()
+            .max_by_key(|route| {
+                net_output(route.amount_out, modelled_swap_gas(std::slice::from_ref(*route)))
+            })
+            .cloned()
+            .ok_or_else(|| anyhow!("未能在已启用的 DEX 上找到可用报价"))?;
+        let best_gas = modelled_swap_gas(std::slice::from_ref(&best));
+        if routes.iter().any(|route| route.amount_out > best.amount_out) {
+            info!(
+                protocol = best.protocol.as_str(),
+                hops = best.path.len().saturating_sub(1),
+                "扣除 gas 后选择了输出较少的路由"
+            );
+        }
+        let impact = self.estimate_route_impact(&best).await?;
+        let mut quote = SwapQuote {
+            protocol: best.protocol,
+            mode: SwapMode::ExactInput,
+            router: self.route_router(&best)?,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out: best.amount_out,
+            path: best.path,
+            fees: best.fees,
+            price_impact_pct: impact.price_impact_pct,
+            lp_fee_pct: impact.lp_fee_pct,
+            legs: Vec::new(),
+            pool: best.pool,
+        };
+
+        let split_quote = match split_decimals {
+            Some(decimals) => self.quote_split_route(&routes, amount_in, decimals).await?,
+            None => None,
+        };
+        if let Some(split) = split_quote {
+            // 按净输出比较后仍要求明显更多，留出 gas 模型的误差
+            let single = net_output(quote.amount_out, best_gas);
+            let threshold = single + single * MIN_SPLIT_GAIN_BPS / 10_000;
+            if net_output(split.amount_out, modelled_swap_gas(&split.legs)) > threshold {
+                info!(
+                    legs = split.legs.len(),
+                    single = %quote.amount_out,
+                    split = %split.amount_out,
+                    "拆单路由优于单一路由"
+                );
+                quote = SwapQuote {
+                    token_in,
+                    token_out,
+                    ..split
+                };
+            }
+        }
+
+        Ok(quote)
+    }
+
+    /// 固定输出数量报价；给出 `gas_pricing`（折算为输入代币）时按计入 gas 后的总输入选择路由，
+    /// 否则按所需输入选择
+    pub async fn quote_best_swap_exact_output(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_out: U256,
+        gas_pricing: Option<GasPricing>,
     ) -> Result<SwapQuote> {
         if token_in == token_out {
             bail!("输入与输出代币相同，无需交换");
         }
 
+        let (routed_in, routed_out) = (self.routable(token_in), self.routable(token_out));
+        if routed_in == routed_out {
+            bail!("原生代币与包装代币之间请使用 wrap_eth / unwrap_weth，无需兑换");
+        }
+
         let mut candidates: Vec<SwapQuote> = Vec::new();
 
         if let Some(v3_quote) = self
-            .quote_uniswap_v3(
-                token_in,
-                token_in_decimals,
-                token_out,
-                token_out_decimals,
-                amount_in,
-            )
+            .quote_uniswap_v3_exact_output(routed_in, routed_out, amount_out)
             .await?
         {
             candidates.push(v3_quote);
         }
 
         if let Some(v2_quote) = self
-            .quote_uniswap_v2(
-                token_in,
-                token_in_decimals,
-                token_out,
-                token_out_decimals,
-                amount_in,
-            )
+            .quote_uniswap_v2_exact_output(routed_in, routed_out, amount_out)
             .await?
         {
             candidates.push(v2_quote);
         }
 
-        candidates
-            .into_iter()
-            .max_by(|a, b| a.amount_out.cmp(&b.amount_out))
+        cheapest_exact_output(candidates, gas_pricing)
+            .map(|quote| SwapQuote {
+                token_in,
+                token_out,
+                ..quote
+            })
             .ok_or_else(|| anyhow!("未能在 Uniswap V2/V3 上找到可用报价"))
     }
 
+    /// 构造 V2 兑换交易；`token_in` / `token_out` 为 0x0 时走路由合约的 ETH 入口，`path` 使用包装代币
+    #[allow(clippy::too_many_arguments)]
     pub async fn build_uniswap_v2_swap_tx(
         &self,
+        protocol: SwapProtocol,
+        token_in: Address,
+        token_out: Address,
         amount_in: U256,
         amount_out_min: U256,
         path: Vec<Address>,
@@ -334,239 +786,1603 @@ impl EthereumClient {
         deadline_secs: u64,
     ) -> Result<TypedTransaction> {
         let deadline = self.deadline_after(deadline_secs)?;
-        let router = UniswapV2Router::new(*UNISWAP_V2_ROUTER, self.provider.clone());
-        let call = router.swap_exact_tokens_for_tokens(
-            amount_in,
-            amount_out_min,
-            path,
-            recipient,
-            deadline,
-        );
-        let calldata = call
-            .calldata()
-            .ok_or_else(|| anyhow!("构造 V2 swap calldata 失败"))?
-            .clone();
+        let v2 = self.v2_deployment(protocol)?;
+        let router = UniswapV2Router::new(v2.router, self.provider.clone());
+        let (calldata, value) = if token_in == Address::zero() {
+            let call = router.swap_exact_eth_for_tokens(amount_out_min, path, recipient, deadline);
+            (call.calldata(), amount_in)
+        } else if token_out == Address::zero() {
+            let call = router.swap_exact_tokens_for_eth(
+                amount_in,
+                amount_out_min,
+                path,
+                recipient,
+                deadline,
+            );
+            (call.calldata(), U256::zero())
+        } else {
+            let call = router.swap_exact_tokens_for_tokens(
+                amount_in,
+                amount_out_min,
+                path,
+                recipient,
+                deadline,
+            );
+            (call.calldata(), U256::zero())
+        };
+        let calldata = calldata.ok_or_else(|| anyhow!("构造 V2 swap calldata 失败"))?;
 
-        let mut tx: TypedTransaction = TransactionRequest::new()
-            .from(self.wallet.address())
-            .to(*UNISWAP_V2_ROUTER)
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(v2.router)
             .data(calldata)
-            .value(U256::zero())
+            .value(value)
             .into();
 
         tx.set_chain_id(self.chain_id);
         Ok(tx)
     }
 
+    /// 构造 V3 `exactInput` 兑换交易（`path` / `fees` 为逐跳路由）；涉及原生代币时通过 multicall 附带 `refundETH` / `unwrapWETH9`
     #[allow(clippy::too_many_arguments)]
     pub async fn build_uniswap_v3_swap_tx(
         &self,
         token_in: Address,
         token_out: Address,
-        fee: u32,
+        path: &[Address],
+        fees: &[u32],
         amount_in: U256,
         amount_out_min: U256,
         recipient: Address,
         deadline_secs: u64,
     ) -> Result<TypedTransaction> {
         let deadline = self.deadline_after(deadline_secs)?;
-        let router = UniswapV3Router::new(*UNISWAP_V3_ROUTER, self.provider.clone());
-        let call = router.exact_input_single(
-            token_in,
-            token_out,
-            fee,
-            recipient,
-            deadline,
-            amount_in,
-            amount_out_min,
-            U256::zero(),
-        );
-        let calldata = call
+        let v3 = self.chain.uniswap_v3()?;
+        let router = UniswapV3Router::new(v3.router, self.provider.clone());
+        let native_in = token_in == Address::zero();
+        let native_out = token_out == Address::zero();
+
+        // 输出原生代币时先把 WETH 留在路由合约里，再由 unwrapWETH9 转给 recipient
+        let swap_recipient = if native_out { v3.router } else { recipient };
+        let swap_call = router
+            .exact_input(uniswap_v3_router::ExactInputParams {
+                path: encode_v3_path(path, fees)?,
+                recipient: swap_recipient,
+                deadline,
+                amount_in,
+                amount_out_minimum: amount_out_min,
+            })
             .calldata()
-            .ok_or_else(|| anyhow!("构造 V3 swap calldata 失败"))?
-            .clone();
+            .ok_or_else(|| anyhow!("构造 V3 swap calldata 失败"))?;
+
+        let calldata = if native_in || native_out {
+            let follow_up = if native_in {
+                router.refund_eth().calldata()
+            } else {
+                router.unwrap_weth9(amount_out_min, recipient).calldata()
+            }
+            .ok_or_else(|| anyhow!("构造 V3 multicall calldata 失败"))?;
+            router
+                .multicall(vec![swap_call, follow_up])
+                .calldata()
+                .ok_or_else(|| anyhow!("构造 V3 multicall calldata 失败"))?
+        } else {
+            swap_call
+        };
+        let value = if native_in { amount_in } else { U256::zero() };
 
-        let mut tx: TypedTransaction = TransactionRequest::new()
-            .from(self.wallet.address())
-            .to(*UNISWAP_V3_ROUTER)
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(v3.router)
             .data(calldata)
-            .value(U256::zero())
+            .value(value)
             .into();
 
         tx.set_chain_id(self.chain_id);
         Ok(tx)
     }
 
-    pub async fn sign_transaction(&self, mut tx: TypedTransaction) -> Result<Bytes> {
-        if tx.from().is_none() {
-            tx.set_from(self.wallet.address());
-        }
-
-        if tx.chain_id().is_none() {
-            tx.set_chain_id(self.chain_id);
-        }
-
-        if tx.gas().is_none() {
-            let gas = self.provider.estimate_gas(&tx, None).await?;
-            tx.set_gas(gas);
-        }
-
-        if tx.gas_price().is_none() {
-            let gas_price = self.provider.get_gas_price().await?;
-            tx.set_gas_price(gas_price);
-        }
+    /// 构造 V2 固定输出兑换交易；输入原生代币时 `value` 为最大输入，多余部分由路由合约退回
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build_uniswap_v2_exact_output_tx(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_out: U256,
+        amount_in_max: U256,
+        path: Vec<Address>,
+        recipient: Address,
+        deadline_secs: u64,
+    ) -> Result<TypedTransaction> {
+        let deadline = self.deadline_after(deadline_secs)?;
+        let v2 = self.chain.uniswap_v2()?;
+        let router = UniswapV2Router::new(v2.router, self.provider.clone());
+        let (calldata, value) = if token_in == Address::zero() {
+            let call = router.swap_eth_for_exact_tokens(amount_out, path, recipient, deadline);
+            (call.calldata(), amount_in_max)
+        } else if token_out == Address::zero() {
+            let call = router.swap_tokens_for_exact_eth(
+                amount_out,
+                amount_in_max,
+                path,
+                recipient,
+                deadline,
+            );
+            (call.calldata(), U256::zero())
+        } else {
+            let call = router.swap_tokens_for_exact_tokens(
+                amount_out,
+                amount_in_max,
+                path,
+                recipient,
+                deadline,
+            );
+            (call.calldata(), U256::zero())
+        };
+        let calldata = calldata.ok_or_else(|| anyhow!("构造 V2 swap calldata 失败"))?;
 
-        if tx.nonce().is_none() {
-            let nonce = self
-                .provider
-                .get_transaction_count(self.wallet.address(), None)
-                .await?;
-            tx.set_nonce(nonce);
-        }
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(v2.router)
+            .data(calldata)
+            .value(value)
+            .into();
 
-        let signature = self.wallet.sign_transaction(&tx).await?;
-        Ok(tx.rlp_signed(&signature))
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
     }
 
-    pub fn provider(&self) -> Arc<Provider<Http>> {
-        self.provider.clone()
-    }
+    /// 构造 V3 固定输出兑换交易；输入原生代币时必须附带 `refundETH` 退回未用完的部分
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build_uniswap_v3_exact_output_tx(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        fee: u32,
+        amount_out: U256,
+        amount_in_max: U256,
+        recipient: Address,
+        deadline_secs: u64,
+    ) -> Result<TypedTransaction> {
+        let deadline = self.deadline_after(deadline_secs)?;
+        let v3 = self.chain.uniswap_v3()?;
+        let router = UniswapV3Router::new(v3.router, self.provider.clone());
+        let native_in = token_in == Address::zero();
+        let native_out = token_out == Address::zero();
+
+        let swap_recipient = if native_out { v3.router } else { recipient };
+        let swap_call = router
+            .exact_output_single(ExactOutputSingleParams {
+                token_in: self.routable(token_in),
+                token_out: self.routable(token_out),
+                fee,
+                recipient: swap_recipient,
+                deadline,
+                amount_out,
+                amount_in_maximum: amount_in_max,
+                sqrt_price_limit_x96: U256::zero(),
+            })
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 V3 swap calldata 失败"))?;
+
+        let calldata = if native_in || native_out {
+            let follow_up = if native_in {
+                router.refund_eth().calldata()
+            } else {
+                router.unwrap_weth9(amount_out, recipient).calldata()
+            }
+            .ok_or_else(|| anyhow!("构造 V3 multicall calldata 失败"))?;
+            router
+                .multicall(vec![swap_call, follow_up])
+                .calldata()
+                .ok_or_else(|| anyhow!("构造 V3 multicall calldata 失败"))?
+        } else {
+            swap_call
+        };
+        let value = if native_in { amount_in_max } else { U256::zero() };
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(v3.router)
+            .data(calldata)
+            .value(value)
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 构造拆单兑换交易：各条腿按报价比例分摊 `amount_out_min`，在 SwapRouter02 的一次 multicall 中执行
+    pub async fn build_split_swap_tx(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        legs: &[RouteLeg],
+        amount_out_min: U256,
+        recipient: Address,
+        deadline_secs: u64,
+    ) -> Result<TypedTransaction> {
+        let deadline = self.deadline_after(deadline_secs)?;
+        let router_address = self.router_for(SwapProtocol::Split)?;
+        let router = SwapRouter02::new(router_address, self.provider.clone());
+        let native_in = token_in == Address::zero();
+        let native_out = token_out == Address::zero();
+
+        let total_out = legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_out);
+        if total_out.is_zero() {
+            bail!("拆单路由没有有效的腿");
+        }
+        let leg_recipient = if native_out { router_address } else { recipient };
+
+        let mut calls = Vec::with_capacity(legs.len() + 1);
+        for leg in legs {
+            let leg_min = leg.amount_out * amount_out_min / total_out;
+            let call = match leg.protocol {
+                SwapProtocol::UniswapV2 => router
+                    .swap_exact_tokens_for_tokens(leg.amount_in, leg_min, leg.path.clone(), leg_recipient)
+                    .calldata(),
+                SwapProtocol::UniswapV3 => router
+                    .exact_input(swap_router_02::ExactInputParams {
+                        path: encode_v3_path(&leg.path, &leg.fees)?,
+                        recipient: leg_recipient,
+                        amount_in: leg.amount_in,
+                        amount_out_minimum: leg_min,
+                    })
+                    .calldata(),
+                other => bail!("拆单路由不能包含 {} 腿", other.as_str()),
+            };
+            calls.push(call.ok_or_else(|| anyhow!("构造拆单 calldata 失败"))?);
+        }
+        if native_in {
+            calls.push(
+                router
+                    .refund_eth()
+                    .calldata()
+                    .ok_or_else(|| anyhow!("构造拆单 calldata 失败"))?,
+            );
+        }
+        if native_out {
+            calls.push(
+                router
+                    .unwrap_weth9(amount_out_min, recipient)
+                    .calldata()
+                    .ok_or_else(|| anyhow!("构造拆单 calldata 失败"))?,
+            );
+        }
+
+        let calldata = router
+            .multicall(deadline, calls)
+            .calldata()
+            .ok_or_else(|| anyhow!("构造拆单 multicall calldata 失败"))?;
+        let value = if native_in {
+            legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_in)
+        } else {
+            U256::zero()
+        };
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(router_address)
+            .data(calldata)
+            .value(value)
+            .into();
 
-    pub fn wallet_address(&self) -> Address {
-        self.wallet.address()
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 构造 Curve 池子 `exchange(i, j, dx, min_dy)` 交易，输出直接转给发送方
+    pub fn build_curve_swap_tx(
+        &self,
+        pool: Address,
+        i: i128,
+        j: i128,
+        amount_in: U256,
+        amount_out_min: U256,
+    ) -> Result<TypedTransaction> {
+        let curve = CurveStableSwap::new(pool, self.provider.clone());
+        let calldata = curve
+            .exchange(i, j, amount_in, amount_out_min)
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 Curve exchange calldata 失败"))?;
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(pool)
+            .data(calldata)
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 构造 Balancer Vault `swap`（GIVEN_IN 单池兑换）交易
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_balancer_swap_tx(
+        &self,
+        pool_id: H256,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        amount_out_min: U256,
+        recipient: Address,
+        deadline_secs: u64,
+    ) -> Result<TypedTransaction> {
+        let deadline = self.deadline_after(deadline_secs)?;
+        let sender = self.signer()?.address();
+        let vault_address = self.chain.balancer()?.vault;
+        let vault = BalancerVault::new(vault_address, self.provider.clone());
+        let calldata = vault
+            .swap(
+                SingleSwap {
+                    pool_id: pool_id.0,
+                    kind: BALANCER_GIVEN_IN,
+                    asset_in: token_in,
+                    asset_out: token_out,
+                    amount: amount_in,
+                    user_data: Bytes::new(),
+                },
+                FundManagement {
+                    sender,
+                    from_internal_balance: false,
+                    recipient,
+                    to_internal_balance: false,
+                },
+                amount_out_min,
+                deadline,
+            )
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 Balancer swap calldata 失败"))?;
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(sender)
+            .to(vault_address)
+            .data(calldata)
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 把固定输入报价改为经 Universal Router 执行，原有路由（或拆单的各条腿）成为命令序列
+    pub fn to_universal_router(&self, quote: SwapQuote) -> Result<SwapQuote> {
+        if quote.mode != SwapMode::ExactInput {
+            bail!("Universal Router 目前只支持固定输入兑换");
+        }
+        let router = self.router_for(SwapProtocol::UniversalRouter)?;
+        let legs = match quote.protocol {
+            SwapProtocol::Split => quote.legs.clone(),
+            SwapProtocol::UniswapV2 | SwapProtocol::UniswapV3 | SwapProtocol::UniswapV4 => {
+                vec![RouteLeg {
+                    protocol: quote.protocol,
+                    path: quote.path.clone(),
+                    fees: quote.fees.clone(),
+                    amount_in: quote.amount_in,
+                    amount_out: quote.amount_out,
+                    pool: quote.pool.clone(),
+                }]
+            }
+            SwapProtocol::UniversalRouter => return Ok(quote),
+            other => bail!("Universal Router 不支持 {} 路由", other.as_str()),
+        };
+
+        Ok(SwapQuote {
+            protocol: SwapProtocol::UniversalRouter,
+            router,
+            legs,
+            ..quote
+        })
+    }
+
+    pub fn permit2_address(&self) -> Address {
+        Address::from_str(PERMIT2).expect("invalid Permit2 address")
+    }
+
+    /// Permit2 中对 `spender` 的授权不足或即将过期时，签名一个新的 `PermitSingle`；授权足够时返回 None
+    pub async fn sign_permit2(
+        &self,
+        token: Address,
+        amount: U256,
+        spender: Address,
+        deadline_secs: u64,
+    ) -> Result<Option<PermitSingle>> {
+        let signer = self.signer()?;
+        let permit2 = Permit2::new(self.permit2_address(), self.provider.clone());
+        let (allowed, expiration, nonce) = permit2
+            .allowance(signer.address(), token, spender)
+            .call()
+            .await
+            .context("查询 Permit2 授权失败")?;
+
+        let sig_deadline = self.deadline_after(deadline_secs)?.as_u64();
+        if allowed >= amount && expiration > sig_deadline {
+            return Ok(None);
+        }
+
+        let expiration = current_timestamp()? + PERMIT2_EXPIRATION_SECS;
+        let data = permit_single_typed_data(
+            self.chain_id,
+            self.permit2_address(),
+            token,
+            amount,
+            expiration,
+            nonce,
+            spender,
+            sig_deadline,
+        )?;
+        let signature = signer.sign_typed_data(&data).await?;
+        info!(
+            token = %format_address(token),
+            spender = %format_address(spender),
+            nonce,
+            "已签名 Permit2 授权"
+        );
+
+        Ok(Some(PermitSingle {
+            token,
+            amount,
+            expiration,
+            nonce,
+            spender,
+            sig_deadline,
+            signature,
+        }))
+    }
+
+    /// 构造 Universal Router `execute(commands, inputs, deadline)` 交易
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build_universal_router_swap_tx(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        legs: &[RouteLeg],
+        amount_out_min: U256,
+        permit: Option<&PermitSingle>,
+        recipient: Address,
+        deadline_secs: u64,
+    ) -> Result<TypedTransaction> {
+        let deadline = self.deadline_after(deadline_secs)?;
+        let router_address = self.router_for(SwapProtocol::UniversalRouter)?;
+        let router = UniversalRouter::new(router_address, self.provider.clone());
+        let native_in = token_in == Address::zero();
+        let native_out = token_out == Address::zero();
+
+        let (commands, inputs) =
+            encode_swap_commands(legs, native_in, native_out, amount_out_min, recipient, permit)?;
+        let calldata = router
+            .execute(commands, inputs, deadline)
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 Universal Router calldata 失败"))?;
+        let value = if native_in {
+            legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_in)
+        } else {
+            U256::zero()
+        };
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(router_address)
+            .data(calldata)
+            .value(value)
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 构造 WETH `deposit()` 交易，把原生代币包装为 WETH
+    pub fn build_wrap_tx(&self, amount: U256) -> Result<TypedTransaction> {
+        let weth = WETH9::new(self.chain.wrapped_native, self.provider.clone());
+        let calldata = weth
+            .deposit()
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 deposit calldata 失败"))?;
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(self.chain.wrapped_native)
+            .data(calldata)
+            .value(amount)
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 构造 WETH `withdraw(amount)` 交易，把 WETH 解包为原生代币
+    pub fn build_unwrap_tx(&self, amount: U256) -> Result<TypedTransaction> {
+        let weth = WETH9::new(self.chain.wrapped_native, self.provider.clone());
+        let calldata = weth
+            .withdraw(amount)
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 withdraw calldata 失败"))?;
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(self.chain.wrapped_native)
+            .data(calldata)
+            .value(U256::zero())
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    /// 查询 `owner` 授权给 `spender` 的 ERC20 额度
+    pub async fn allowance(&self, token: Address, owner: Address, spender: Address) -> Result<U256> {
+        IERC20::new(token, self.provider.clone())
+            .allowance(owner, spender)
+            .call()
+            .await
+            .context("调用 allowance 失败")
+    }
+
+    /// 构造 `approve(spender, amount)` 交易
+    pub fn build_approve_tx(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<TypedTransaction> {
+        let erc20 = IERC20::new(token, self.provider.clone());
+        let calldata = erc20
+            .approve(spender, amount)
+            .calldata()
+            .ok_or_else(|| anyhow!("构造 approve calldata 失败"))?;
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(self.signer()?.address())
+            .to(token)
+            .data(calldata)
+            .value(U256::zero())
+            .into();
+
+        tx.set_chain_id(self.chain_id);
+        Ok(tx)
+    }
+
+    pub async fn sign_transaction(&self, mut tx: TypedTransaction) -> Result<Bytes> {
+        let signer = self.signer()?;
+
+        if tx.from().is_none() {
+            tx.set_from(signer.address());
+        }
+
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        if tx.gas().is_none() {
+            let gas = self.provider.estimate_gas(&tx, None).await?;
+            tx.set_gas(gas);
+        }
+
+        let missing_fees = match &tx {
+            TypedTransaction::Eip1559(inner) => {
+                inner.max_fee_per_gas.is_none() || inner.max_priority_fee_per_gas.is_none()
+            }
+            _ => tx.gas_price().is_none(),
+        };
+        if missing_fees {
+            let fees = self.suggest_fees(GasSpeed::Normal).await?;
+            apply_fees(&mut tx, &fees);
+        }
+
+        // 未指定 nonce 的交易只签名不广播，不占用 nonce；需要广播的交易先用 reserve_nonces 分配
+        if tx.nonce().is_none() {
+            tx.set_nonce(self.next_nonce().await?);
+        }
+
+        signer.sign_transaction(&tx).await
+    }
+
+    /// 下一个将分配的 nonce，不占用；并发的模拟与执行不会因此错开 nonce
+    pub async fn next_nonce(&self) -> Result<U256> {
+        self.nonces.peek(self.pending_nonce()).await
+    }
+
+    /// 从本地 nonce 管理器连续分配 `count` 个 nonce，返回第一个；并发构造的交易不会拿到相同的 nonce
+    pub async fn reserve_nonces(&self, count: u64) -> Result<U256> {
+        self.nonces.reserve(count, self.pending_nonce()).await
+    }
+
+    /// pending 区块中本钱包的交易数
+    async fn pending_nonce(&self) -> Result<U256> {
+        let address = self.signer()?.address();
+        self.provider
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await
+            .context("获取 pending nonce 失败")
+    }
+
+    /// 归还已分配但未能签名或发出的 nonce
+    pub async fn release_nonces(&self, first: U256, count: u64) {
+        self.nonces.release(first, count).await;
+    }
+
+    /// 查询本钱包发出、仍在交易池中等待打包的交易
+    pub async fn pending_transaction(&self, tx_hash: H256) -> Result<Transaction> {
+        let wallet = self.signer()?.address();
+        let tx = self
+            .provider
+            .get_transaction(tx_hash)
+            .await
+            .context("查询交易失败")?
+            .ok_or_else(|| anyhow!("节点上找不到交易 {:?}", tx_hash))?;
+        if tx.from != wallet {
+            bail!("交易 {:?} 不是由当前钱包发出的", tx_hash);
+        }
+        if tx.block_number.is_some() {
+            bail!("交易 {:?} 已上链，无法替换", tx_hash);
+        }
+        Ok(tx)
+    }
+
+    /// 按 eth_feeHistory 最近区块的小费百分位给出 type-2 交易的费用
+    pub async fn suggest_fees(&self, speed: GasSpeed) -> Result<FeeSuggestion> {
+        let history = self
+            .provider
+            .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &FEE_HISTORY_PERCENTILES)
+            .await
+            .context("获取 fee history 失败")?;
+        suggest_fees(&history, speed)
+    }
+
+    /// 按预计 gas 单价和计价代币的 ETH 价格构造路由比较用的 gas 折算
+    pub async fn gas_pricing(&self, fees: &FeeSuggestion, token: Address) -> Result<GasPricing> {
+        let token_info = self.get_token_info(token).await?;
+        let price_in_eth = self.get_price_in_eth(token, &token_info).await?;
+        if price_in_eth <= Decimal::ZERO {
+            bail!("{} 的 ETH 价格为 0，无法折算 gas", token_info.symbol);
+        }
+
+        Ok(GasPricing {
+            fee_per_gas: fees.expected_fee_per_gas(),
+            output_per_native: decimal_to_units(Decimal::ONE / price_in_eth, token_info.decimals)?,
+        })
+    }
+
+    /// 通过 eth_sendRawTransaction 广播已签名交易，并等待指定数量的确认
+    pub async fn send_raw_transaction(
+        &self,
+        raw: Bytes,
+        confirmations: usize,
+        wait_timeout: Duration,
+    ) -> Result<TransactionReceipt> {
+        let tx_hash = self.broadcast_raw_transaction(raw).await?;
+        let pending = PendingTransaction::new(tx_hash, self.provider.provider());
+        info!(tx = ?tx_hash, confirmations, "交易已广播，等待确认");
+
+        tokio::time::timeout(wait_timeout, pending.confirmations(confirmations))
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "交易 {:?} 已广播，但 {} 秒内未达到 {} 个确认",
+                    tx_hash,
+                    wait_timeout.as_secs(),
+                    confirmations
+                )
+            })?
+            .with_context(|| format!("等待交易 {:?} 确认失败", tx_hash))?
+            .ok_or_else(|| anyhow!("交易 {:?} 已被节点丢弃", tx_hash))
+    }
+
+    /// 通过 eth_sendRawTransaction 广播已签名交易，不等待确认
+    pub async fn broadcast_raw_transaction(&self, raw: Bytes) -> Result<H256> {
+        let tx_hash = H256::from(keccak256(&raw));
+        match self.provider.send_raw_transaction(raw).await {
+            Ok(_) => Ok(tx_hash),
+            // RPC 故障切换时交易可能已被前一个端点接收
+            Err(e) if e.to_string().contains("already known") => Ok(tx_hash),
+            Err(e) => Err(e).context("广播交易失败"),
+        }
+    }
+
+    /// 汇总回执中 `token` 转给 `recipient` 的 Transfer 数量
+    pub fn transferred_amount(
+        receipt: &TransactionReceipt,
+        token: Address,
+        recipient: Address,
+    ) -> U256 {
+        receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == token)
+            .filter_map(|log| parse_log::<TransferFilter>(log.clone()).ok())
+            .filter(|transfer| transfer.to == recipient)
+            .fold(U256::zero(), |total, transfer| total + transfer.value)
+    }
+
+    /// 交易所在区块前后钱包原生代币余额之差（加回 gas 花费），用于统计没有 Transfer 日志的原生代币到账
+    pub async fn native_received(&self, receipt: &TransactionReceipt, wallet: Address) -> Result<U256> {
+        let block = receipt
+            .block_number
+            .ok_or_else(|| anyhow!("交易回执缺少区块号"))?;
+        let (before, after) = try_join!(
+            self.provider.get_balance(wallet, Some((block - 1).into())),
+            self.provider.get_balance(wallet, Some(block.into())),
+        )
+        .context("查询原生代币余额失败")?;
+        let gas_cost = receipt.gas_used.unwrap_or_default()
+            * receipt.effective_gas_price.unwrap_or_default();
+        Ok((after + gas_cost).saturating_sub(before))
+    }
+
+    /// 开启仲裁读取时返回固定区块的仲裁视图，否则返回自身
+    async fn read_scope(&self) -> Result<(Cow<'_, Self>, Option<Arc<QuorumLog>>)> {
+        let pool: &RpcPool = (*self.provider).as_ref();
+        if pool.quorum().is_none() {
+            return Ok((Cow::Borrowed(self), None));
+        }
+
+        // 固定在最新区块的前一个区块，容忍节点之间一个区块的延迟
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .context("获取区块高度失败")?
+            .as_u64();
+        match pool.pin_quorum(head.saturating_sub(1)) {
+            Some((pinned, log)) => {
+                let client = Self {
+                    provider: Arc::new(Provider::new(pinned)),
+                    ..self.clone()
+                };
+                Ok((Cow::Owned(client), Some(log)))
+            }
+            None => Ok((Cow::Borrowed(self), None)),
+        }
+    }
+
+    pub fn provider(&self) -> Arc<PooledProvider> {
+        self.provider.clone()
+    }
+
+    pub fn wallet_address(&self) -> Option<Address> {
+        self.signer.as_ref().map(|signer| signer.address())
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.signer.is_none()
+    }
+
+    fn signer(&self) -> Result<&Arc<dyn TransactionSigner>> {
+        self.signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("当前为只读模式，未配置签名器"))
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn chain(&self) -> &ChainInfo {
+        &self.chain
+    }
+
+    async fn get_price_in_eth(&self, token: Address, token_info: &TokenInfo) -> Result<Decimal> {
+        let wrapped_native = self.chain.wrapped_native;
+        if token == wrapped_native || token == Address::zero() {
+            return Ok(dec!(1));
+        }
+
+        let amount_in = decimal_to_units(dec!(1), token_info.decimals)?;
+        let quote = self
+            .quote_single_route(token, wrapped_native, amount_in)
+            .await?;
+
+        units_to_decimal(quote.amount_out, 18)
+    }
+
+    async fn get_price_in_usd(&self, token: Address, token_info: &TokenInfo) -> Result<Decimal> {
+        let price_in_eth = self.get_price_in_eth(token, token_info).await?;
+        let eth_price_usd = self.get_eth_price_in_usd().await?;
+        Ok(price_in_eth * eth_price_usd)
+    }
+
+    pub async fn get_eth_price_in_usd(&self) -> Result<Decimal> {
+        let amount_in = U256::exp10(18);
+        let quote = self
+            .quote_single_route(self.chain.wrapped_native, self.chain.stable_token, amount_in)
+            .await?;
+
+        units_to_decimal(quote.amount_out, self.chain.stable_decimals)
+    }
+
+    /// V2（及 SushiSwap 等分叉）固定输入报价：直连路径和经由包装原生代币的路径
+    pub(crate) async fn uniswap_v2_routes(
+        &self,
+        protocol: SwapProtocol,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        let factory = self.v2_deployment(protocol)?.factory;
+        let paths = self.v2_candidate_paths(token_in, token_out);
+        let reserves = join_all(paths.iter().map(|path| self.v2_path_reserves(factory, path))).await;
+
+        let mut routes = Vec::new();
+        for (path, reserves) in paths.into_iter().zip(reserves) {
+            let Some(reserves) = reserves? else {
+                continue;
+            };
+            let Some(amount_out) = v2_amount_out(amount_in, &reserves) else {
+                continue;
+            };
+            routes.push(RouteLeg {
+                protocol,
+                path,
+                fees: Vec::new(),
+                amount_in,
+                amount_out,
+                pool: Some(PoolRef::UniswapV2 { reserves }),
+            });
+        }
+
+        Ok(routes)
+    }
+
+    /// V3 固定输入报价：各费率档位的直连池，以及经由中间代币的两跳路由
+    ///
+    /// 配置了 Factory 时读取池子状态在本地模拟，读取失败或需要的 tick 过多时改用 QuoterV2。
+    pub(crate) async fn uniswap_v3_routes(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Vec<RouteLeg> {
+        let Some(v3) = self.chain.uniswap_v3 else {
+            return Vec::new();
+        };
+        if let Some(factory) = v3.factory {
+            match self
+                .uniswap_v3_local_routes(factory, token_in, token_out, amount_in)
+                .await
+            {
+                Ok(routes) => return routes,
+                Err(err) => warn!("本地 V3 报价失败，改用 QuoterV2: {:#}", err),
+            }
+        }
+        self.uniswap_v3_quoter_routes(token_in, token_out, amount_in)
+            .await
+    }
+
+    /// 读取各候选池子的状态，在本地模拟直连和两跳路由
+    async fn uniswap_v3_local_routes(
+        &self,
+        factory: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        let intermediates: Vec<Address> = self
+            .chain
+            .intermediate_tokens()
+            .into_iter()
+            .filter(|token| *token != token_in && *token != token_out)
+            .collect();
+
+        // 每个 (代币对, 费率) 的池子只读取一次
+        let mut hops = vec![(token_in, token_out)];
+        for &middle in &intermediates {
+            hops.push((token_in, middle));
+            hops.push((middle, token_out));
+        }
+        let keys: Vec<(Address, Address, u32)> = hops
+            .iter()
+            .flat_map(|&(a, b)| V3_FEE_TIERS.iter().map(move |&fee| (a, b, fee)))
+            .collect();
+        let loaded = join_all(
+            keys.iter()
+                .map(|&(a, b, fee)| self.load_v3_pool(factory, a, b, fee)),
+        )
+        .await;
+        let mut pools = HashMap::new();
+        for (key, pool) in keys.into_iter().zip(loaded) {
+            if let Some(pool) = pool? {
+                pools.insert(key, pool);
+            }
+        }
+
+        let direct_path = [token_in, token_out];
+        let direct = join_all(
+            V3_FEE_TIERS
+                .iter()
+                .filter_map(|&fee| pools.get(&(token_in, token_out, fee)))
+                .map(|pool| self.simulate_v3_hop(pool, &direct_path, amount_in)),
+        )
+        .await;
+        let two_hop = join_all(intermediates.into_iter().map(|middle| {
+            self.local_v3_two_hop(&pools, token_in, middle, token_out, amount_in)
+        }))
+        .await;
+
+        let mut routes = Vec::new();
+        for hop in direct {
+            if let Some((pool, amount_out)) = hop? {
+                routes.push((direct_path.to_vec(), vec![pool], amount_out));
+            }
+        }
+        for route in two_hop {
+            if let Some(route) = route? {
+                routes.push(route);
+            }
+        }
+
+        Ok(routes
+            .into_iter()
+            .map(|(path, pools, amount_out)| RouteLeg {
+                protocol: SwapProtocol::UniswapV3,
+                path,
+                fees: pools.iter().map(|pool| pool.fee).collect(),
+                amount_in,
+                amount_out,
+                pool: Some(PoolRef::UniswapV3 { pools }),
+            })
+            .collect())
+    }
+
+    /// 经由中间代币的两跳路由：与 QuoterV2 路径相同，两跳互不影响，逐跳取最优费率
+    async fn local_v3_two_hop(
+        &self,
+        pools: &HashMap<(Address, Address, u32), V3Pool>,
+        token_in: Address,
+        middle: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Option<(Vec<Address>, Vec<V3Pool>, U256)>> {
+        let Some((first, middle_amount)) = self
+            .best_local_v3_hop(pools, token_in, middle, amount_in)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Some((second, amount_out)) = self
+            .best_local_v3_hop(pools, middle, token_out, middle_amount)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some((vec![token_in, middle, token_out], vec![first, second], amount_out)))
+    }
+
+    /// 单跳在所有费率档位的已读取池子中本地模拟输出最多的一个
+    async fn best_local_v3_hop(
+        &self,
+        pools: &HashMap<(Address, Address, u32), V3Pool>,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Option<(V3Pool, U256)>> {
+        let path = [token_in, token_out];
+        let hops = join_all(
+            V3_FEE_TIERS
+                .iter()
+                .filter_map(|&fee| pools.get(&(token_in, token_out, fee)))
+                .map(|pool| self.simulate_v3_hop(pool, &path, amount_in)),
+        )
+        .await;
+
+        let mut best: Option<(V3Pool, U256)> = None;
+        for hop in hops {
+            if let Some((pool, amount_out)) = hop? {
+                if best.as_ref().is_none_or(|(_, best_out)| amount_out > *best_out) {
+                    best = Some((pool, amount_out));
+                }
+            }
+        }
+        Ok(best)
+    }
+
+    /// 在池子状态的副本上模拟单跳，返回补充读取后的状态和输出
+    async fn simulate_v3_hop(
+        &self,
+        pool: &V3Pool,
+        path: &[Address],
+        amount_in: U256,
+    ) -> Result<Option<(V3Pool, U256)>> {
+        let mut pools = [pool.clone()];
+        let amount_out = self.simulate_v3_path(&mut pools, path, amount_in).await?;
+        let [pool] = pools;
+        Ok(amount_out.map(|amount_out| (pool, amount_out)))
+    }
+
+    /// 沿路径逐跳本地模拟 V3 固定输入兑换，缺少的 bitmap 字和 tick 会读取后写回 `pools`
+    pub(crate) async fn simulate_v3_path(
+        &self,
+        pools: &mut [V3Pool],
+        path: &[Address],
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        let mut amount = amount_in;
+        let mut reads = 0;
+        for (pool, hop) in pools.iter_mut().zip(path.windows(2)) {
+            let zero_for_one = hop[0] < hop[1];
+            let contract = UniswapV3Pool::new(pool.address, self.provider.clone());
+            amount = loop {
+                let missing = match pool.swap_exact_input(zero_for_one, amount) {
+                    Ok(Some(amount_out)) => break amount_out,
+                    Ok(None) => return Ok(None),
+                    Err(missing) => missing,
+                };
+                if reads >= V3_MAX_STATE_READS {
+                    bail!(
+                        "模拟 V3 池子 {} 需要读取的 tick 超过 {} 个",
+                        format_address(pool.address),
+                        V3_MAX_STATE_READS
+                    );
+                }
+                reads += 1;
+                match missing {
+                    MissingState::BitmapWord(word) => {
+                        let bitmap = contract.tick_bitmap(word).call().await?;
+                        pool.bitmap.insert(word, bitmap);
+                    }
+                    MissingState::Tick(tick) => {
+                        let (_, liquidity_net, ..) = contract.ticks(tick).call().await?;
+                        pool.liquidity_net.insert(tick, liquidity_net);
+                    }
+                }
+            };
+        }
+        Ok(Some(amount))
+    }
+
+    /// 读取 V3 池子的 slot0、活跃流动性和 tick 间距，池子不存在或未初始化时返回 None
+    async fn load_v3_pool(
+        &self,
+        factory: Address,
+        token_a: Address,
+        token_b: Address,
+        fee: u32,
+    ) -> Result<Option<V3Pool>> {
+        let factory = UniswapV3Factory::new(factory, self.provider.clone());
+        let address = factory.get_pool(token_a, token_b, fee).call().await?;
+        if address.is_zero() {
+            return Ok(None);
+        }
+
+        let pool = UniswapV3Pool::new(address, self.provider.clone());
+        let slot0 = pool.slot_0();
+        let liquidity = pool.liquidity();
+        let tick_spacing = pool.tick_spacing();
+        let ((sqrt_price_x96, tick, ..), liquidity, tick_spacing) =
+            try_join!(slot0.call(), liquidity.call(), tick_spacing.call())?;
+        if sqrt_price_x96.is_zero() {
+            return Ok(None);
+        }
+
+        Ok(Some(V3Pool {
+            address,
+            fee,
+            tick_spacing,
+            sqrt_price_x96,
+            tick,
+            liquidity,
+            bitmap: BTreeMap::new(),
+            liquidity_net: BTreeMap::new(),
+        }))
+    }
+
+    /// 通过 QuoterV2 按编码路径报价直连池和两跳路由
+    async fn uniswap_v3_quoter_routes(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Vec<RouteLeg> {
+
+        let direct = join_all(V3_FEE_TIERS.iter().map(|&fee| async move {
+            let path = vec![token_in, token_out];
+            let amount_out = self.quote_v3_path(&path, &[fee], amount_in).await?;
+            Some((path, vec![fee], amount_out))
+        }))
+        .await;
+
+        // 两跳路由的两个池子互不影响，且第二跳的输出随输入单调递增，
+        // 所以逐跳取最优费率就是该中间代币下所有费率组合中的最优解
+        let intermediates: Vec<Address> = self
+            .chain
+            .intermediate_tokens()
+            .into_iter()
+            .filter(|token| *token != token_in && *token != token_out)
+            .collect();
+        let two_hop = join_all(intermediates.into_iter().map(|middle| async move {
+            let (first_fee, middle_amount) = self.best_v3_hop(token_in, middle, amount_in).await?;
+            let (second_fee, _) = self.best_v3_hop(middle, token_out, middle_amount).await?;
+            let path = vec![token_in, middle, token_out];
+            let fees = vec![first_fee, second_fee];
+            let amount_out = self.quote_v3_path(&path, &fees, amount_in).await?;
+            Some((path, fees, amount_out))
+        }))
+        .await;
+
+        direct
+            .into_iter()
+            .chain(two_hop)
+            .flatten()
+            .map(|(path, fees, amount_out)| RouteLeg {
+                protocol: SwapProtocol::UniswapV3,
+                path,
+                fees,
+                amount_in,
+                amount_out,
+                pool: None,
+            })
+            .collect()
+    }
+
+    /// V4 固定输入报价：按 PoolKey 枚举各档位（及配置的 hooks）的池子，经 StateView 确认已初始化后用 V4Quoter 报价
+    pub(crate) async fn uniswap_v4_routes(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Vec<RouteLeg> {
+        let (Some(deployment), Some(_)) = (&self.chain.uniswap_v4, self.chain.universal_router)
+        else {
+            return Vec::new();
+        };
+        if amount_in > U256::from(u128::MAX) {
+            return Vec::new();
+        }
+
+        let hooks: Vec<Address> = std::iter::once(Address::zero())
+            .chain(deployment.hooks.iter().copied())
+            .collect();
+        let keys: Vec<PoolKey> = V4_POOL_TIERS
+            .iter()
+            .flat_map(|&(fee, tick_spacing)| {
+                hooks
+                    .iter()
+                    .map(move |&hooks| v4_pool_key(token_in, token_out, fee, tick_spacing, hooks))
+            })
+            .collect();
+
+        join_all(keys.into_iter().map(|key| async move {
+            if !self.v4_pool_initialized(&key).await {
+                return None;
+            }
+            let amount_out = self.quote_v4_pool(&key, token_in, amount_in).await?;
+            Some(RouteLeg {
+                protocol: SwapProtocol::UniswapV4,
+                path: vec![token_in, token_out],
+                fees: vec![key.fee],
+                amount_in,
+                amount_out,
+                pool: Some(PoolRef::UniswapV4(key)),
+            })
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// 池子未初始化时 StateView 返回的 sqrtPriceX96 为 0
+    async fn v4_pool_initialized(&self, key: &PoolKey) -> bool {
+        let Some(deployment) = &self.chain.uniswap_v4 else {
+            return false;
+        };
+        let state_view = UniswapV4StateView::new(deployment.state_view, self.provider.clone());
+        match state_view.get_slot_0(v4_pool_id(key)).call().await {
+            Ok((sqrt_price_x96, _, _, _)) => !sqrt_price_x96.is_zero(),
+            Err(_) => false,
+        }
+    }
+
+    /// V4Quoter 单池报价，池子不可用时返回 None
+    pub(crate) async fn quote_v4_pool(&self, key: &PoolKey, token_in: Address, amount_in: U256) -> Option<U256> {
+        let deployment = self.chain.uniswap_v4.as_ref()?;
+        let quoter = UniswapV4Quoter::new(deployment.quoter, self.provider.clone());
+        let params = QuoteExactSingleParams {
+            pool_key: key.clone(),
+            zero_for_one: token_in == key.currency_0,
+            exact_amount: u128::try_from(amount_in).ok()?,
+            hook_data: Bytes::new(),
+        };
+        quoter
+            .quote_exact_input_single(params)
+            .call()
+            .await
+            .ok()
+            .map(|(amount_out, _)| amount_out)
+            .filter(|amount| !amount.is_zero())
+    }
+
+    /// 按给定输入数量重新报价一条路由，路径不可用时返回 None
+    async fn quote_route(&self, route: &RouteLeg, amount_in: U256) -> Result<Option<U256>> {
+        match adapters_for(&self.chain)
+            .iter()
+            .find(|adapter| adapter.protocol() == route.protocol)
+        {
+            Some(adapter) => adapter.requote(self, route, amount_in).await,
+            None => bail!("{} 不能作为单条路由报价", route.protocol.as_str()),
+        }
+    }
+
+    /// 按路径上各交易对的当前储备本地计算 V2 类输出，路径不存在时返回 None
+    pub(crate) async fn quote_v2_path(
+        &self,
+        protocol: SwapProtocol,
+        path: &[Address],
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        let factory = self.v2_deployment(protocol)?.factory;
+        Ok(self
+            .v2_path_reserves(factory, path)
+            .await?
+            .and_then(|reserves| v2_amount_out(amount_in, &reserves)))
+    }
+
+    /// 路径上各交易对按兑换方向排列的储备，任一交易对不存在或没有流动性时返回 None
+    pub(crate) async fn v2_path_reserves(
+        &self,
+        factory: Address,
+        path: &[Address],
+    ) -> Result<Option<Vec<V2Reserves>>> {
+        let hops = join_all(
+            path.windows(2)
+                .map(|hop| self.v2_pair_reserves(factory, hop[0], hop[1])),
+        )
+        .await;
+        let hops = hops.into_iter().collect::<Result<Vec<_>>>()?;
+        Ok(hops.into_iter().collect())
+    }
+
+    async fn v2_pair_reserves(
+        &self,
+        factory: Address,
+        token_in: Address,
+        token_out: Address,
+    ) -> Result<Option<V2Reserves>> {
+        if token_in == token_out {
+            return Ok(None);
+        }
+        let factory = UniswapV2Factory::new(factory, self.provider.clone());
+        let pair = factory.get_pair(token_in, token_out).call().await?;
+        if pair.is_zero() {
+            return Ok(None);
+        }
+
+        let (reserve0, reserve1, _) = UniswapV2Pair::new(pair, self.provider.clone())
+            .get_reserves()
+            .call()
+            .await?;
+        if reserve0 == 0 || reserve1 == 0 {
+            return Ok(None);
+        }
+        // 交易对的 token0 是地址较小的一方
+        let (reserve_in, reserve_out) = if token_in < token_out {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        };
+        Ok(Some(V2Reserves {
+            reserve_in: U256::from(reserve_in),
+            reserve_out: U256::from(reserve_out),
+        }))
+    }
+
+    /// Curve 池子的 `get_dy` 报价
+    pub(crate) async fn quote_curve_pool(
+        &self,
+        pool: Address,
+        i: i128,
+        j: i128,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        let curve = CurveStableSwap::new(pool, self.provider.clone());
+        let amount_out = curve.get_dy(i, j, amount_in).call().await?;
+        Ok(Some(amount_out).filter(|amount| !amount.is_zero()))
+    }
+
+    /// Balancer Vault `queryBatchSwap` 单池报价，输出数量为 Vault 对输出代币的负 delta
+    pub(crate) async fn quote_balancer_pool(
+        &self,
+        pool_id: H256,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        let vault = BalancerVault::new(self.chain.balancer()?.vault, self.provider.clone());
+        let deltas = vault
+            .query_batch_swap(
+                BALANCER_GIVEN_IN,
+                vec![BatchSwapStep {
+                    pool_id: pool_id.0,
+                    asset_in_index: U256::zero(),
+                    asset_out_index: U256::one(),
+                    amount: amount_in,
+                    user_data: Bytes::new(),
+                }],
+                vec![token_in, token_out],
+                FundManagement {
+                    sender: Address::zero(),
+                    from_internal_balance: false,
+                    recipient: Address::zero(),
+                    to_internal_balance: false,
+                },
+            )
+            .call()
+            .await?;
+        Ok(deltas
+            .get(1)
+            .filter(|delta| delta.is_negative())
+            .map(|delta| delta.unsigned_abs()))
     }
 
-    pub fn chain_id(&self) -> u64 {
-        self.chain_id
-    }
+    /// 在输出最多的几条路由之间按百分比拆分输入，寻找总输出最多的组合
+    async fn quote_split_route(
+        &self,
+        routes: &[RouteLeg],
+        amount_in: U256,
+        token_in_decimals: u8,
+    ) -> Result<Option<SwapQuote>> {
+        let Some(router) = self.chain.swap_router02 else {
+            return Ok(None);
+        };
+        // SwapRouter02 只能执行 Uniswap V2 / V3 池子
+        let mut candidates: Vec<&RouteLeg> = routes
+            .iter()
+            .filter(|route| {
+                matches!(route.protocol, SwapProtocol::UniswapV2 | SwapProtocol::UniswapV3)
+            })
+            .collect();
+        candidates.sort_by_key(|route| std::cmp::Reverse(route.amount_out));
+        candidates.truncate(SPLIT_MAX_ROUTES);
+        if candidates.len() < 2 {
+            return Ok(None);
+        }
 
-    async fn get_price_in_eth(&self, token: Address, token_info: &TokenInfo) -> Result<Decimal> {
-        if token == *WETH_ADDRESS {
-            return Ok(dec!(1));
+        // quotes[r][k - 1]：路由 r 分得 k / SPLIT_STEPS 输入时的输出，100% 即已有报价
+        let quotes: Vec<Vec<Option<U256>>> = join_all(candidates.iter().map(|route| async move {
+            let mut outputs = join_all((1..SPLIT_STEPS).map(|step| {
+                let amount = amount_in * step / SPLIT_STEPS;
+                async move { self.quote_route(route, amount).await.ok().flatten() }
+            }))
+            .await;
+            outputs.push(Some(route.amount_out));
+            outputs
+        }))
+        .await;
+
+        let Some(allocation) = optimize_split(&quotes, SPLIT_STEPS, SPLIT_MAX_LEGS) else {
+            return Ok(None);
+        };
+        if allocation.len() < 2 {
+            return Ok(None);
         }
 
-        let amount_in = decimal_to_units(dec!(1), token_info.decimals)?;
-        let quote = self
-            .quote_best_swap(token, token_info.decimals, *WETH_ADDRESS, 18, amount_in)
-            .await?;
+        // 最后一条腿拿剩余部分，保证各腿输入之和精确等于 amount_in，再按实际数量重新报价
+        let mut legs = Vec::with_capacity(allocation.len());
+        let mut remaining = amount_in;
+        for (index, &(route, steps)) in allocation.iter().enumerate() {
+            let leg_in = if index + 1 == allocation.len() {
+                remaining
+            } else {
+                amount_in * steps / SPLIT_STEPS
+            };
+            remaining -= leg_in;
+            let Some(leg_out) = self.quote_route(candidates[route], leg_in).await? else {
+                return Ok(None);
+            };
+            legs.push(RouteLeg {
+                amount_in: leg_in,
+                amount_out: leg_out,
+                ..candidates[route].clone()
+            });
+        }
 
-        units_to_decimal(quote.amount_out, 18)
+        let amount_out = legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_out);
+        let mut weighted_impact = Decimal::ZERO;
+        let mut weighted_fee = Decimal::ZERO;
+        for leg in &legs {
+            let impact = self.estimate_route_impact(leg).await?;
+            let weight = units_to_decimal(leg.amount_in, token_in_decimals)?;
+            weighted_impact += impact.price_impact_pct * weight;
+            weighted_fee += impact.lp_fee_pct * weight;
+        }
+        let total_in = units_to_decimal(amount_in, token_in_decimals)?;
+
+        let first = &legs[0];
+        Ok(Some(SwapQuote {
+            protocol: SwapProtocol::Split,
+            mode: SwapMode::ExactInput,
+            router,
+            token_in: first.path[0],
+            token_out: first.path[first.path.len() - 1],
+            amount_in,
+            amount_out,
+            path: vec![first.path[0], first.path[first.path.len() - 1]],
+            fees: Vec::new(),
+            price_impact_pct: (weighted_impact / total_in).round_dp(6),
+            lp_fee_pct: (weighted_fee / total_in).round_dp(6),
+            legs,
+            pool: None,
+        }))
     }
 
-    async fn get_price_in_usd(&self, token: Address, token_info: &TokenInfo) -> Result<Decimal> {
-        let price_in_eth = self.get_price_in_eth(token, token_info).await?;
-        let eth_price_usd = self.get_eth_price_in_usd().await?;
-        Ok(price_in_eth * eth_price_usd)
+    /// 单条路由相对中间价的价格影响和 LP 手续费，无法取得池子价格时返回错误而不是 0%
+    async fn estimate_route_impact(&self, route: &RouteLeg) -> Result<PriceImpact> {
+        let impact = match (route.protocol, &route.pool) {
+            (SwapProtocol::UniswapV2 | SwapProtocol::SushiSwap, pool) => {
+                let reserves = match pool {
+                    Some(PoolRef::UniswapV2 { reserves }) => reserves.clone(),
+                    _ => self
+                        .v2_path_reserves(self.v2_deployment(route.protocol)?.factory, &route.path)
+                        .await?
+                        .ok_or_else(|| anyhow!("路径上的交易对不存在，无法计算价格影响"))?,
+                };
+                v2_price_impact(route.amount_in, route.amount_out, &reserves)
+            }
+            (SwapProtocol::UniswapV3, Some(PoolRef::UniswapV3 { pools })) => {
+                let mid_price = pools
+                    .iter()
+                    .zip(route.path.windows(2))
+                    .map(|(pool, hop)| pool.mid_price(hop[0] < hop[1]))
+                    .product();
+                let fees = v3_fee_fractions(&route.fees);
+                mid_price_impact(route.amount_in, route.amount_out, mid_price, &fees)
+            }
+            (SwapProtocol::UniswapV3, _) => {
+                let mid_price = self.v3_path_mid_price(&route.path, &route.fees).await?;
+                let fees = v3_fee_fractions(&route.fees);
+                mid_price_impact(route.amount_in, route.amount_out, mid_price, &fees)
+            }
+            (SwapProtocol::UniswapV4, Some(PoolRef::UniswapV4(key))) => {
+                let (mid_price, lp_fee) = self.v4_mid_price(key, route.path[0]).await?;
+                let fees = v3_fee_fractions(&[lp_fee]);
+                mid_price_impact(route.amount_in, route.amount_out, mid_price, &fees)
+            }
+            (SwapProtocol::Curve | SwapProtocol::BalancerV2, _) => {
+                return self.estimate_price_impact_sampled(route).await;
+            }
+            (other, _) => bail!("{} 的价格影响按各腿加权计算", other.as_str()),
+        };
+        impact.ok_or_else(|| anyhow!("成交数量或池子价格异常，无法计算价格影响"))
     }
 
-    async fn get_eth_price_in_usd(&self) -> Result<Decimal> {
-        let amount_in = U256::exp10(18);
-        let quote = self
-            .quote_best_swap(*WETH_ADDRESS, 18, *USDC_ADDRESS, 6, amount_in)
-            .await?;
+    /// 路由实际调用（也是授权对象）的合约；Curve 路由为具体的池子
+    fn route_router(&self, route: &RouteLeg) -> Result<Address> {
+        match (&route.pool, route.protocol) {
+            (Some(PoolRef::Curve { pool, .. }), _) => Ok(*pool),
+            (_, protocol) => self.router_for(protocol),
+        }
+    }
 
-        units_to_decimal(quote.amount_out, 6)
+    fn router_for(&self, protocol: SwapProtocol) -> Result<Address> {
+        match protocol {
+            SwapProtocol::UniswapV2 => Ok(self.chain.uniswap_v2()?.router),
+            SwapProtocol::SushiSwap => Ok(self.chain.sushiswap()?.router),
+            SwapProtocol::BalancerV2 => Ok(self.chain.balancer()?.vault),
+            SwapProtocol::Curve => bail!("Curve 路由直接调用池子，没有统一的路由合约"),
+            SwapProtocol::UniswapV3 => Ok(self.chain.uniswap_v3()?.router),
+            SwapProtocol::Split => self
+                .chain
+                .swap_router02
+                .ok_or_else(|| anyhow!("链 {} 未配置 SwapRouter02", self.chain.name)),
+            SwapProtocol::UniversalRouter | SwapProtocol::UniswapV4 => self
+                .chain
+                .universal_router
+                .ok_or_else(|| anyhow!("链 {} 未配置 Universal Router", self.chain.name)),
+        }
     }
 
-    async fn quote_uniswap_v2(
+    /// 单跳在所有费率档位中输出最多的池子
+    async fn best_v3_hop(
         &self,
         token_in: Address,
-        token_in_decimals: u8,
         token_out: Address,
-        token_out_decimals: u8,
         amount_in: U256,
+    ) -> Option<(u32, U256)> {
+        join_all(V3_FEE_TIERS.iter().map(|&fee| async move {
+            self.quote_v3_path(&[token_in, token_out], &[fee], amount_in)
+                .await
+                .map(|amount_out| (fee, amount_out))
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .max_by_key(|(_, amount_out)| *amount_out)
+    }
+
+    /// QuoterV2 `quoteExactInput`，池子不存在或流动性不足时返回 None
+    pub(crate) async fn quote_v3_path(&self, path: &[Address], fees: &[u32], amount_in: U256) -> Option<U256> {
+        let quoter_v2 = self.chain.uniswap_v3.as_ref()?.quoter_v2;
+        let quoter = UniswapV3QuoterV2::new(quoter_v2, self.provider.clone());
+        let encoded = encode_v3_path(path, fees).ok()?;
+        match quoter.quote_exact_input(encoded, amount_in).call().await {
+            Ok((amount_out, _, _, _)) if !amount_out.is_zero() => Some(amount_out),
+            _ => None,
+        }
+    }
+
+    async fn quote_uniswap_v2_exact_output(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_out: U256,
     ) -> Result<Option<SwapQuote>> {
-        let router = UniswapV2Router::new(*UNISWAP_V2_ROUTER, self.provider.clone());
+        let Some(v2) = self.chain.uniswap_v2 else {
+            return Ok(None);
+        };
         let mut best: Option<SwapQuote> = None;
 
-        for path in self.v2_candidate_paths(token_in, token_out).await? {
-            let amounts = match router.get_amounts_out(amount_in, path.clone()).call().await {
-                Ok(amounts) => amounts,
-                Err(err) => {
-                    if is_bad_path_error(&err) {
-                        continue;
-                    }
-                    return Err(err.into());
-                }
+        for path in self.v2_candidate_paths(token_in, token_out) {
+            let Some(reserves) = self.v2_path_reserves(v2.factory, &path).await? else {
+                continue;
             };
+            let Some(amount_in) = v2_amount_in(amount_out, &reserves) else {
+                continue;
+            };
+            let impact = v2_price_impact(amount_in, amount_out, &reserves)
+                .ok_or_else(|| anyhow!("交易对储备异常，无法计算价格影响"))?;
 
-            if let Some(amount_out) = amounts.last() {
-                let price_impact = self
-                    .estimate_price_impact_v2(
-                        token_in_decimals,
-                        token_out_decimals,
-                        amount_in,
-                        *amount_out,
-                        path.clone(),
-                    )
-                    .await?;
-
-                let quote = SwapQuote {
-                    protocol: SwapProtocol::UniswapV2,
-                    router: *UNISWAP_V2_ROUTER,
-                    token_in,
-                    token_out,
-                    amount_in,
-                    amount_out: *amount_out,
-                    path: path.clone(),
-                    fee: None,
-                    price_impact_pct: price_impact,
-                };
+            let quote = SwapQuote {
+                protocol: SwapProtocol::UniswapV2,
+                mode: SwapMode::ExactOutput,
+                router: v2.router,
+                token_in,
+                token_out,
+                amount_in,
+                amount_out,
+                path,
+                fees: Vec::new(),
+                price_impact_pct: impact.price_impact_pct,
+                lp_fee_pct: impact.lp_fee_pct,
+                legs: Vec::new(),
+                pool: Some(PoolRef::UniswapV2 { reserves }),
+            };
 
-                if best
-                    .as_ref()
-                    .is_none_or(|q| quote.amount_out > q.amount_out)
-                {
-                    best = Some(quote);
-                }
+            if best.as_ref().is_none_or(|q| quote.amount_in < q.amount_in) {
+                best = Some(quote);
             }
         }
 
         Ok(best)
     }
 
-    async fn quote_uniswap_v3(
+    async fn quote_uniswap_v3_exact_output(
         &self,
         token_in: Address,
-        token_in_decimals: u8,
         token_out: Address,
-        token_out_decimals: u8,
-        amount_in: U256,
+        amount_out: U256,
     ) -> Result<Option<SwapQuote>> {
-        let quoter = UniswapV3Quoter::new(*UNISWAP_V3_QUOTER, self.provider.clone());
+        let Some(v3) = self.chain.uniswap_v3 else {
+            return Ok(None);
+        };
+        let quoter = UniswapV3Quoter::new(v3.quoter, self.provider.clone());
         let mut best: Option<SwapQuote> = None;
-        for &fee in &[500_u32, 3000_u32, 10000_u32] {
+        for &fee in V3_FEE_TIERS {
             match quoter
-                .quote_exact_input_single(token_in, token_out, fee, amount_in, U256::zero())
+                .quote_exact_output_single(token_in, token_out, fee, amount_out, U256::zero())
                 .call()
                 .await
             {
-                Ok(amount_out) if !amount_out.is_zero() => {
-                    let price_impact = self
-                        .estimate_price_impact_v3(
-                            token_in_decimals,
-                            token_out_decimals,
-                            amount_in,
-                            amount_out,
-                            token_in,
-                            token_out,
-                            fee,
-                        )
-                        .await?;
+                Ok(amount_in) if !amount_in.is_zero() => {
+                    let mid_price = self.v3_path_mid_price(&[token_in, token_out], &[fee]).await?;
+                    let fees = v3_fee_fractions(&[fee]);
+                    let impact = mid_price_impact(amount_in, amount_out, mid_price, &fees)
+                        .ok_or_else(|| anyhow!("成交数量或池子价格异常，无法计算价格影响"))?;
 
                     let quote = SwapQuote {
                         protocol: SwapProtocol::UniswapV3,
-                        router: *UNISWAP_V3_ROUTER,
+                        mode: SwapMode::ExactOutput,
+                        router: v3.router,
                         token_in,
                         token_out,
                         amount_in,
                         amount_out,
                         path: vec![token_in, token_out],
-                        fee: Some(fee),
-                        price_impact_pct: price_impact,
+                        fees: vec![fee],
+                        price_impact_pct: impact.price_impact_pct,
+                        lp_fee_pct: impact.lp_fee_pct,
+                        legs: Vec::new(),
+                        pool: None,
                     };
 
-                    if best.as_ref().is_none_or(|q| amount_out > q.amount_out) {
+                    if best.as_ref().is_none_or(|q| amount_in < q.amount_in) {
                         best = Some(quote);
                     }
                 }
@@ -578,111 +2394,96 @@ impl EthereumClient {
         Ok(best)
     }
 
-    async fn v2_candidate_paths(
-        &self,
-        token_in: Address,
-        token_out: Address,
-    ) -> Result<Vec<Vec<Address>>> {
-        let mut paths = Vec::new();
-
-        if self.v2_pair_exists(token_in, token_out).await? {
-            paths.push(vec![token_in, token_out]);
-        }
-
-        if token_in != *WETH_ADDRESS 
-            && token_out != *WETH_ADDRESS
-            && self.v2_pair_exists(token_in, *WETH_ADDRESS).await?
-            && self.v2_pair_exists(*WETH_ADDRESS, token_out).await?
-        {
-            paths.push(vec![token_in, *WETH_ADDRESS, token_out]);
+    /// V2 候选路径：直连，以及经由包装原生代币的两跳（交易对是否存在由储备查询判断）
+    fn v2_candidate_paths(&self, token_in: Address, token_out: Address) -> Vec<Vec<Address>> {
+        let wrapped_native = self.chain.wrapped_native;
+        let mut paths = vec![vec![token_in, token_out]];
+        if token_in != wrapped_native && token_out != wrapped_native {
+            paths.push(vec![token_in, wrapped_native, token_out]);
         }
-
-        Ok(paths)
+        paths
     }
 
-    async fn v2_pair_exists(&self, token_a: Address, token_b: Address) -> Result<bool> {
-        if token_a == token_b {
-            return Ok(false);
+    /// 经 Factory 定位路径上的各跳池子，按 slot0 的 sqrtPriceX96 计算中间价之积
+    async fn v3_path_mid_price(&self, path: &[Address], fees: &[u32]) -> Result<f64> {
+        let factory = self.chain.uniswap_v3()?.factory.ok_or_else(|| {
+            anyhow!("链 {} 未配置 Uniswap V3 Factory，无法读取池子价格", self.chain.name)
+        })?;
+        let factory = UniswapV3Factory::new(factory, self.provider.clone());
+
+        let mut mid_price = 1.0;
+        for (hop, &fee) in path.windows(2).zip(fees) {
+            let pool = factory.get_pool(hop[0], hop[1], fee).call().await?;
+            if pool.is_zero() {
+                bail!(
+                    "V3 池子 {}/{} ({}) 不存在",
+                    format_address(hop[0]),
+                    format_address(hop[1]),
+                    fee
+                );
+            }
+            let (sqrt_price_x96, ..) = UniswapV3Pool::new(pool, self.provider.clone())
+                .slot_0()
+                .call()
+                .await?;
+            mid_price *= sqrt_price_to_price(sqrt_price_x96, hop[0] < hop[1]);
         }
-        let factory = UniswapV2Factory::new(*UNISWAP_V2_FACTORY, self.provider.clone());
-        let pair = factory.get_pair(token_a, token_b).call().await?;
-        Ok(pair != Address::zero())
+        Ok(mid_price)
     }
 
-    async fn estimate_price_impact_v2(
-        &self,
-        token_in_decimals: u8,
-        token_out_decimals: u8,
-        amount_in: U256,
-        amount_out: U256,
-        path: Vec<Address>,
-    ) -> Result<Decimal> {
-        let sample_in = sample_amount(amount_in);
-        if sample_in == amount_in || sample_in.is_zero() {
-            return Ok(Decimal::ZERO);
-        }
-
-        let router = UniswapV2Router::new(*UNISWAP_V2_ROUTER, self.provider.clone());
-        let sample_out = router
-            .get_amounts_out(sample_in, path)
-            .call()
-            .await
-            .unwrap_or_default();
+    /// V4 池子的中间价和当前 LP 费率（动态费率池以 slot0 中的值为准）
+    async fn v4_mid_price(&self, key: &PoolKey, token_in: Address) -> Result<(f64, u32)> {
+        let deployment = self.chain.uniswap_v4()?;
+        let state_view = UniswapV4StateView::new(deployment.state_view, self.provider.clone());
+        let (sqrt_price_x96, _, _, lp_fee) = state_view.get_slot_0(v4_pool_id(key)).call().await?;
+        Ok((sqrt_price_to_price(sqrt_price_x96, token_in == key.currency_0), lp_fee))
+    }
 
-        let sample_last = match sample_out.last() {
-            Some(value) => *value,
-            None => return Ok(Decimal::ZERO),
+    /// Curve / Balancer 没有统一的现价接口：以 1% 输入的报价扣除池子手续费后作为中间价
+    async fn estimate_price_impact_sampled(&self, route: &RouteLeg) -> Result<PriceImpact> {
+        let lp_fee = self.pool_fee_fraction(route).await?;
+        let sample_in = sample_amount(route.amount_in);
+        let sample_out = if sample_in == route.amount_in {
+            route.amount_out
+        } else {
+            self.quote_route(route, sample_in).await?.ok_or_else(|| {
+                anyhow!("{} 路由无法按 1% 输入报价，无法计算价格影响", route.protocol.as_str())
+            })?
         };
 
-        let spot_price = units_to_decimal(sample_last, token_out_decimals)?
-            / units_to_decimal(sample_in, token_in_decimals)?;
-        if spot_price.is_zero() {
-            return Ok(Decimal::ZERO);
-        }
-
-        let executed_price = units_to_decimal(amount_out, token_out_decimals)?
-            / units_to_decimal(amount_in, token_in_decimals)?;
-
-        Ok(((spot_price - executed_price) / spot_price).abs() * dec!(100))
+        let mid_price = u256_to_f64(sample_out) / u256_to_f64(sample_in) / (1.0 - lp_fee);
+        mid_price_impact(route.amount_in, route.amount_out, mid_price, &[lp_fee])
+            .ok_or_else(|| anyhow!("成交数量或池子价格异常，无法计算价格影响"))
     }
 
-    #[allow(clippy::too_many_arguments)]
-    async fn estimate_price_impact_v3(
-        &self,
-        token_in_decimals: u8,
-        token_out_decimals: u8,
-        amount_in: U256,
-        amount_out: U256,
-        token_in: Address,
-        token_out: Address,
-        fee: u32,
-    ) -> Result<Decimal> {
-        let sample_in = sample_amount(amount_in);
-        if sample_in == amount_in || sample_in.is_zero() {
-            return Ok(Decimal::ZERO);
-        }
-
-        let quoter = UniswapV3Quoter::new(*UNISWAP_V3_QUOTER, self.provider.clone());
-        let sample_out = quoter
-            .quote_exact_input_single(token_in, token_out, fee, sample_in, U256::zero())
-            .call()
-            .await
-            .unwrap_or(U256::zero());
-
-        if sample_out.is_zero() {
-            return Ok(Decimal::ZERO);
+    /// Curve / Balancer 池子当前的交易手续费比例
+    async fn pool_fee_fraction(&self, route: &RouteLeg) -> Result<f64> {
+        match &route.pool {
+            // Curve 的 fee 以 1e10 为分母
+            Some(PoolRef::Curve { pool, .. }) => {
+                let fee = CurveStableSwap::new(*pool, self.provider.clone()).fee().call().await?;
+                Ok(u256_to_f64(fee) / 1e10)
+            }
+            // Balancer 池子 ID 的前 20 字节是池子合约地址，费率以 1e18 为分母
+            Some(PoolRef::Balancer { pool_id }) => {
+                let pool = Address::from_slice(&pool_id[..20]);
+                let fee = BalancerPool::new(pool, self.provider.clone())
+                    .get_swap_fee_percentage()
+                    .call()
+                    .await?;
+                Ok(u256_to_f64(fee) / 1e18)
+            }
+            _ => bail!("{} 路由缺少池子信息，无法读取手续费", route.protocol.as_str()),
         }
+    }
 
-        let spot_price = units_to_decimal(sample_out, token_out_decimals)?
-            / units_to_decimal(sample_in, token_in_decimals)?;
-        if spot_price.is_zero() {
-            return Ok(Decimal::ZERO);
+    /// Uniswap V2 及其分叉的部署
+    fn v2_deployment(&self, protocol: SwapProtocol) -> Result<UniswapV2Deployment> {
+        match protocol {
+            SwapProtocol::UniswapV2 => self.chain.uniswap_v2(),
+            SwapProtocol::SushiSwap => self.chain.sushiswap(),
+            other => bail!("{} 不是 V2 类协议", other.as_str()),
         }
-
-        let executed_price = units_to_decimal(amount_out, token_out_decimals)?
-            / units_to_decimal(amount_in, token_in_decimals)?;
-
-        Ok(((spot_price - executed_price) / spot_price).abs() * dec!(100))
     }
 
     fn deadline_after(&self, seconds: u64) -> Result<U256> {
@@ -694,13 +2495,144 @@ impl EthereumClient {
         Ok(U256::from(deadline))
     }
 
+    /// 报价和路由使用的地址：原生代币（0x0）映射为包装代币
+    pub fn routable(&self, token: Address) -> Address {
+        if token == Address::zero() {
+            self.chain.wrapped_native
+        } else {
+            token
+        }
+    }
+
     fn resolve_token_address(&self, symbol: &str) -> Option<Address> {
-        let key = symbol.to_ascii_uppercase();
-        TOKEN_SYMBOLS.get(key.as_str()).copied()
+        self.chain.resolve_token(symbol)
+    }
+}
+
+/// 在候选路由之间分配 `steps` 份输入，返回总输出最多的分配 `(路由下标, 份数)`
+///
+/// `quotes[r][k - 1]` 为路由 r 分得 k 份时的输出（None 表示该数量无法成交），
+/// 最多使用 `max_legs` 条路由，所有份数之和等于 `steps`。
+pub(crate) fn optimize_split(
+    quotes: &[Vec<Option<U256>>],
+    steps: u64,
+    max_legs: usize,
+) -> Option<Vec<(usize, u64)>> {
+    fn search(
+        quotes: &[Vec<Option<U256>>],
+        route: usize,
+        remaining: u64,
+        legs_left: usize,
+    ) -> Option<(U256, Vec<(usize, u64)>)> {
+        if remaining == 0 {
+            return Some((U256::zero(), Vec::new()));
+        }
+        if route == quotes.len() || legs_left == 0 {
+            return None;
+        }
+
+        // 当前路由不分配
+        let mut best = search(quotes, route + 1, remaining, legs_left);
+        for take in 1..=remaining {
+            let Some(Some(output)) = quotes[route].get(take as usize - 1) else {
+                continue;
+            };
+            if let Some((rest, mut allocation)) =
+                search(quotes, route + 1, remaining - take, legs_left - 1)
+            {
+                let total = rest + *output;
+                if best.as_ref().is_none_or(|(current, _)| total > *current) {
+                    allocation.insert(0, (route, take));
+                    best = Some((total, allocation));
+                }
+            }
+        }
+        best
+    }
+
+    search(quotes, 0, steps, max_legs).map(|(_, allocation)| allocation)
+}
+
+/// 按 V3 路由格式编码路径：`token (20 字节) | fee (3 字节) | token | ...`
+pub(crate) fn encode_v3_path(tokens: &[Address], fees: &[u32]) -> Result<Bytes> {
+    if tokens.len() < 2 || fees.len() != tokens.len() - 1 {
+        bail!("V3 路径需要 n 个代币和 n-1 个费率");
+    }
+
+    let mut encoded = Vec::with_capacity(tokens.len() * 20 + fees.len() * 3);
+    for (index, token) in tokens.iter().enumerate() {
+        encoded.extend_from_slice(token.as_bytes());
+        if let Some(fee) = fees.get(index) {
+            if *fee >= 1 << 24 {
+                bail!("无效的 V3 费率: {}", fee);
+            }
+            encoded.extend_from_slice(&fee.to_be_bytes()[1..]);
+        }
+    }
+    Ok(Bytes::from(encoded))
+}
+
+/// 构造 V4 PoolKey，两种货币按地址升序排列（原生代币 0x0 总是 currency0）
+pub(crate) fn v4_pool_key(
+    token_a: Address,
+    token_b: Address,
+    fee: u32,
+    tick_spacing: i32,
+    hooks: Address,
+) -> PoolKey {
+    let (currency_0, currency_1) = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+    PoolKey {
+        currency_0,
+        currency_1,
+        fee,
+        tick_spacing,
+        hooks,
     }
 }
 
-fn format_address(address: Address) -> String {
+/// PoolManager 中的池子 ID：`keccak256(abi.encode(poolKey))`
+pub(crate) fn v4_pool_id(key: &PoolKey) -> [u8; 32] {
+    keccak256(key.clone().encode())
+}
+
+/// 按 gas 模型估算执行这些腿（单一路由时为一条）所需的 gas
+pub(crate) fn modelled_swap_gas(legs: &[RouteLeg]) -> u64 {
+    let legs_gas: u64 = legs
+        .iter()
+        .map(|leg| {
+            let hops = leg.path.len().saturating_sub(1).max(1) as u64;
+            match leg.protocol {
+                SwapProtocol::UniswapV2 | SwapProtocol::SushiSwap => V2_HOP_GAS * hops,
+                SwapProtocol::UniswapV3 | SwapProtocol::UniswapV4 => V3_HOP_GAS * hops,
+                SwapProtocol::Curve => CURVE_SWAP_GAS,
+                SwapProtocol::BalancerV2 => BALANCER_SWAP_GAS,
+                // 组合路由由各条腿分别计入
+                SwapProtocol::Split | SwapProtocol::UniversalRouter => 0,
+            }
+        })
+        .sum();
+    SWAP_BASE_GAS + legs_gas
+}
+
+/// 固定输出候选中总花费最少的报价：所需输入加上折算为输入代币的 gas
+fn cheapest_exact_output(
+    candidates: Vec<SwapQuote>,
+    gas_pricing: Option<GasPricing>,
+) -> Option<SwapQuote> {
+    let total_input = |quote: &SwapQuote| match gas_pricing {
+        Some(pricing) => quote
+            .amount_in
+            .saturating_add(pricing.cost_in_output(modelled_swap_gas(&quote.route_legs()))),
+        None => quote.amount_in,
+    };
+    candidates.into_iter().min_by_key(total_input)
+}
+
+pub(crate) fn format_address(address: Address) -> String {
     format!("0x{:x}", address)
 }
 
@@ -740,8 +2672,537 @@ fn current_timestamp() -> Result<u64> {
     Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
 }
 
-fn is_bad_path_error(error: &ethers::contract::ContractError<ethers::providers::Provider<ethers::providers::Http>>) -> bool {
-    error
-        .to_string()
-        .contains("UniswapV2Library: INSUFFICIENT_LIQUIDITY")
+/// 与 `UniswapV2Library.getAmountOut` 相同的整数运算（0.3% 手续费），沿路径逐跳计算
+pub(crate) fn v2_amount_out(amount_in: U256, reserves: &[V2Reserves]) -> Option<U256> {
+    reserves.iter().try_fold(amount_in, |amount, hop| {
+        if amount.is_zero() || hop.reserve_in.is_zero() || hop.reserve_out.is_zero() {
+            return None;
+        }
+        let amount_with_fee = amount.checked_mul(U256::from(997))?;
+        let numerator = amount_with_fee.checked_mul(hop.reserve_out)?;
+        let denominator = hop
+            .reserve_in
+            .checked_mul(U256::from(1000))?
+            .checked_add(amount_with_fee)?;
+        Some(numerator / denominator).filter(|out| !out.is_zero())
+    })
+}
+
+/// 与 `UniswapV2Library.getAmountIn` 相同的整数运算，从路径末端倒推所需输入
+pub(crate) fn v2_amount_in(amount_out: U256, reserves: &[V2Reserves]) -> Option<U256> {
+    reserves.iter().rev().try_fold(amount_out, |amount, hop| {
+        if amount.is_zero() || amount >= hop.reserve_out || hop.reserve_in.is_zero() {
+            return None;
+        }
+        let numerator = hop
+            .reserve_in
+            .checked_mul(amount)?
+            .checked_mul(U256::from(1000))?;
+        let denominator = (hop.reserve_out - amount).checked_mul(U256::from(997))?;
+        Some(numerator / denominator + 1)
+    })
+}
+
+/// V2 路径相对中间价（各跳储备比之积）的价格影响，每跳 0.3% 手续费单独统计
+pub(crate) fn v2_price_impact(
+    amount_in: U256,
+    amount_out: U256,
+    reserves: &[V2Reserves],
+) -> Option<PriceImpact> {
+    if reserves.is_empty() {
+        return None;
+    }
+    // 储备最大为 uint112，超出 Decimal 的表示范围，比值用 f64 计算
+    let mid_price = reserves
+        .iter()
+        .map(|hop| u256_to_f64(hop.reserve_out) / u256_to_f64(hop.reserve_in))
+        .product();
+    mid_price_impact(amount_in, amount_out, mid_price, &vec![V2_FEE; reserves.len()])
+}
+
+/// V3 / V4 以百万分之一为单位的费率转为比例
+fn v3_fee_fractions(fees: &[u32]) -> Vec<f64> {
+    fees.iter().map(|&fee| fee as f64 / 1e6).collect()
+}
+
+/// 成交价（输出 / 输入，最小单位）相对中间价的偏离，拆成 LP 手续费和扣除手续费后的价格影响
+///
+/// `fees` 为各跳手续费比例；价格影响以扣除手续费后的中间价为基准，只反映流动性深度带来的滑移。
+pub(crate) fn mid_price_impact(
+    amount_in: U256,
+    amount_out: U256,
+    mid_price: f64,
+    fees: &[f64],
+) -> Option<PriceImpact> {
+    if amount_in.is_zero() || amount_out.is_zero() || !mid_price.is_finite() || mid_price <= 0.0 {
+        return None;
+    }
+    let retained: f64 = fees.iter().map(|fee| 1.0 - fee).product();
+    let executed_price = u256_to_f64(amount_out) / u256_to_f64(amount_in);
+    // 整数取整可能让极小额成交略优于理论价格，按 0 计
+    let price_impact = (1.0 - executed_price / (mid_price * retained)).max(0.0) * 100.0;
+    Some(PriceImpact {
+        price_impact_pct: Decimal::from_f64(price_impact)?.round_dp(6),
+        lp_fee_pct: Decimal::from_f64((1.0 - retained) * 100.0)?.round_dp(6),
+    })
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::INFINITY)
+}
+
+/// 连接本地桩 RPC（以太坊主网）并带测试私钥的客户端，用于检查构造出的交易
+#[cfg(test)]
+pub(crate) async fn test_signing_client() -> EthereumClient {
+    let url = crate::ethereum::rpc::spawn_stub_rpc(1, "0x0").await;
+    let signer = crate::ethereum::signer::LocalSigner::from_private_key(
+        "0x1234567890123456789012345678901234567890123456789012345678901234",
+    )
+    .unwrap();
+    EthereumClient::new(url, Some(Arc::new(signer)), &BTreeMap::new())
+        .await
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{abi::AbiEncode, types::Log};
+
+    fn transfer_log(token: Address, from: Address, to: Address, value: u64) -> Log {
+        Log {
+            address: token,
+            topics: vec![
+                H256::from(keccak256("Transfer(address,address,uint256)")),
+                H256::from(from),
+                H256::from(to),
+            ],
+            data: Bytes::from(U256::from(value).encode()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn transferred_amount_sums_transfers_to_recipient() {
+        let token = Address::from_low_u64_be(1);
+        let other_token = Address::from_low_u64_be(2);
+        let pool = Address::from_low_u64_be(3);
+        let wallet = Address::from_low_u64_be(4);
+
+        let receipt = TransactionReceipt {
+            logs: vec![
+                transfer_log(token, pool, wallet, 700),
+                transfer_log(token, pool, wallet, 300),
+                // 输入代币转出、其他代币的转账都不计入
+                transfer_log(token, wallet, pool, 50),
+                transfer_log(other_token, pool, wallet, 999),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            EthereumClient::transferred_amount(&receipt, token, wallet),
+            U256::from(1000)
+        );
+    }
+
+    #[test]
+    fn encodes_v3_path_with_packed_fees() {
+        let weth = Address::from_str("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2").unwrap();
+        let usdc = Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap();
+        let dai = Address::from_str("0x6B175474E89094C44Da98b954EedeAC495271d0F").unwrap();
+
+        let encoded = encode_v3_path(&[weth, usdc, dai], &[500, 100]).unwrap();
+        assert_eq!(
+            hex::encode(&encoded),
+            concat!(
+                "c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                "0001f4",
+                "a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "000064",
+                "6b175474e89094c44da98b954eedeac495271d0f"
+            )
+        );
+
+        assert!(encode_v3_path(&[weth, usdc], &[500, 3000]).is_err());
+        assert!(encode_v3_path(&[weth, usdc], &[1 << 24]).is_err());
+    }
+
+    #[test]
+    fn v3_router_calls_use_struct_selectors() {
+        use ethers::contract::EthCall;
+
+        // SwapRouter 的参数是结构体，选择器按 tuple 签名计算
+        assert_eq!(
+            uniswap_v3_router::ExactInputCall::selector(),
+            [0xc0, 0x4b, 0x8d, 0x59]
+        );
+        assert_eq!(ExactOutputSingleCall::selector(), [0xdb, 0x3e, 0x21, 0x98]);
+    }
+
+    #[test]
+    fn split_optimizer_prefers_even_allocation_on_equal_pools() {
+        // 两个相同的凹收益池：输出 = 100·k − k²，平均分配最优
+        let pool: Vec<Option<U256>> = (1..=10u64).map(|k| Some(U256::from(100 * k - k * k))).collect();
+        let allocation = optimize_split(&[pool.clone(), pool], 10, 3).unwrap();
+        assert_eq!(allocation, vec![(0, 5), (1, 5)]);
+    }
+
+    #[test]
+    fn split_optimizer_respects_leg_limit_and_missing_quotes() {
+        let linear: Vec<Option<U256>> = (1..=4u64).map(|k| Some(U256::from(10 * k))).collect();
+        let mut partial = linear.clone();
+        // 第二条路由只能吃下 1 份
+        for quote in partial.iter_mut().skip(1) {
+            *quote = None;
+        }
+        let better: Vec<Option<U256>> = (1..=4u64).map(|k| Some(U256::from(11 * k))).collect();
+
+        let allocation = optimize_split(&[linear.clone(), partial, better], 4, 1).unwrap();
+        assert_eq!(allocation, vec![(2, 4)]);
+
+        assert!(optimize_split(&[vec![None; 4]], 4, 3).is_none());
+    }
+
+    #[test]
+    fn gas_adjusted_output_prefers_cheaper_route() {
+        let token = |n| Address::from_low_u64_be(n);
+        let direct = RouteLeg {
+            protocol: SwapProtocol::UniswapV2,
+            path: vec![token(1), token(2)],
+            fees: Vec::new(),
+            amount_in: U256::exp10(18),
+            amount_out: U256::from(2_000_000_000u64),
+            pool: None,
+        };
+        let two_hop = RouteLeg {
+            path: vec![token(1), token(3), token(2)],
+            amount_out: U256::from(2_001_000_000u64),
+            ..direct.clone()
+        };
+        assert_eq!(modelled_swap_gas(std::slice::from_ref(&direct)), 120_000);
+        assert_eq!(modelled_swap_gas(std::slice::from_ref(&two_hop)), 180_000);
+
+        // 20 gwei、1 ETH = 2000 USDC（6 位小数）：每 1 万 gas 折合 0.4 USDC
+        let pricing = GasPricing {
+            fee_per_gas: U256::from(20_000_000_000u64),
+            output_per_native: U256::from(2_000_000_000u64),
+        };
+        assert_eq!(pricing.cost_in_output(10_000), U256::from(400_000));
+        let net = |route: &RouteLeg| {
+            route.amount_out - pricing.cost_in_output(modelled_swap_gas(std::slice::from_ref(route)))
+        };
+        assert!(two_hop.amount_out > direct.amount_out);
+        assert!(net(&direct) > net(&two_hop));
+    }
+
+    #[test]
+    fn exact_output_counts_gas_in_input_token() {
+        let token = |n| Address::from_low_u64_be(n);
+        let direct = SwapQuote {
+            protocol: SwapProtocol::UniswapV3,
+            mode: SwapMode::ExactOutput,
+            router: token(9),
+            token_in: token(1),
+            token_out: token(2),
+            amount_in: U256::from(2_001_000_000u64),
+            amount_out: U256::exp10(18),
+            path: vec![token(1), token(2)],
+            fees: vec![500],
+            price_impact_pct: Decimal::ZERO,
+            lp_fee_pct: Decimal::ZERO,
+            legs: Vec::new(),
+            pool: None,
+        };
+        let two_hop = SwapQuote {
+            protocol: SwapProtocol::UniswapV2,
+            amount_in: U256::from(2_000_000_000u64),
+            path: vec![token(1), token(3), token(2)],
+            fees: Vec::new(),
+            ..direct.clone()
+        };
+        // 20 gwei、1 ETH = 2000 USDC：两跳 V2 多出的 40k gas 折合 1.6 USDC，超过少付的 1 USDC
+        let pricing = GasPricing {
+            fee_per_gas: U256::from(20_000_000_000u64),
+            output_per_native: U256::from(2_000_000_000u64),
+        };
+        let candidates = vec![direct.clone(), two_hop.clone()];
+        let chosen = |pricing| cheapest_exact_output(candidates.clone(), pricing).map(|quote| quote.protocol);
+        assert_eq!(chosen(None), Some(SwapProtocol::UniswapV2));
+        assert_eq!(chosen(Some(pricing)), Some(SwapProtocol::UniswapV3));
+    }
+
+    fn reserves(reserve_in: u128, reserve_out: u128) -> V2Reserves {
+        V2Reserves {
+            reserve_in: U256::from(reserve_in),
+            reserve_out: U256::from(reserve_out),
+        }
+    }
+
+    #[test]
+    fn v2_local_quotes_match_library_integer_math() {
+        let eth = 10u128.pow(18);
+        let usdc = 10u128.pow(6);
+
+        // 期望值按 UniswapV2Library.getAmountOut / getAmountIn 的整数运算得出
+        let single = [reserves(100 * eth, 200 * eth)];
+        assert_eq!(
+            v2_amount_out(U256::from(eth), &single),
+            Some(U256::from(1_974_316_068_794_122_597u128))
+        );
+
+        let path = [
+            reserves(5_000 * eth, 10_000_000 * usdc),
+            reserves(8_000_000 * usdc, 7_990_000 * eth),
+        ];
+        assert_eq!(
+            v2_amount_out(U256::from(3 * eth), &path),
+            Some(U256::from(5_948_605_749_866_428_573_146u128))
+        );
+        assert_eq!(
+            v2_amount_in(U256::from(2_000 * usdc), &path[..1]),
+            Some(U256::from(1_003_209_669_015_046_741u128))
+        );
+
+        // 倒推出的输入至少能换到目标输出
+        let amount_in = v2_amount_in(U256::from(5_000 * eth), &path).unwrap();
+        assert!(v2_amount_out(amount_in, &path).unwrap() >= U256::from(5_000 * eth));
+
+        assert_eq!(v2_amount_in(U256::from(200 * eth), &single), None);
+        assert_eq!(v2_amount_out(U256::zero(), &single), None);
+    }
+
+    #[test]
+    fn v2_impact_is_measured_against_mid_price() {
+        let pool = [reserves(1_000_000, 1_000_000)];
+
+        // 极小成交几乎没有价格影响，0.3% 手续费单独列出
+        let small = v2_amount_out(U256::from(1_000), &pool).unwrap();
+        let impact = v2_price_impact(U256::from(1_000), small, &pool).unwrap();
+        assert_eq!(impact.lp_fee_pct, dec!(0.3));
+        assert!(impact.price_impact_pct < dec!(0.2), "{impact:?}");
+
+        // 10% 储备的成交：1 - 1 / 1.0997 ≈ 9.07%，手续费不计入价格影响
+        let large = v2_amount_out(U256::from(100_000), &pool).unwrap();
+        let impact = v2_price_impact(U256::from(100_000), large, &pool).unwrap();
+        assert!(
+            impact.price_impact_pct > dec!(9.0) && impact.price_impact_pct < dec!(9.1),
+            "{impact:?}"
+        );
+
+        // 两跳 V3 路径：手续费按 1 - (1 - 0.05%)(1 - 0.3%) 合计
+        let impact = mid_price_impact(
+            U256::from(1_000_000),
+            U256::from(1_996_500),
+            2.0,
+            &v3_fee_fractions(&[500, 3000]),
+        )
+        .unwrap();
+        assert_eq!(impact.lp_fee_pct, dec!(0.34985));
+        assert_eq!(impact.price_impact_pct, Decimal::ZERO);
+
+        assert!(mid_price_impact(U256::zero(), U256::one(), 1.0, &[]).is_none());
+    }
+
+    fn decode_call<C: ethers::abi::AbiDecode>(tx: &TypedTransaction) -> C {
+        C::decode(tx.data().expect("交易缺少 calldata")).unwrap()
+    }
+
+    /// 拆开 SwapRouter 的 multicall，返回其中的各个调用
+    fn multicall_parts(tx: &TypedTransaction) -> Vec<Bytes> {
+        decode_call::<uniswap_v3_router::MulticallCall>(tx).data
+    }
+
+    #[tokio::test]
+    async fn v2_native_swaps_use_eth_entry_points() {
+        let client = test_signing_client().await;
+        let weth = client.chain().wrapped_native;
+        let usdc = Address::from_low_u64_be(0x05dc);
+        let recipient = Address::from_low_u64_be(0xaa);
+        let router = client.chain().uniswap_v2().unwrap().router;
+        let amount_in = U256::exp10(18);
+        let min_out = U256::from(1_900_000_000u64);
+
+        let tx = client
+            .build_uniswap_v2_swap_tx(
+                SwapProtocol::UniswapV2,
+                Address::zero(),
+                usdc,
+                amount_in,
+                min_out,
+                vec![weth, usdc],
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        let call: uniswap_v2_router::SwapExactETHForTokensCall = decode_call(&tx);
+        assert_eq!(tx.to_addr(), Some(&router));
+        assert_eq!(tx.value(), Some(&amount_in));
+        assert_eq!(call.amount_out_min, min_out);
+        assert_eq!(call.path, vec![weth, usdc]);
+        assert_eq!(call.to, recipient);
+
+        let tx = client
+            .build_uniswap_v2_swap_tx(
+                SwapProtocol::UniswapV2,
+                usdc,
+                Address::zero(),
+                min_out,
+                amount_in,
+                vec![usdc, weth],
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        let call: uniswap_v2_router::SwapExactTokensForETHCall = decode_call(&tx);
+        assert_eq!(tx.value(), Some(&U256::zero()));
+        assert_eq!(call.amount_in, min_out);
+        assert_eq!(call.amount_out_min, amount_in);
+        assert_eq!(call.path, vec![usdc, weth]);
+        assert_eq!(call.to, recipient);
+    }
+
+    #[tokio::test]
+    async fn v3_native_swaps_wrap_swap_in_multicall() {
+        use ethers::abi::AbiDecode;
+
+        let client = test_signing_client().await;
+        let weth = client.chain().wrapped_native;
+        let usdc = Address::from_low_u64_be(0x05dc);
+        let recipient = Address::from_low_u64_be(0xaa);
+        let router = client.chain().uniswap_v3().unwrap().router;
+        let amount_in = U256::exp10(18);
+        let min_out = U256::from(1_900_000_000u64);
+
+        // 输入 ETH：value 为输入数量，exactInput 之后 refundETH
+        let tx = client
+            .build_uniswap_v3_swap_tx(
+                Address::zero(),
+                usdc,
+                &[weth, usdc],
+                &[500],
+                amount_in,
+                min_out,
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(tx.to_addr(), Some(&router));
+        assert_eq!(tx.value(), Some(&amount_in));
+        let parts = multicall_parts(&tx);
+        assert_eq!(parts.len(), 2);
+        let swap = uniswap_v3_router::ExactInputCall::decode(&parts[0]).unwrap().params;
+        assert_eq!(swap.path, encode_v3_path(&[weth, usdc], &[500]).unwrap());
+        assert_eq!(swap.recipient, recipient);
+        assert_eq!(swap.amount_in, amount_in);
+        assert_eq!(swap.amount_out_minimum, min_out);
+        uniswap_v3_router::RefundETHCall::decode(&parts[1]).unwrap();
+
+        // 输出 ETH：WETH 先留在路由合约，再由 unwrapWETH9 按最少输出转给 recipient
+        let tx = client
+            .build_uniswap_v3_swap_tx(
+                usdc,
+                Address::zero(),
+                &[usdc, weth],
+                &[500],
+                min_out,
+                amount_in,
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(tx.value(), Some(&U256::zero()));
+        let parts = multicall_parts(&tx);
+        assert_eq!(parts.len(), 2);
+        let swap = uniswap_v3_router::ExactInputCall::decode(&parts[0]).unwrap().params;
+        assert_eq!(swap.recipient, router);
+        assert_eq!(swap.amount_out_minimum, amount_in);
+        let unwrap = uniswap_v3_router::UnwrapWETH9Call::decode(&parts[1]).unwrap();
+        assert_eq!(unwrap.amount_minimum, amount_in);
+        assert_eq!(unwrap.recipient, recipient);
+
+        // 不涉及原生代币时直接调用 exactInput
+        let tx = client
+            .build_uniswap_v3_swap_tx(usdc, weth, &[usdc, weth], &[500], min_out, U256::one(), recipient, 600)
+            .await
+            .unwrap();
+        assert_eq!(tx.value(), Some(&U256::zero()));
+        let swap: uniswap_v3_router::ExactInputCall = decode_call(&tx);
+        assert_eq!(swap.params.recipient, recipient);
+    }
+
+    #[tokio::test]
+    async fn exact_output_native_input_sends_max_and_refunds() {
+        use ethers::abi::AbiDecode;
+
+        let client = test_signing_client().await;
+        let weth = client.chain().wrapped_native;
+        let usdc = Address::from_low_u64_be(0x05dc);
+        let recipient = Address::from_low_u64_be(0xaa);
+        let amount_out = U256::from(2_000_000_000u64);
+        let amount_in_max = U256::from(1_005_000_000_000_000_000u64);
+
+        // V2：swapETHForExactTokens 附带最大输入，多余的 ETH 由路由合约退回
+        let tx = client
+            .build_uniswap_v2_exact_output_tx(
+                Address::zero(),
+                usdc,
+                amount_out,
+                amount_in_max,
+                vec![weth, usdc],
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        let call: uniswap_v2_router::SwapETHForExactTokensCall = decode_call(&tx);
+        assert_eq!(tx.value(), Some(&amount_in_max));
+        assert_eq!(call.amount_out, amount_out);
+        assert_eq!(call.path, vec![weth, usdc]);
+
+        let tx = client
+            .build_uniswap_v2_exact_output_tx(
+                usdc,
+                weth,
+                amount_out,
+                amount_in_max,
+                vec![usdc, weth],
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        let call: uniswap_v2_router::SwapTokensForExactTokensCall = decode_call(&tx);
+        assert_eq!(tx.value(), Some(&U256::zero()));
+        assert_eq!(call.amount_in_max, amount_in_max);
+
+        // V3：exactOutputSingle 以 WETH 为输入，value 为最大输入，随后 refundETH
+        let tx = client
+            .build_uniswap_v3_exact_output_tx(
+                Address::zero(),
+                usdc,
+                500,
+                amount_out,
+                amount_in_max,
+                recipient,
+                600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(tx.value(), Some(&amount_in_max));
+        let parts = multicall_parts(&tx);
+        assert_eq!(parts.len(), 2);
+        let swap = ExactOutputSingleCall::decode(&parts[0]).unwrap().params;
+        assert_eq!(swap.token_in, weth);
+        assert_eq!(swap.token_out, usdc);
+        assert_eq!(swap.fee, 500);
+        assert_eq!(swap.recipient, recipient);
+        assert_eq!(swap.amount_out, amount_out);
+        assert_eq!(swap.amount_in_maximum, amount_in_max);
+        uniswap_v3_router::RefundETHCall::decode(&parts[1]).unwrap();
+    }
 }
@@ -0,0 +1,336 @@
+use crate::ethereum::chains::{BalancerDeployment, ChainInfo, CurvePool};
+use crate::ethereum::client::{v2_amount_out, EthereumClient, PoolRef, RouteLeg, SwapProtocol};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use futures::future::join_all;
+
+/// DEX 报价适配器
+///
+/// 每个协议（或分叉）一个实现，`quote_best_swap` 汇总当前链上所有已启用适配器的候选路由，
+/// 拆单和价格影响估算通过 `requote` 按新的输入数量重新报价。
+#[async_trait]
+pub trait DexAdapter: Send + Sync {
+    fn protocol(&self) -> SwapProtocol;
+
+    /// 固定输入报价的候选路由；`token_in` / `token_out` 为用户传入的地址（0x0 为原生代币）
+    async fn quote_routes(
+        &self,
+        client: &EthereumClient,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>>;
+
+    /// 按新的输入数量重新报价本协议的一条路由，路由不可用时返回 None
+    async fn requote(
+        &self,
+        client: &EthereumClient,
+        route: &RouteLeg,
+        amount_in: U256,
+    ) -> Result<Option<U256>>;
+}
+
+/// 当前链上已部署（且已配置池子）的适配器
+pub fn adapters_for(chain: &ChainInfo) -> Vec<Box<dyn DexAdapter>> {
+    let mut adapters: Vec<Box<dyn DexAdapter>> = Vec::new();
+    if chain.uniswap_v2.is_some() {
+        adapters.push(Box::new(UniswapV2Adapter(SwapProtocol::UniswapV2)));
+    }
+    if chain.uniswap_v3.is_some() {
+        adapters.push(Box::new(UniswapV3Adapter));
+    }
+    // V4 池子只能经 Universal Router 执行
+    if chain.uniswap_v4.is_some() && chain.universal_router.is_some() {
+        adapters.push(Box::new(UniswapV4Adapter));
+    }
+    if chain.sushiswap.is_some() {
+        adapters.push(Box::new(UniswapV2Adapter(SwapProtocol::SushiSwap)));
+    }
+    if !chain.curve_pools.is_empty() {
+        adapters.push(Box::new(CurveAdapter {
+            pools: chain.curve_pools.clone(),
+        }));
+    }
+    if let Some(balancer) = chain.balancer.as_ref().filter(|balancer| !balancer.pools.is_empty()) {
+        adapters.push(Box::new(BalancerAdapter {
+            deployment: balancer.clone(),
+        }));
+    }
+    adapters
+}
+
+/// Uniswap V2 及接口相同的分叉，原生代币按包装代币报价
+struct UniswapV2Adapter(SwapProtocol);
+
+#[async_trait]
+impl DexAdapter for UniswapV2Adapter {
+    fn protocol(&self) -> SwapProtocol {
+        self.0
+    }
+
+    async fn quote_routes(
+        &self,
+        client: &EthereumClient,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        client
+            .uniswap_v2_routes(
+                self.0,
+                client.routable(token_in),
+                client.routable(token_out),
+                amount_in,
+            )
+            .await
+    }
+
+    async fn requote(
+        &self,
+        client: &EthereumClient,
+        route: &RouteLeg,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        match &route.pool {
+            Some(PoolRef::UniswapV2 { reserves }) => Ok(v2_amount_out(amount_in, reserves)),
+            _ => client.quote_v2_path(self.0, &route.path, amount_in).await,
+        }
+    }
+}
+
+struct UniswapV3Adapter;
+
+#[async_trait]
+impl DexAdapter for UniswapV3Adapter {
+    fn protocol(&self) -> SwapProtocol {
+        SwapProtocol::UniswapV3
+    }
+
+    async fn quote_routes(
+        &self,
+        client: &EthereumClient,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        Ok(client
+            .uniswap_v3_routes(client.routable(token_in), client.routable(token_out), amount_in)
+            .await)
+    }
+
+    async fn requote(
+        &self,
+        client: &EthereumClient,
+        route: &RouteLeg,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        match &route.pool {
+            Some(PoolRef::UniswapV3 { pools }) => {
+                client
+                    .simulate_v3_path(&mut pools.clone(), &route.path, amount_in)
+                    .await
+            }
+            _ => Ok(client.quote_v3_path(&route.path, &route.fees, amount_in).await),
+        }
+    }
+}
+
+/// V4 池子直接以原生代币计价，使用用户传入的地址
+struct UniswapV4Adapter;
+
+#[async_trait]
+impl DexAdapter for UniswapV4Adapter {
+    fn protocol(&self) -> SwapProtocol {
+        SwapProtocol::UniswapV4
+    }
+
+    async fn quote_routes(
+        &self,
+        client: &EthereumClient,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        Ok(client.uniswap_v4_routes(token_in, token_out, amount_in).await)
+    }
+
+    async fn requote(
+        &self,
+        client: &EthereumClient,
+        route: &RouteLeg,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        let key = route
+            .pool_key()
+            .ok_or_else(|| anyhow!("V4 路由缺少 PoolKey"))?;
+        Ok(client.quote_v4_pool(key, route.path[0], amount_in).await)
+    }
+}
+
+/// 包含输入和输出代币的 Curve 池子逐个 `get_dy` 报价；只支持 ERC20
+struct CurveAdapter {
+    pools: Vec<CurvePool>,
+}
+
+#[async_trait]
+impl DexAdapter for CurveAdapter {
+    fn protocol(&self) -> SwapProtocol {
+        SwapProtocol::Curve
+    }
+
+    async fn quote_routes(
+        &self,
+        client: &EthereumClient,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        if token_in.is_zero() || token_out.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        let quoted = join_all(self.pools.iter().filter_map(|pool| {
+            let (i, j) = curve_indices(pool, token_in, token_out)?;
+            Some(async move {
+                let amount_out = client
+                    .quote_curve_pool(pool.address, i, j, amount_in)
+                    .await
+                    .ok()??;
+                Some(RouteLeg {
+                    protocol: SwapProtocol::Curve,
+                    path: vec![token_in, token_out],
+                    fees: Vec::new(),
+                    amount_in,
+                    amount_out,
+                    pool: Some(PoolRef::Curve {
+                        pool: pool.address,
+                        i,
+                        j,
+                    }),
+                })
+            })
+        }))
+        .await;
+
+        Ok(quoted.into_iter().flatten().collect())
+    }
+
+    async fn requote(
+        &self,
+        client: &EthereumClient,
+        route: &RouteLeg,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        match route.pool {
+            Some(PoolRef::Curve { pool, i, j }) => {
+                client.quote_curve_pool(pool, i, j, amount_in).await
+            }
+            _ => Err(anyhow!("Curve 路由缺少池子信息")),
+        }
+    }
+}
+
+/// 包含输入和输出代币的 Balancer 池子逐个 `queryBatchSwap` 报价；只支持 ERC20
+struct BalancerAdapter {
+    deployment: BalancerDeployment,
+}
+
+#[async_trait]
+impl DexAdapter for BalancerAdapter {
+    fn protocol(&self) -> SwapProtocol {
+        SwapProtocol::BalancerV2
+    }
+
+    async fn quote_routes(
+        &self,
+        client: &EthereumClient,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<RouteLeg>> {
+        if token_in.is_zero() || token_out.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        let pools = self.deployment.pools.iter().filter(|pool| {
+            pool.tokens.contains(&token_in) && pool.tokens.contains(&token_out)
+        });
+        let quoted = join_all(pools.map(|pool| async move {
+            let amount_out = client
+                .quote_balancer_pool(pool.id, token_in, token_out, amount_in)
+                .await
+                .ok()??;
+            Some(RouteLeg {
+                protocol: SwapProtocol::BalancerV2,
+                path: vec![token_in, token_out],
+                fees: Vec::new(),
+                amount_in,
+                amount_out,
+                pool: Some(PoolRef::Balancer { pool_id: pool.id }),
+            })
+        }))
+        .await;
+
+        Ok(quoted.into_iter().flatten().collect())
+    }
+
+    async fn requote(
+        &self,
+        client: &EthereumClient,
+        route: &RouteLeg,
+        amount_in: U256,
+    ) -> Result<Option<U256>> {
+        match route.pool {
+            Some(PoolRef::Balancer { pool_id }) => {
+                let (token_in, token_out) = (route.path[0], route.path[route.path.len() - 1]);
+                client
+                    .quote_balancer_pool(pool_id, token_in, token_out, amount_in)
+                    .await
+            }
+            _ => Err(anyhow!("Balancer 路由缺少池子信息")),
+        }
+    }
+}
+
+/// 输入 / 输出代币在 Curve 池子中的序号
+fn curve_indices(pool: &CurvePool, token_in: Address, token_out: Address) -> Option<(i128, i128)> {
+    let i = pool.coins.iter().position(|coin| *coin == token_in)?;
+    let j = pool.coins.iter().position(|coin| *coin == token_out)?;
+    Some((i as i128, j as i128))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::chains::resolve_chain;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn enabled_adapters_follow_chain_deployments() {
+        let protocols = |chain_id| {
+            adapters_for(&resolve_chain(chain_id, &BTreeMap::new()).unwrap())
+                .iter()
+                .map(|adapter| adapter.protocol().as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            protocols(1),
+            vec!["UniswapV2", "UniswapV3", "UniswapV4", "SushiSwap", "Curve", "BalancerV2"]
+        );
+        // Base 没有旧版 V3 部署、SushiSwap、Curve 池子，Balancer 也没有内置池子
+        assert_eq!(protocols(8453), vec!["UniswapV2", "UniswapV4"]);
+    }
+
+    #[test]
+    fn curve_indices_follow_coin_order() {
+        let coins: Vec<Address> = (1..=3).map(Address::from_low_u64_be).collect();
+        let pool = CurvePool {
+            address: Address::from_low_u64_be(9),
+            coins: coins.clone(),
+        };
+        assert_eq!(curve_indices(&pool, coins[2], coins[0]), Some((2, 0)));
+        assert_eq!(curve_indices(&pool, coins[0], Address::from_low_u64_be(4)), None);
+    }
+}
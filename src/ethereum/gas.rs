@@ -0,0 +1,188 @@
+use crate::config::GasSpeed;
+use anyhow::{anyhow, Result};
+use ethers::types::{transaction::eip2718::TypedTransaction, FeeHistory, U256};
+
+/// eth_feeHistory 回看的区块数
+pub const FEE_HISTORY_BLOCKS: u64 = 20;
+
+/// 请求的小费百分位，依次对应 slow / normal / fast
+pub const FEE_HISTORY_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// EIP-1559 费用建议（wei）
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    pub speed: GasSpeed,
+    /// 下一个区块的 base fee
+    pub base_fee: U256,
+    pub max_priority_fee_per_gas: U256,
+    /// 两倍 base fee 加小费，可承受连续 6 个满块的 base fee 上涨
+    pub max_fee_per_gas: U256,
+}
+
+impl FeeSuggestion {
+    /// 预计实际支付的 gas 单价：base fee 加小费，不超过 max fee
+    pub fn expected_fee_per_gas(&self) -> U256 {
+        (self.base_fee + self.max_priority_fee_per_gas).min(self.max_fee_per_gas)
+    }
+}
+
+impl GasSpeed {
+    /// 在 `FEE_HISTORY_PERCENTILES` 中的位置
+    fn percentile_index(&self) -> usize {
+        match self {
+            GasSpeed::Slow => 0,
+            GasSpeed::Normal => 1,
+            GasSpeed::Fast => 2,
+        }
+    }
+}
+
+/// 由 eth_feeHistory 结果计算费用：小费取各区块对应百分位的中位数（跳过小费为 0 的空块）
+pub fn suggest_fees(history: &FeeHistory, speed: GasSpeed) -> Result<FeeSuggestion> {
+    // base_fee_per_gas 比请求的区块数多一项，最后一项是下一个区块的 base fee
+    let base_fee = history
+        .base_fee_per_gas
+        .last()
+        .copied()
+        .filter(|fee| !fee.is_zero())
+        .ok_or_else(|| anyhow!("节点未返回 base fee，该链可能未启用 EIP-1559"))?;
+
+    let index = speed.percentile_index();
+    let mut rewards: Vec<U256> = history
+        .reward
+        .iter()
+        .filter_map(|block| block.get(index).copied())
+        .filter(|reward| !reward.is_zero())
+        .collect();
+    rewards.sort();
+    let priority = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+
+    Ok(FeeSuggestion {
+        speed,
+        base_fee,
+        max_priority_fee_per_gas: priority,
+        max_fee_per_gas: base_fee * 2 + priority,
+    })
+}
+
+/// 替换交易的费用至少比原交易高出的比例（节点要求至少 10%）
+const REPLACEMENT_BUMP_PCT: u64 = 12;
+
+/// 替换 pending 交易（加速或取消）的费用：原交易的 max fee 和小费各上调 12%，
+/// 若当前建议更高则取建议值
+pub fn replacement_fees(
+    previous_max_fee: U256,
+    previous_priority_fee: U256,
+    suggestion: &FeeSuggestion,
+) -> FeeSuggestion {
+    // 向上取整，保证小额费用也满足涨幅要求
+    let bump = |fee: U256| fee + (fee * REPLACEMENT_BUMP_PCT + 99) / 100;
+    let priority = bump(previous_priority_fee).max(suggestion.max_priority_fee_per_gas);
+    let max_fee = bump(previous_max_fee)
+        .max(suggestion.max_fee_per_gas)
+        .max(priority);
+    FeeSuggestion {
+        speed: suggestion.speed,
+        base_fee: suggestion.base_fee,
+        max_priority_fee_per_gas: priority,
+        max_fee_per_gas: max_fee,
+    }
+}
+
+/// 把费用写入交易；type-2 交易分别设置 max fee 和小费，legacy 交易按 max fee 设置 gas price
+pub fn apply_fees(tx: &mut TypedTransaction, fees: &FeeSuggestion) {
+    match tx {
+        TypedTransaction::Eip1559(inner) => {
+            inner.max_fee_per_gas = Some(fees.max_fee_per_gas);
+            inner.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas);
+        }
+        _ => {
+            tx.set_gas_price(fees.max_fee_per_gas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Eip1559TransactionRequest;
+
+    fn gwei(value: u64) -> U256 {
+        U256::from(value) * U256::exp10(9)
+    }
+
+    fn history(base_fees: &[u64], rewards: &[[u64; 3]]) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees.iter().map(|fee| gwei(*fee)).collect(),
+            gas_used_ratio: vec![0.5; rewards.len()],
+            oldest_block: U256::from(100),
+            reward: rewards
+                .iter()
+                .map(|block| block.iter().map(|reward| gwei(*reward)).collect())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn presets_use_median_of_their_percentile() {
+        let history = history(
+            &[10, 11, 12, 13],
+            &[[1, 2, 5], [0, 0, 0], [1, 3, 8], [2, 4, 9]],
+        );
+
+        let slow = suggest_fees(&history, GasSpeed::Slow).unwrap();
+        assert_eq!(slow.base_fee, gwei(13));
+        assert_eq!(slow.max_priority_fee_per_gas, gwei(1));
+        assert_eq!(slow.max_fee_per_gas, gwei(27));
+
+        let normal = suggest_fees(&history, GasSpeed::Normal).unwrap();
+        assert_eq!(normal.max_priority_fee_per_gas, gwei(3));
+        assert_eq!(normal.max_fee_per_gas, gwei(29));
+
+        let fast = suggest_fees(&history, GasSpeed::Fast).unwrap();
+        assert_eq!(fast.max_priority_fee_per_gas, gwei(8));
+        assert_eq!(fast.max_fee_per_gas, gwei(34));
+        assert_eq!(fast.expected_fee_per_gas(), gwei(21));
+    }
+
+    #[test]
+    fn missing_base_fee_is_rejected() {
+        let history = history(&[0, 0], &[[1, 2, 3]]);
+        assert!(suggest_fees(&history, GasSpeed::Normal).is_err());
+    }
+
+    #[test]
+    fn replacement_bumps_previous_fees() {
+        let suggestion = suggest_fees(&history(&[10, 10], &[[1, 2, 3]]), GasSpeed::Normal).unwrap();
+
+        // 原交易费用高于当前建议：各上调 12%
+        let bumped = replacement_fees(gwei(50), gwei(5), &suggestion);
+        assert_eq!(bumped.max_fee_per_gas, gwei(56));
+        assert_eq!(bumped.max_priority_fee_per_gas, U256::from(5_600_000_000u64));
+
+        // 当前建议更高时直接采用建议
+        let bumped = replacement_fees(gwei(15), gwei(1), &suggestion);
+        assert_eq!(bumped.max_fee_per_gas, gwei(22));
+        assert_eq!(bumped.max_priority_fee_per_gas, gwei(2));
+
+        // 涨幅向上取整，极小的费用也会上调
+        let quiet = suggest_fees(&history(&[0, 1], &[[0, 0, 0]]), GasSpeed::Normal).unwrap();
+        let bumped = replacement_fees(U256::from(1), U256::from(1), &quiet);
+        assert_eq!(bumped.max_priority_fee_per_gas, U256::from(2));
+    }
+
+    #[test]
+    fn fees_are_applied_to_type2_transactions() {
+        let fees = suggest_fees(&history(&[10, 10], &[[1, 2, 3]]), GasSpeed::Fast).unwrap();
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new().into();
+        apply_fees(&mut tx, &fees);
+        match tx {
+            TypedTransaction::Eip1559(inner) => {
+                assert_eq!(inner.max_fee_per_gas, Some(gwei(23)));
+                assert_eq!(inner.max_priority_fee_per_gas, Some(gwei(3)));
+            }
+            other => panic!("expected a type-2 transaction, got {:?}", other),
+        }
+    }
+}
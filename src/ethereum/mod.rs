@@ -1,3 +1,10 @@
+pub mod chains;
 pub mod client;
+pub mod dex;
+pub mod gas;
+pub mod nonce;
 pub mod rpc;
+pub mod signer;
 pub mod types;
+pub mod universal_router;
+pub mod v3_math;
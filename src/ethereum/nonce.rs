@@ -0,0 +1,188 @@
+use anyhow::Result;
+use ethers::types::U256;
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// 已分配的 nonce 超过这段时间仍未出现在 pending 区块中，视为被放弃（如签名后未能广播的交易）
+const NONCE_GAP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 单个钱包的本地 nonce 分配器：并发构造的交易各自拿到不同的 nonce
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    state: Mutex<NonceState>,
+}
+
+impl NonceManager {
+    /// 连续分配 `count` 个 nonce 并返回第一个；分配前先用 `pending_count`（pending 区块的交易数）同步
+    pub async fn reserve(
+        &self,
+        count: u64,
+        pending_count: impl Future<Output = Result<U256>>,
+    ) -> Result<U256> {
+        // 同步和分配在同一把锁内完成，并发调用按顺序拿到连续的 nonce
+        let mut state = self.state.lock().await;
+        let pending = pending_count.await?;
+        let now = Instant::now();
+        if let Some((from, to)) = state.sync(pending, now) {
+            warn!(
+                from = %from,
+                to = %to,
+                "已分配的 nonce 一直未广播，之后的交易无法上链，改从 pending nonce 重新分配"
+            );
+        }
+        Ok(state.allocate(count, now))
+    }
+
+    /// 返回下一个将分配的 nonce 但不占用，供只签名不广播的模拟交易使用
+    pub async fn peek(&self, pending_count: impl Future<Output = Result<U256>>) -> Result<U256> {
+        let mut state = self.state.lock().await;
+        let pending = pending_count.await?;
+        state.sync(pending, Instant::now());
+        Ok(state.next.unwrap_or(pending))
+    }
+
+    /// 交易签名失败等未能发出时归还 nonce；只有它们仍是最后分配的一段时才会被复用
+    pub async fn release(&self, first: U256, count: u64) {
+        self.state.lock().await.release(first, count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    /// 下一个可分配的 nonce，首次分配前为空
+    next: Option<U256>,
+    /// 已分配但尚未出现在 pending 区块中的 nonce 及其分配时间
+    outstanding: BTreeMap<U256, Instant>,
+}
+
+impl NonceState {
+    /// 按 pending 区块的交易数同步；发现空洞时返回被放弃的 nonce 区间 `[from, to)`
+    fn sync(&mut self, pending: U256, now: Instant) -> Option<(U256, U256)> {
+        // pending 之前的 nonce 都已进入交易池或上链
+        self.outstanding = self.outstanding.split_off(&pending);
+
+        let next = match self.next {
+            Some(next) if next > pending => next,
+            // 首次同步，或其他客户端用同一钱包发过交易
+            _ => {
+                self.next = Some(pending);
+                self.outstanding.clear();
+                return None;
+            }
+        };
+
+        // pending 对应的 nonce 迟迟未广播（或已归还），排在它之后的交易都会卡住
+        let abandoned = match self.outstanding.get(&pending) {
+            Some(reserved_at) => now.duration_since(*reserved_at) >= NONCE_GAP_TIMEOUT,
+            None => true,
+        };
+        if !abandoned {
+            return None;
+        }
+        self.next = Some(pending);
+        self.outstanding.clear();
+        Some((pending, next))
+    }
+
+    fn allocate(&mut self, count: u64, now: Instant) -> U256 {
+        let first = self.next.unwrap_or_default();
+        for offset in 0..count {
+            self.outstanding.insert(first + offset, now);
+        }
+        self.next = Some(first + count);
+        first
+    }
+
+    fn release(&mut self, first: U256, count: u64) {
+        for offset in 0..count {
+            self.outstanding.remove(&(first + offset));
+        }
+        if self.next == Some(first + count) {
+            self.next = Some(first);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_reservations_get_distinct_nonces() {
+        let now = Instant::now();
+        let mut state = NonceState::default();
+
+        assert_eq!(state.sync(U256::from(7), now), None);
+        assert_eq!(state.allocate(2, now), U256::from(7));
+        // 前两笔还没广播，pending 仍是 7
+        assert_eq!(state.sync(U256::from(7), now), None);
+        assert_eq!(state.allocate(1, now), U256::from(9));
+
+        // 三笔都已进入交易池
+        assert_eq!(state.sync(U256::from(10), now), None);
+        assert!(state.outstanding.is_empty());
+        // 其他客户端又发了两笔
+        assert_eq!(state.sync(U256::from(12), now), None);
+        assert_eq!(state.allocate(1, now), U256::from(12));
+    }
+
+    #[test]
+    fn released_tail_is_reused() {
+        let now = Instant::now();
+        let mut state = NonceState::default();
+        state.sync(U256::from(3), now);
+        let first = state.allocate(2, now);
+
+        state.release(first, 2);
+        assert_eq!(state.sync(U256::from(3), now), None);
+        assert_eq!(state.allocate(1, now), U256::from(3));
+    }
+
+    #[test]
+    fn abandoned_nonce_is_detected_as_gap() {
+        let start = Instant::now();
+        let mut state = NonceState::default();
+        state.sync(U256::from(5), start);
+        state.allocate(1, start);
+        state.allocate(1, start);
+
+        // 超时前认为交易仍在构造或等待广播
+        assert_eq!(state.sync(U256::from(5), start + Duration::from_secs(10)), None);
+        let later = start + NONCE_GAP_TIMEOUT;
+        assert_eq!(
+            state.sync(U256::from(5), later),
+            Some((U256::from(5), U256::from(7)))
+        );
+        assert_eq!(state.allocate(1, later), U256::from(5));
+
+        // 中间的 nonce 被归还后，pending 走到它时立即视为空洞
+        state.sync(U256::from(5), later);
+        let first = state.allocate(3, later);
+        state.release(first + 1, 1);
+        assert_eq!(state.sync(U256::from(6), later), None);
+        assert_eq!(
+            state.sync(U256::from(7), later),
+            Some((U256::from(7), U256::from(9)))
+        );
+    }
+
+    #[tokio::test]
+    async fn simulation_does_not_hold_nonce_for_execute() {
+        let manager = NonceManager::default();
+        let pending = || async { Ok(U256::from(4)) };
+
+        // 模拟兑换只读取 nonce，随后的执行拿到同一个 nonce，不会被模拟占用的 nonce 卡住
+        assert_eq!(manager.peek(pending()).await.unwrap(), U256::from(4));
+        assert_eq!(manager.peek(pending()).await.unwrap(), U256::from(4));
+        assert_eq!(manager.reserve(1, pending()).await.unwrap(), U256::from(4));
+
+        // 执行中的交易尚未广播时，模拟读到的是它之后的 nonce
+        assert_eq!(manager.peek(pending()).await.unwrap(), U256::from(5));
+        assert_eq!(manager.reserve(2, pending()).await.unwrap(), U256::from(5));
+    }
+}
@@ -1,9 +1,36 @@
-use anyhow::{Context, Result};
-use std::time::Duration;
+use crate::config::QuorumConfig;
+use crate::ethereum::types::{QuorumDisagreement, QuorumReport};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use ethers::providers::{
+    HttpClientError, HttpRateLimitRetryPolicy, JsonRpcClient, JsonRpcError, Provider,
+    ProviderError, RetryPolicy, RpcError,
+};
+use futures::future::join_all;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
 use tokio::time::timeout;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+
+/// 单次 RPC 请求超时，超时后切换到下一个端点
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// 探活请求超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 重新探测不可用端点的间隔
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+/// 连续失败多少次后标记为不可用
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// 延迟和错误率的指数移动平均系数
+const EWMA_ALPHA: f64 = 0.3;
+/// 尚未测得延迟的端点按此值参与排序
+const UNMEASURED_LATENCY_MS: f64 = 500.0;
 
 /// 免费公共 RPC 提供商列表
+#[allow(dead_code)]
 pub const FREE_RPC_PROVIDERS: &[&str] = &[
     "https://eth.llamarpc.com",           // LlamaRPC - 推荐
     "https://rpc.ankr.com/eth",           // Ankr
@@ -19,6 +46,7 @@ pub struct RpcProvider {
     pub url: String,
     pub name: String,
     pub description: String,
+    #[allow(dead_code)]
     pub rate_limit: Option<u32>, // 每分钟请求限制
 }
 
@@ -31,6 +59,15 @@ impl RpcProvider {
             rate_limit,
         }
     }
+
+    /// 配置文件或环境变量中给出的 RPC，名称取主机名，不限速
+    pub fn configured(url: &str) -> Self {
+        let name = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_else(|| url.to_string());
+        Self::new(url, &name, "配置的 RPC", None)
+    }
 }
 
 /// 获取推荐的 RPC 提供商列表
@@ -64,6 +101,7 @@ pub fn get_recommended_providers() -> Vec<RpcProvider> {
 }
 
 /// 测试 RPC 连接是否可用
+#[allow(dead_code)]
 pub async fn test_rpc_connection(rpc_url: &str) -> Result<bool> {
     use ethers::providers::{Http, Provider};
     use ethers::middleware::Middleware;
@@ -91,6 +129,7 @@ pub async fn test_rpc_connection(rpc_url: &str) -> Result<bool> {
 }
 
 /// 自动选择可用的 RPC 提供商
+#[allow(dead_code)]
 pub async fn auto_select_rpc() -> Result<String> {
     info!("🔍 自动选择可用的 RPC 提供商...");
 
@@ -107,6 +146,7 @@ pub async fn auto_select_rpc() -> Result<String> {
 }
 
 /// 获取最佳 RPC URL
+#[allow(dead_code)]
 pub async fn get_best_rpc_url() -> Result<String> {
     // 首先检查环境变量
     if let Ok(env_rpc) = std::env::var("ETHEREUM_RPC_URL") {
@@ -125,6 +165,7 @@ pub async fn get_best_rpc_url() -> Result<String> {
 }
 
 /// 显示 RPC 提供商信息
+#[allow(dead_code)]
 pub fn print_rpc_info() {
     println!("🌐 可用的免费 RPC 提供商:");
     println!("================================");
@@ -145,3 +186,863 @@ pub fn print_rpc_info() {
     println!("   cargo run");
     println!();
 }
+
+/// 按分钟配额限速的令牌桶，突发上限为一秒的配额（至少 1 个）
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(limit: u32) -> Self {
+        let refill_per_sec = f64::from(limit.max(1)) / 60.0;
+        let capacity = refill_per_sec.max(1.0);
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec,
+            updated: Instant::now(),
+        }
+    }
+
+    /// 取一个令牌，没有可用令牌时返回需要等待的时间
+    fn try_take(&mut self) -> std::result::Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.refill_per_sec,
+            ))
+        }
+    }
+}
+
+/// 端点的健康统计
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    /// 延迟的指数移动平均（毫秒）
+    latency_ms: Option<f64>,
+    /// 错误率的指数移动平均，近期失败权重更高
+    error_rate: f64,
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    down: bool,
+}
+
+impl EndpointHealth {
+    fn record_success(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(previous) => previous * (1.0 - EWMA_ALPHA) + latency_ms * EWMA_ALPHA,
+            None => latency_ms,
+        });
+        self.error_rate *= 1.0 - EWMA_ALPHA;
+        self.requests += 1;
+        self.consecutive_failures = 0;
+        self.down = false;
+    }
+
+    /// 记录一次失败，返回本次是否导致端点被标记为不可用
+    fn record_failure(&mut self) -> bool {
+        self.error_rate = self.error_rate * (1.0 - EWMA_ALPHA) + EWMA_ALPHA;
+        self.requests += 1;
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        let was_down = self.down;
+        self.down = self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES;
+        self.down && !was_down
+    }
+
+    /// 分数越低越优先：延迟按错误率加权
+    fn score(&self) -> f64 {
+        self.latency_ms.unwrap_or(UNMEASURED_LATENCY_MS) * (1.0 + 10.0 * self.error_rate)
+    }
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    provider: RpcProvider,
+    client: ethers::providers::Http,
+    /// 配置的端点优先于内置公共端点，只有都不可用时才使用公共端点
+    preferred: bool,
+    health: Mutex<EndpointHealth>,
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+impl Endpoint {
+    fn new(provider: RpcProvider, preferred: bool) -> Result<Self> {
+        let url = url::Url::parse(&provider.url)
+            .map_err(|e| anyhow::anyhow!("无效的 RPC URL {}: {}", provider.url, e))?;
+        let http = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self {
+            client: ethers::providers::Http::new_with_client(url, http),
+            preferred,
+            health: Mutex::new(EndpointHealth::default()),
+            bucket: provider.rate_limit.map(|limit| Mutex::new(TokenBucket::per_minute(limit))),
+            provider,
+        })
+    }
+
+    fn health(&self) -> std::sync::MutexGuard<'_, EndpointHealth> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn try_take_token(&self) -> std::result::Result<(), Duration> {
+        match &self.bucket {
+            Some(bucket) => bucket.lock().unwrap_or_else(|e| e.into_inner()).try_take(),
+            None => Ok(()),
+        }
+    }
+
+    /// 等待直到令牌桶放行
+    async fn take_token(&self) {
+        while let Err(wait) = self.try_take_token() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn request<R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> std::result::Result<R, HttpClientError> {
+        let started = Instant::now();
+        let result = self.client.request(method, params).await;
+        match &result {
+            Ok(_) => self.health().record_success(started.elapsed()),
+            Err(e) if is_endpoint_failure(e) => {
+                if self.health().record_failure() {
+                    warn!(rpc = %self.provider.name, "RPC 端点连续失败，暂时停用: {}", e);
+                }
+            }
+            // 端点正常返回了 JSON-RPC 错误（如 revert），不影响健康度
+            Err(_) => self.health().record_success(started.elapsed()),
+        }
+        result
+    }
+
+    /// 探活并返回链 ID 和延迟
+    ///
+    /// 直接请求端点而不经过 `request`：成功时不改动健康状态，由调用方核对链 ID 后再启用端点
+    async fn probe(&self) -> Option<(u64, Duration)> {
+        if self.try_take_token().is_err() {
+            return None;
+        }
+        let started = Instant::now();
+        let result = timeout(
+            PROBE_TIMEOUT,
+            self.client.request::<_, ethers::types::U256>(
+                "eth_chainId",
+                serde_json::Value::Array(vec![]),
+            ),
+        )
+        .await;
+        match result {
+            Ok(Ok(chain_id)) => Some((chain_id.as_u64(), started.elapsed())),
+            Ok(Err(e)) => {
+                if is_endpoint_failure(&e) {
+                    self.health().record_failure();
+                }
+                debug!(rpc = %self.provider.name, "RPC 探活失败: {}", e);
+                None
+            }
+            Err(_) => {
+                self.health().record_failure();
+                debug!(rpc = %self.provider.name, "RPC 探活超时");
+                None
+            }
+        }
+    }
+}
+
+/// 连接失败、超时、限流或返回无法解析的内容时换下一个端点；
+/// 端点正常返回的 JSON-RPC 错误（如 execution reverted）直接交给调用方
+fn is_endpoint_failure(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::ReqwestError(_) | HttpClientError::SerdeJson { .. } => true,
+        HttpClientError::JsonRpcError(_) => HttpRateLimitRetryPolicy.should_retry(error),
+    }
+}
+
+/// RPC 连接池错误
+#[derive(Debug, thiserror::Error)]
+pub enum RpcPoolError {
+    #[error(transparent)]
+    Client(#[from] HttpClientError),
+    #[error("序列化 RPC 参数失败: {0}")]
+    Params(serde_json::Error),
+    #[error("解析 RPC 结果失败: {0}")]
+    Decode(serde_json::Error),
+    #[error("仲裁读取失败: {0}")]
+    Quorum(String),
+}
+
+impl RpcError for RpcPoolError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcPoolError::Client(e) => e.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcPoolError::Client(e) => e.as_serde_error(),
+            RpcPoolError::Params(e) | RpcPoolError::Decode(e) => Some(e),
+            RpcPoolError::Quorum(_) => None,
+        }
+    }
+}
+
+impl From<RpcPoolError> for ProviderError {
+    fn from(error: RpcPoolError) -> Self {
+        match error {
+            RpcPoolError::Client(e) => e.into(),
+            other => ProviderError::JsonRpcClientError(Box::new(other)),
+        }
+    }
+}
+
+/// 端点当前状态，用于日志和诊断
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct EndpointStatus {
+    pub name: String,
+    pub url: String,
+    pub preferred: bool,
+    pub healthy: bool,
+    pub latency_ms: Option<f64>,
+    pub requests: u64,
+    pub failures: u64,
+}
+
+/// 同一条链的多个 RPC 端点组成的连接池
+///
+/// 每次请求按「配置优先、再按延迟 × 错误率评分」的顺序挑选健康端点，
+/// 失败时自动换下一个；连续失败的端点会被停用，并由后台任务定期重新探测。
+/// 设置了 `rate_limit` 的端点按令牌桶限速。
+#[derive(Debug, Clone)]
+pub struct RpcPool {
+    inner: Arc<PoolInner>,
+    quorum: Option<QuorumConfig>,
+    /// 由 `pin_quorum` 创建的视图：读请求固定区块并走仲裁
+    pinned: Option<Arc<QuorumLog>>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    endpoints: Vec<Endpoint>,
+    chain_id: u64,
+}
+
+/// 经过连接池的 Provider
+pub type PooledProvider = Provider<RpcPool>;
+
+#[allow(dead_code)]
+impl RpcPool {
+    /// 探测所有端点并建立连接池
+    ///
+    /// `configured` 为配置中的 RPC（优先使用）。链 ID 只取自 `expected_chain_id` 或配置的端点，
+    /// 配置了端点却都无法应答时启动失败，不会借用公共节点的链 ID。
+    /// 链 ID 确定后才以它调用 `fallbacks` 获取公共备用 RPC；没有配置端点时以 None 调用，
+    /// 链 ID 取自排在最前的可用备用端点。链 ID 不符的端点都会被丢弃。
+    pub async fn connect(
+        configured: Vec<RpcProvider>,
+        fallbacks: impl FnOnce(Option<u64>) -> Vec<RpcProvider>,
+        expected_chain_id: Option<u64>,
+    ) -> Result<Self> {
+        let mut endpoints = configured
+            .into_iter()
+            .map(|provider| Endpoint::new(provider, true))
+            .collect::<Result<Vec<_>>>()?;
+        let mut probes = join_all(endpoints.iter().map(Endpoint::probe)).await;
+
+        let mut chain_id = expected_chain_id
+            .or_else(|| probes.iter().flatten().map(|(id, _)| *id).next());
+        if chain_id.is_none() && !endpoints.is_empty() {
+            let names: Vec<&str> = endpoints.iter().map(|e| e.provider.url.as_str()).collect();
+            bail!("配置的 RPC 都不可用，无法确定链 ID: {}", names.join(", "));
+        }
+
+        let fallbacks = fallbacks(chain_id)
+            .into_iter()
+            .map(|provider| Endpoint::new(provider, false))
+            .collect::<Result<Vec<_>>>()?;
+        let fallback_probes = join_all(fallbacks.iter().map(Endpoint::probe)).await;
+        chain_id = chain_id.or_else(|| fallback_probes.iter().flatten().map(|(id, _)| *id).next());
+        endpoints.extend(fallbacks);
+        probes.extend(fallback_probes);
+        if endpoints.is_empty() {
+            bail!("没有可用的 RPC 端点，请配置 rpc_url");
+        }
+        let Some(chain_id) = chain_id else {
+            let names: Vec<&str> = endpoints.iter().map(|e| e.provider.url.as_str()).collect();
+            bail!("所有 RPC 提供商都不可用: {}", names.join(", "));
+        };
+
+        let mut kept = Vec::new();
+        for (endpoint, probed) in endpoints.into_iter().zip(probes) {
+            match probed {
+                Some((id, _)) if id != chain_id => {
+                    if endpoint.preferred {
+                        bail!(
+                            "RPC {} 返回的链 ID 为 {}，与期望的 {} 不符",
+                            endpoint.provider.url,
+                            id,
+                            chain_id
+                        );
+                    }
+                    debug!(rpc = %endpoint.provider.name, "链 ID {} 不符，跳过", id);
+                }
+                Some((_, latency)) => {
+                    endpoint.health().record_success(latency);
+                    kept.push(endpoint);
+                }
+                None => {
+                    // 链 ID 未经确认，由后台重新探测核对后才会启用
+                    warn!(rpc = %endpoint.provider.url, "RPC 暂不可用，稍后重试");
+                    endpoint.health().down = true;
+                    kept.push(endpoint);
+                }
+            }
+        }
+        if kept.iter().all(|endpoint| endpoint.health().down) {
+            bail!("没有返回链 ID {} 的可用 RPC", chain_id);
+        }
+
+        let inner = Arc::new(PoolInner {
+            endpoints: kept,
+            chain_id,
+        });
+        tokio::spawn(reprobe_loop(Arc::downgrade(&inner)));
+
+        let pool = Self {
+            inner,
+            quorum: None,
+            pinned: None,
+        };
+        info!(chain_id, rpc = %pool.describe(), "RPC 连接池已就绪");
+        Ok(pool)
+    }
+
+    /// 开启仲裁读取，端点数量不足时仍然开启，但读取会因凑不够一致结果而失败
+    pub fn with_quorum(mut self, quorum: QuorumConfig) -> Self {
+        if self.inner.endpoints.len() < quorum.threshold {
+            warn!(
+                chain_id = self.inner.chain_id,
+                "RPC 端点只有 {} 个，不足以达到仲裁阈值 {}",
+                self.inner.endpoints.len(),
+                quorum.threshold
+            );
+        }
+        self.quorum = Some(quorum);
+        self
+    }
+
+    pub fn quorum(&self) -> Option<QuorumConfig> {
+        self.quorum
+    }
+
+    /// 返回固定在 `block_number` 的仲裁视图，未开启仲裁时返回 None
+    ///
+    /// 视图与原连接池共享端点；其中的 eth_getBalance / eth_call 都走仲裁，
+    /// 结果汇总在返回的 `QuorumLog` 中。
+    pub fn pin_quorum(&self, block_number: u64) -> Option<(RpcPool, Arc<QuorumLog>)> {
+        let quorum = self.quorum?;
+        let log = Arc::new(QuorumLog {
+            block_number,
+            quorum,
+            reads: Mutex::new(0),
+            disagreements: Mutex::new(Vec::new()),
+        });
+        let pool = Self {
+            inner: self.inner.clone(),
+            quorum: None,
+            pinned: Some(log.clone()),
+        };
+        Some((pool, log))
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.inner.chain_id
+    }
+
+    /// 形如 `eth.llamarpc.com, LlamaRPC, Ankr`，用于日志
+    pub fn describe(&self) -> String {
+        self.inner
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.provider.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        self.inner
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let health = endpoint.health();
+                EndpointStatus {
+                    name: endpoint.provider.name.clone(),
+                    url: endpoint.provider.url.clone(),
+                    preferred: endpoint.preferred,
+                    healthy: !health.down,
+                    latency_ms: health.latency_ms,
+                    requests: health.requests,
+                    failures: health.failures,
+                }
+            })
+            .collect()
+    }
+
+    /// 按优先级排序的候选端点；没有健康端点时退回全部端点
+    fn ranked(&self) -> Vec<&Endpoint> {
+        let mut candidates: Vec<(&Endpoint, f64)> = self
+            .inner
+            .endpoints
+            .iter()
+            .filter_map(|endpoint| {
+                let health = endpoint.health();
+                (!health.down).then(|| (endpoint, health.score()))
+            })
+            .collect();
+        if candidates.is_empty() {
+            candidates = self
+                .inner
+                .endpoints
+                .iter()
+                .map(|endpoint| (endpoint, endpoint.health().score()))
+                .collect();
+        }
+        candidates.sort_by(|(a, a_score), (b, b_score)| {
+            b.preferred
+                .cmp(&a.preferred)
+                .then(a_score.total_cmp(b_score))
+        });
+        candidates.into_iter().map(|(endpoint, _)| endpoint).collect()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcPool {
+    type Error = RpcPoolError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> std::result::Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params).map_err(RpcPoolError::Params)?;
+        if let Some(log) = &self.pinned {
+            if matches!(method, "eth_getBalance" | "eth_call") {
+                let value = self.quorum_request(method, params, log).await?;
+                return serde_json::from_value(value).map_err(RpcPoolError::Decode);
+            }
+        }
+
+        let candidates = self.ranked();
+        let mut throttled = Vec::new();
+        let mut last_error = None;
+
+        // 先尝试有令牌的端点，被限速的端点留到最后等待令牌
+        for endpoint in &candidates {
+            if let Err(wait) = endpoint.try_take_token() {
+                throttled.push((*endpoint, wait));
+                continue;
+            }
+            match endpoint.request(method, &params).await {
+                Ok(result) => return Ok(result),
+                Err(e) if is_endpoint_failure(&e) => {
+                    debug!(rpc = %endpoint.provider.name, method, "RPC 请求失败，切换端点: {}", e);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        throttled.sort_by_key(|(_, wait)| *wait);
+        for (endpoint, _) in throttled {
+            endpoint.take_token().await;
+            match endpoint.request(method, &params).await {
+                Ok(result) => return Ok(result),
+                Err(e) if is_endpoint_failure(&e) => last_error = Some(e),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        warn!(method, "所有 RPC 端点均请求失败");
+        Err(last_error
+            .expect("连接池至少包含一个端点")
+            .into())
+    }
+}
+
+/// 仲裁读取中单个端点的结果
+#[derive(Debug)]
+enum QuorumVote {
+    Value(serde_json::Value),
+    /// 端点正常返回的 JSON-RPC 错误（如 revert）同样参与投票
+    Error(JsonRpcError),
+}
+
+impl QuorumVote {
+    fn same_as(&self, other: &QuorumVote) -> bool {
+        match (self, other) {
+            (QuorumVote::Value(a), QuorumVote::Value(b)) => a == b,
+            (QuorumVote::Error(a), QuorumVote::Error(b)) => a.message == b.message,
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            QuorumVote::Value(value) => value.to_string(),
+            QuorumVote::Error(error) => format!("错误: {}", error.message),
+        }
+    }
+}
+
+/// 一次查询中的仲裁记录，多个读取共享同一区块
+#[derive(Debug)]
+pub struct QuorumLog {
+    block_number: u64,
+    quorum: QuorumConfig,
+    reads: Mutex<usize>,
+    disagreements: Mutex<Vec<QuorumDisagreement>>,
+}
+
+impl QuorumLog {
+    pub fn report(&self) -> QuorumReport {
+        QuorumReport {
+            block_number: self.block_number,
+            providers: self.quorum.providers,
+            threshold: self.quorum.threshold,
+            reads: *self.reads.lock().unwrap_or_else(|e| e.into_inner()),
+            disagreements: self
+                .disagreements
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+}
+
+impl RpcPool {
+    /// 把同一读请求发给多个端点，至少 threshold 个结果一致才返回
+    async fn quorum_request(
+        &self,
+        method: &str,
+        mut params: serde_json::Value,
+        log: &QuorumLog,
+    ) -> std::result::Result<serde_json::Value, RpcPoolError> {
+        // eth_getBalance / eth_call 的第二个参数是区块
+        let block = serde_json::json!(format!("0x{:x}", log.block_number));
+        if let Some(items) = params.as_array_mut() {
+            items.truncate(1);
+            items.push(block);
+        }
+
+        let endpoints: Vec<&Endpoint> = self
+            .ranked()
+            .into_iter()
+            .take(log.quorum.providers)
+            .collect();
+        let responses = join_all(endpoints.iter().map(|endpoint| {
+            let params = &params;
+            async move {
+                endpoint.take_token().await;
+                let vote = match endpoint.request::<serde_json::Value>(method, params).await {
+                    Ok(value) => Ok(QuorumVote::Value(value)),
+                    Err(HttpClientError::JsonRpcError(error))
+                        if !HttpRateLimitRetryPolicy
+                            .should_retry(&HttpClientError::JsonRpcError(error.clone())) =>
+                    {
+                        Ok(QuorumVote::Error(error))
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+                (endpoint.provider.name.as_str(), vote)
+            }
+        }))
+        .await;
+
+        // 按结果分组，找出票数最多的一组
+        let mut groups: Vec<(&QuorumVote, usize)> = Vec::new();
+        for vote in responses.iter().filter_map(|(_, vote)| vote.as_ref().ok()) {
+            match groups.iter_mut().find(|(existing, _)| existing.same_as(vote)) {
+                Some((_, count)) => *count += 1,
+                None => groups.push((vote, 1)),
+            }
+        }
+        let winner = groups.iter().max_by_key(|(_, count)| *count).copied();
+
+        let describe = |vote: &std::result::Result<QuorumVote, String>| match vote {
+            Ok(vote) => vote.describe(),
+            Err(e) => format!("请求失败: {}", e),
+        };
+        let Some((accepted, count)) = winner.filter(|(_, count)| *count >= log.quorum.threshold)
+        else {
+            let details: Vec<String> = responses
+                .iter()
+                .map(|(name, vote)| format!("{} => {}", name, describe(vote)))
+                .collect();
+            return Err(RpcPoolError::Quorum(format!(
+                "{} 在区块 {} 上未能达到 {}/{} 一致: {}",
+                method,
+                log.block_number,
+                log.quorum.threshold,
+                log.quorum.providers,
+                details.join("; ")
+            )));
+        };
+
+        *log.reads.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        if count < responses.len() {
+            let mut disagreements = log.disagreements.lock().unwrap_or_else(|e| e.into_inner());
+            for (name, vote) in &responses {
+                if !matches!(vote, Ok(vote) if vote.same_as(accepted)) {
+                    warn!(rpc = %name, method, "RPC 返回结果与多数不一致");
+                    disagreements.push(QuorumDisagreement {
+                        method: method.to_string(),
+                        provider: name.to_string(),
+                        response: describe(vote),
+                        accepted: accepted.describe(),
+                    });
+                }
+            }
+        }
+
+        match accepted {
+            QuorumVote::Value(value) => Ok(value.clone()),
+            QuorumVote::Error(error) => Err(HttpClientError::JsonRpcError(error.clone()).into()),
+        }
+    }
+}
+
+/// 定期重新探测被停用的端点，连接池释放后退出
+async fn reprobe_loop(pool: Weak<PoolInner>) {
+    let mut interval = tokio::time::interval(PROBE_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let Some(pool) = pool.upgrade() else {
+            return;
+        };
+        reprobe_down_endpoints(&pool).await;
+    }
+}
+
+/// 重新探测停用的端点，链 ID 与连接池一致时才重新启用
+async fn reprobe_down_endpoints(pool: &PoolInner) {
+    let down: Vec<&Endpoint> = pool
+        .endpoints
+        .iter()
+        .filter(|endpoint| endpoint.health().down)
+        .collect();
+    for endpoint in down {
+        match endpoint.probe().await {
+            Some((chain_id, latency)) if chain_id == pool.chain_id => {
+                endpoint.health().record_success(latency);
+                info!(rpc = %endpoint.provider.name, "RPC 端点已恢复");
+            }
+            Some((chain_id, _)) => {
+                warn!(rpc = %endpoint.provider.name, "RPC 端点返回的链 ID 为 {}，保持停用", chain_id);
+            }
+            None => {}
+        }
+    }
+}
+
+/// 在本地启动一个 JSON-RPC 服务：eth_chainId 返回 chain_id，其余方法都返回 `result`
+#[cfg(test)]
+pub(crate) async fn spawn_stub_rpc(chain_id: u64, result: &'static str) -> String {
+    use axum::{routing::post, Json, Router};
+
+    let app = Router::new().route(
+        "/",
+        post(move |Json(request): Json<serde_json::Value>| async move {
+            let result = match request["method"].as_str() {
+                Some("eth_chainId") => serde_json::json!(format!("0x{:x}", chain_id)),
+                _ => serde_json::json!(result),
+            };
+            Json(serde_json::json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 绑定后立即释放的端口，连接会被拒绝
+    async fn dead_url() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_healthy_endpoint() {
+        let dead = dead_url().await;
+        let alive = spawn_stub_rpc(1, "0x10").await;
+        let pool = RpcPool::connect(
+            vec![RpcProvider::configured(&dead)],
+            |_| vec![RpcProvider::configured(&alive)],
+            Some(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pool.chain_id(), 1);
+
+        // 让配置的端点重新排在最前，请求失败后应落到备用端点上
+        pool.inner.endpoints[0].health().down = false;
+        let block: ethers::types::U64 = pool.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block.as_u64(), 16);
+
+        let status = pool.status();
+        assert!(status[0].failures >= 2, "{status:?}");
+        assert!(status[1].healthy);
+    }
+
+    #[tokio::test]
+    async fn configured_endpoint_on_wrong_chain_is_rejected() {
+        let url = spawn_stub_rpc(10, "0x10").await;
+        let err = RpcPool::connect(vec![RpcProvider::configured(&url)], |_| vec![], Some(1))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("10"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn chain_id_is_never_taken_from_public_fallbacks() {
+        let dead = dead_url().await;
+        let public = spawn_stub_rpc(1, "0x10").await;
+        let err = RpcPool::connect(
+            vec![RpcProvider::configured(&dead)],
+            |_| vec![RpcProvider::configured(&public)],
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("无法确定链 ID"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn fallbacks_are_requested_for_the_configured_chain() {
+        let base = spawn_stub_rpc(8453, "0x10").await;
+        let mut requested = None;
+        let pool = RpcPool::connect(
+            vec![RpcProvider::configured(&base)],
+            |chain_id| {
+                requested = Some(chain_id);
+                vec![]
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pool.chain_id(), 8453);
+        assert_eq!(requested, Some(Some(8453)));
+        assert_eq!(pool.status().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reprobe_only_restores_endpoints_on_the_pool_chain() {
+        let wrong = spawn_stub_rpc(5, "0x10").await;
+        let right = spawn_stub_rpc(1, "0x10").await;
+        let inner = PoolInner {
+            endpoints: vec![
+                Endpoint::new(RpcProvider::configured(&wrong), false).unwrap(),
+                Endpoint::new(RpcProvider::configured(&right), false).unwrap(),
+            ],
+            chain_id: 1,
+        };
+        for endpoint in &inner.endpoints {
+            endpoint.health().down = true;
+        }
+
+        reprobe_down_endpoints(&inner).await;
+        assert!(inner.endpoints[0].health().down);
+        assert!(!inner.endpoints[1].health().down);
+    }
+
+    async fn quorum_pool(results: [&'static str; 3]) -> RpcPool {
+        let mut providers = Vec::new();
+        for result in results {
+            providers.push(RpcProvider::configured(&spawn_stub_rpc(1, result).await));
+        }
+        RpcPool::connect(providers, |_| vec![], Some(1))
+            .await
+            .unwrap()
+            .with_quorum(QuorumConfig {
+                enabled: true,
+                providers: 3,
+                threshold: 2,
+            })
+    }
+
+    #[tokio::test]
+    async fn quorum_returns_majority_and_reports_disagreement() {
+        let pool = quorum_pool(["0x64", "0x64", "0x65"]).await;
+        let (pinned, log) = pool.pin_quorum(16).unwrap();
+        let balance: ethers::types::U256 = pinned
+            .request("eth_getBalance", (ethers::types::Address::zero(), "latest"))
+            .await
+            .unwrap();
+        assert_eq!(balance.as_u64(), 100);
+
+        let report = log.report();
+        assert_eq!(report.block_number, 16);
+        assert_eq!(report.reads, 1);
+        assert_eq!(report.disagreements.len(), 1);
+        assert_eq!(report.disagreements[0].response, "\"0x65\"");
+    }
+
+    #[tokio::test]
+    async fn quorum_fails_without_agreement() {
+        let pool = quorum_pool(["0x1", "0x2", "0x3"]).await;
+        let (pinned, _) = pool.pin_quorum(16).unwrap();
+        let err = pinned
+            .request::<_, ethers::types::U256>("eth_getBalance", (ethers::types::Address::zero(), "latest"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RpcPoolError::Quorum(_)), "{err}");
+    }
+
+    #[test]
+    fn token_bucket_enforces_rate_limit() {
+        let mut bucket = TokenBucket::per_minute(60);
+        assert!(bucket.try_take().is_ok());
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait <= Duration::from_secs(1), "{wait:?}");
+    }
+
+    #[test]
+    fn failing_endpoint_scores_worse() {
+        let mut fast = EndpointHealth::default();
+        fast.record_success(Duration::from_millis(50));
+        fast.record_failure();
+        let mut slow = EndpointHealth::default();
+        slow.record_success(Duration::from_millis(120));
+        assert!(fast.score() > slow.score());
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            fast.record_failure();
+        }
+        assert!(fast.down);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::config::{EthereumConfig, SignerConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use ethers::{
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::TypedData},
+        Address, Bytes, Signature,
+    },
+};
+use serde_json::json;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::timeout;
+use tracing::info;
+
+/// 外部签名器可能需要人工确认，给足等待时间
+const EXTERNAL_SIGNER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 交易签名后端
+///
+/// `EthereumClient` 只通过该 trait 签名，不关心私钥来自哪里。
+#[async_trait]
+pub trait TransactionSigner: Send + Sync {
+    /// 签名账户地址
+    fn address(&self) -> Address;
+
+    /// 签名后端类型，用于日志
+    fn kind(&self) -> &'static str;
+
+    /// 对交易签名，返回 RLP 编码的已签名交易
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Bytes>;
+
+    /// 对 EIP-712 结构化数据签名（如 Permit2 授权）
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<Signature>;
+}
+
+/// 进程内持有私钥的签名器（私钥、加密 keystore 或助记词派生）
+pub struct LocalSigner {
+    wallet: LocalWallet,
+    kind: &'static str,
+}
+
+impl LocalSigner {
+    pub fn from_private_key(private_key: &str) -> Result<Self> {
+        let hex_part = private_key.trim().strip_prefix("0x").unwrap_or(private_key.trim());
+        if hex_part.len() != 64 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("PRIVATE_KEY 格式错误：应为 64 位十六进制字符（可带 0x 前缀）");
+        }
+        let wallet = LocalWallet::from_str(hex_part)
+            .context("解析 PRIVATE_KEY 失败，私钥不是有效的 secp256k1 密钥")?;
+        Ok(Self {
+            wallet,
+            kind: "private_key",
+        })
+    }
+
+    pub fn from_keystore(path: &Path, password: &str) -> Result<Self> {
+        let wallet = LocalWallet::decrypt_keystore(path, password)
+            .with_context(|| format!("解密 keystore {} 失败，请检查密码", path.display()))?;
+        Ok(Self {
+            wallet,
+            kind: "keystore",
+        })
+    }
+
+    pub fn from_mnemonic(phrase: &str, derivation_path: &str) -> Result<Self> {
+        let wallet = MnemonicBuilder::<English>::default()
+            .phrase(phrase.trim())
+            .derivation_path(derivation_path)
+            .with_context(|| format!("无效的派生路径: {}", derivation_path))?
+            .build()
+            .context("由助记词派生钱包失败，请检查助记词")?;
+        Ok(Self {
+            wallet,
+            kind: "mnemonic",
+        })
+    }
+}
+
+#[async_trait]
+impl TransactionSigner for LocalSigner {
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Bytes> {
+        let signature = self
+            .wallet
+            .sign_transaction(tx)
+            .await
+            .context("签名交易失败")?;
+        Ok(tx.rlp_signed(&signature))
+    }
+
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<Signature> {
+        self.wallet
+            .sign_typed_data(data)
+            .await
+            .context("签名 EIP-712 数据失败")
+    }
+}
+
+/// 外部签名进程的连接方式
+#[derive(Debug, Clone)]
+enum ExternalEndpoint {
+    /// 本地 IPC socket，如 clef 的 `clef.ipc`
+    Ipc(PathBuf),
+    /// HTTP 端点，如 `clef --http` 的 `http://127.0.0.1:8550`
+    Http(url::Url),
+}
+
+/// 通过 JSON-RPC 委托外部进程（如 Clef）签名，私钥不进入本进程
+pub struct ExternalSigner {
+    endpoint: ExternalEndpoint,
+    address: Address,
+    http: reqwest::Client,
+    next_id: AtomicU64,
+}
+
+impl ExternalSigner {
+    /// 连接外部签名器；未指定账户时使用 `account_list` 返回的第一个账户
+    pub async fn connect(endpoint: &str, address: Option<Address>) -> Result<Self> {
+        let endpoint = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            ExternalEndpoint::Http(
+                url::Url::parse(endpoint)
+                    .with_context(|| format!("无效的外部签名器地址: {}", endpoint))?,
+            )
+        } else {
+            ExternalEndpoint::Ipc(PathBuf::from(endpoint))
+        };
+
+        let mut signer = Self {
+            endpoint,
+            address: address.unwrap_or_default(),
+            http: reqwest::Client::new(),
+            next_id: AtomicU64::new(1),
+        };
+
+        let accounts: Vec<Address> = serde_json::from_value(
+            signer
+                .call("account_list", json!([]))
+                .await
+                .context("查询外部签名器账户失败")?,
+        )
+        .context("解析 account_list 响应失败")?;
+
+        signer.address = match address {
+            Some(address) if accounts.contains(&address) => address,
+            Some(address) => bail!("外部签名器中不存在账户 {:?}", address),
+            None => *accounts
+                .first()
+                .ok_or_else(|| anyhow!("外部签名器没有可用账户"))?,
+        };
+
+        Ok(signer)
+    }
+
+    async fn call(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+
+        let response = timeout(EXTERNAL_SIGNER_TIMEOUT, self.send(&request))
+            .await
+            .map_err(|_| anyhow!("外部签名器 {} 超时", method))??;
+
+        if let Some(error) = response.get("error") {
+            bail!("外部签名器返回错误: {}", error);
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("外部签名器响应缺少 result"))
+    }
+
+    async fn send(&self, request: &serde_json::Value) -> Result<serde_json::Value> {
+        match &self.endpoint {
+            ExternalEndpoint::Http(url) => self
+                .http
+                .post(url.clone())
+                .json(request)
+                .send()
+                .await
+                .context("连接外部签名器失败")?
+                .json()
+                .await
+                .context("解析外部签名器响应失败"),
+            ExternalEndpoint::Ipc(path) => send_ipc(path, request).await,
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn send_ipc(path: &Path, request: &serde_json::Value) -> Result<serde_json::Value> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut stream = tokio::net::UnixStream::connect(path)
+        .await
+        .with_context(|| format!("连接外部签名器 {} 失败", path.display()))?;
+    let mut payload = serde_json::to_vec(request)?;
+    payload.push(b'\n');
+    stream.write_all(&payload).await?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).await?;
+    serde_json::from_str(&line).context("解析外部签名器响应失败")
+}
+
+#[cfg(not(unix))]
+async fn send_ipc(path: &Path, _request: &serde_json::Value) -> Result<serde_json::Value> {
+    bail!("当前平台不支持 IPC 外部签名器: {}", path.display())
+}
+
+#[async_trait]
+impl TransactionSigner for ExternalSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn kind(&self) -> &'static str {
+        "external"
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Bytes> {
+        let mut args = json!({
+            "from": self.address,
+            "to": tx.to_addr(),
+            "gas": tx.gas(),
+            "value": tx.value().copied().unwrap_or_default(),
+            "nonce": tx.nonce(),
+            "chainId": tx.chain_id(),
+            "input": tx.data().cloned().unwrap_or_default(),
+        });
+        match tx {
+            TypedTransaction::Eip1559(inner) => {
+                args["maxFeePerGas"] = json!(inner.max_fee_per_gas);
+                args["maxPriorityFeePerGas"] = json!(inner.max_priority_fee_per_gas);
+            }
+            _ => args["gasPrice"] = json!(tx.gas_price()),
+        }
+
+        let result = self.call("account_signTransaction", json!([args])).await?;
+        let raw = result
+            .get("raw")
+            .and_then(|raw| raw.as_str())
+            .ok_or_else(|| anyhow!("外部签名器响应缺少 raw 字段"))?;
+        Bytes::from_str(raw).context("解析外部签名器返回的交易失败")
+    }
+
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<Signature> {
+        let result = self
+            .call("account_signTypedData", json!([self.address, data]))
+            .await?;
+        let signature = result
+            .as_str()
+            .ok_or_else(|| anyhow!("外部签名器返回的签名格式错误"))?;
+        Signature::from_str(signature).context("解析外部签名器返回的签名失败")
+    }
+}
+
+/// 按配置构造签名器，既没有私钥也没有 [signer] 时返回 None（只读模式）
+pub async fn build_signer(
+    ethereum: &EthereumConfig,
+    signer: Option<&SignerConfig>,
+) -> Result<Option<Arc<dyn TransactionSigner>>> {
+    let signer: Arc<dyn TransactionSigner> = match (signer, &ethereum.private_key) {
+        (Some(_), Some(_)) => bail!("同时配置了 private_key 和 [signer]，请只保留一种签名方式"),
+        (None, None) => return Ok(None),
+        (None, Some(private_key)) => Arc::new(LocalSigner::from_private_key(private_key)?),
+        (Some(SignerConfig::Keystore { path, password_file }), None) => {
+            let password = match password_file {
+                Some(file) => read_secret_file(file)?,
+                None => rpassword::prompt_password(format!("输入 keystore {} 的密码: ", path.display()))
+                    .context("读取 keystore 密码失败")?,
+            };
+            Arc::new(LocalSigner::from_keystore(path, &password)?)
+        }
+        (
+            Some(SignerConfig::Mnemonic {
+                phrase_file,
+                derivation_path,
+            }),
+            None,
+        ) => {
+            let phrase = match (phrase_file, std::env::var("MNEMONIC")) {
+                (_, Ok(phrase)) if !phrase.trim().is_empty() => phrase,
+                (Some(file), _) => read_secret_file(file)?,
+                (None, _) => bail!("助记词签名需要设置 MNEMONIC 环境变量或 phrase_file"),
+            };
+            Arc::new(LocalSigner::from_mnemonic(&phrase, derivation_path)?)
+        }
+        (Some(SignerConfig::External { endpoint, address }), None) => {
+            Arc::new(ExternalSigner::connect(endpoint, *address).await?)
+        }
+    };
+
+    info!(
+        signer = signer.kind(),
+        address = %format!("0x{:x}", signer.address()),
+        "签名器已加载"
+    );
+    Ok(Some(signer))
+}
+
+fn read_secret_file(path: &Path) -> Result<String> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("读取 {} 失败", path.display()))?;
+    Ok(content.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hardhat 默认助记词的第一个账户
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn mnemonic_derives_expected_account() {
+        let signer = LocalSigner::from_mnemonic(TEST_MNEMONIC, "m/44'/60'/0'/0/0").unwrap();
+        assert_eq!(
+            signer.address(),
+            Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_private_key_is_rejected() {
+        assert!(LocalSigner::from_private_key("0x1234").is_err());
+        assert!(LocalSigner::from_private_key(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        )
+        .is_ok());
+    }
+}
@@ -10,6 +10,9 @@ pub struct Balance {
     pub balance: Decimal,
     pub decimals: u8,
     pub formatted_balance: String,
+    /// 仅在开启仲裁读取时返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumReport>,
 }
 
 #[allow(dead_code)]
@@ -20,6 +23,9 @@ pub struct TokenPrice {
     pub price: Decimal,
     pub quote_currency: String,
     pub timestamp: u64,
+    /// 仅在开启仲裁读取时返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumReport>,
 }
 
 #[allow(dead_code)]
@@ -29,17 +35,157 @@ pub struct SwapResult {
     pub to_token: String,
     pub input_amount: Decimal,
     pub output_amount: Decimal,
+    /// 相对池子中间价的价格影响（百分比），不含 LP 手续费
     pub price_impact: Decimal,
+    /// 路径上各池子 LP 手续费合计（百分比）
+    pub lp_fee_pct: Decimal,
     pub gas_estimate: u64,
-    pub gas_price: Decimal,
-    pub total_cost: Decimal,
+    /// swap 交易设置的 gas 上限；需要授权时无法模拟，取 gas 模型估算的两倍
+    pub gas_limit: u64,
+    /// EIP-1559 费用档位：slow / normal / fast
+    pub gas_speed: String,
+    /// 下一个区块的 base fee（gwei）
+    pub base_fee: Decimal,
+    /// maxPriorityFeePerGas（gwei）
+    pub priority_fee: Decimal,
+    /// maxFeePerGas（gwei）
+    pub max_fee: Decimal,
+    /// 按 base fee 加小费预计的 gas 花费（原生代币），需要授权时包含 approve 交易
+    pub gas_cost_eth: Decimal,
+    /// 预计 gas 花费折合 USD，无法获取原生代币价格时为空
+    pub gas_cost_usd: Option<Decimal>,
+    /// 固定输入时预计 gas 花费折合的输出代币数量，无法取得输出代币的 ETH 价格时为空
+    pub gas_cost_in_output: Option<Decimal>,
+    /// 固定输入时扣除 gas 花费后的净输出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_output: Option<Decimal>,
+    /// 按 maxFeePerGas 计算的最高 gas 花费（原生代币），需要授权时包含 approve 交易
+    pub max_cost_eth: Decimal,
+    /// 最高 gas 花费折合 USD，无法获取原生代币价格时为空
+    pub max_cost_usd: Option<Decimal>,
     pub slippage_tolerance: Decimal,
+    /// exact_input 或 exact_output
+    pub mode: String,
+    /// 固定输出时等于 output_amount
     pub minimum_output: Decimal,
+    /// 固定输出时按滑点放宽后的最大输入
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum_input: Option<Decimal>,
     pub protocol: String,
-    pub fee_tier: Option<u32>,
+    /// V3 每一跳的费率，与 `path` 中相邻两个代币对应；V2 为空
+    pub fee_tier: Vec<u32>,
     pub router_address: String,
     pub path: Vec<String>,
     pub transaction_data: String,
+    /// 授权额度不足时需要先广播的 approve 交易，nonce 排在 swap 之前
+    pub approval: Option<ApprovalTransaction>,
+    /// 拆单路由的各条腿，单一路由时为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub legs: Vec<SwapLeg>,
+    /// 经 Universal Router 执行时随交易提交的 Permit2 签名授权
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permit2: Option<Permit2Authorization>,
+}
+
+/// 拆单路由中的一条腿
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub protocol: String,
+    pub path: Vec<String>,
+    pub fee_tier: Vec<u32>,
+    /// 分得的输入占比（百分比）
+    pub share: Decimal,
+    pub input_amount: Decimal,
+    pub output_amount: Decimal,
+}
+
+/// swap 之前需要发送的 ERC20 授权交易
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalTransaction {
+    pub token: String,
+    pub spender: String,
+    pub current_allowance: Decimal,
+    /// 授权数量，无限授权时为 "unlimited"
+    pub amount: String,
+    pub unlimited: bool,
+    pub gas_estimate: u64,
+    pub transaction_data: String,
+}
+
+/// 已签名的 Permit2 授权，作为 Universal Router 的第一条命令执行，无需单独上链
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Permit2Authorization {
+    pub token: String,
+    /// 被授权的路由合约
+    pub spender: String,
+    /// 授权数量，无限授权时为 "unlimited"
+    pub amount: String,
+    /// 授权过期时间（unix 秒）
+    pub expiration: u64,
+    pub nonce: u64,
+    /// 签名的有效截止时间（unix 秒）
+    pub sig_deadline: u64,
+}
+
+/// wrap_eth / unwrap_weth 返回的已签名交易
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrapResult {
+    /// wrap 或 unwrap
+    pub action: String,
+    pub wrapped_token: String,
+    pub amount: Decimal,
+    pub gas_estimate: u64,
+    /// maxPriorityFeePerGas（gwei）
+    pub priority_fee: Decimal,
+    /// maxFeePerGas（gwei）
+    pub max_fee: Decimal,
+    pub transaction_data: String,
+}
+
+/// 已广播兑换交易的回执
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapExecution {
+    pub transaction_hash: String,
+    /// 先行广播的 approve 交易
+    pub approval_transaction_hash: Option<String>,
+    /// success 或 reverted
+    pub status: String,
+    pub block_number: Option<u64>,
+    pub confirmations: usize,
+    pub gas_used: Option<u64>,
+    /// 实际 gas 价格（gwei）
+    pub effective_gas_price: Option<Decimal>,
+    /// 实际 gas 花费（原生代币）
+    pub gas_cost: Option<Decimal>,
+    /// 由回执中的 Transfer 日志解析出的实际到账数量
+    pub actual_output: Decimal,
+    pub swap: SwapResult,
+}
+
+/// speed_up_transaction / cancel_transaction 广播的替换交易
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacementResult {
+    /// speed_up 或 cancel
+    pub action: String,
+    pub original_transaction_hash: String,
+    pub transaction_hash: String,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    /// 原交易的 maxFeePerGas（legacy 交易为 gasPrice，gwei）
+    pub previous_max_fee: Decimal,
+    /// 原交易的 maxPriorityFeePerGas（legacy 交易为 gasPrice，gwei）
+    pub previous_priority_fee: Decimal,
+    /// maxFeePerGas（gwei）
+    pub max_fee: Decimal,
+    /// maxPriorityFeePerGas（gwei）
+    pub priority_fee: Decimal,
+    pub transaction_data: String,
 }
 
 #[allow(dead_code)]
@@ -51,3 +197,39 @@ pub struct TokenInfo {
     pub decimals: u8,
     pub is_native: bool,
 }
+
+/// 服务器已连接的一条链，供 list_chains 返回
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSummary {
+    pub chain_id: u64,
+    pub name: String,
+    pub native_symbol: String,
+    pub wrapped_native: String,
+    pub protocols: Vec<String>,
+    pub tokens: Vec<String>,
+    pub is_default: bool,
+}
+
+/// 一次查询中所有仲裁读取的汇总
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuorumReport {
+    /// 所有读取固定在该区块
+    pub block_number: u64,
+    pub providers: usize,
+    pub threshold: usize,
+    /// 经过仲裁的 RPC 读取次数
+    pub reads: usize,
+    /// 与多数结果不一致（或未响应）的提供者
+    pub disagreements: Vec<QuorumDisagreement>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuorumDisagreement {
+    pub method: String,
+    pub provider: String,
+    pub response: String,
+    pub accepted: String,
+}
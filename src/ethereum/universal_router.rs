@@ -0,0 +1,338 @@
+use crate::ethereum::client::{encode_v3_path, RouteLeg, SwapProtocol};
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    abi::{encode, Token, Tokenizable},
+    types::{transaction::eip712::TypedData, Address, Bytes, Signature, U256},
+};
+use serde_json::json;
+
+/// Universal Router 命令字节（见 Uniswap universal-router 的 Commands.sol）
+pub const V3_SWAP_EXACT_IN: u8 = 0x00;
+pub const V2_SWAP_EXACT_IN: u8 = 0x08;
+pub const PERMIT2_PERMIT: u8 = 0x0a;
+pub const WRAP_ETH: u8 = 0x0b;
+pub const UNWRAP_WETH: u8 = 0x0c;
+pub const V4_SWAP: u8 = 0x10;
+
+/// V4_SWAP 内部的 V4Router 动作（见 v4-periphery 的 Actions.sol）
+const V4_SWAP_EXACT_IN_SINGLE: u8 = 0x06;
+const V4_SETTLE_ALL: u8 = 0x0c;
+const V4_TAKE: u8 = 0x0e;
+
+/// 命令参数中的特殊地址：路由合约自身
+const ADDRESS_THIS: u64 = 2;
+
+/// 已签名的 Permit2 `PermitSingle`，随 PERMIT2_PERMIT 命令提交
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct PermitSingle {
+    pub token: Address,
+    /// uint160
+    pub amount: U256,
+    /// uint48，授权过期时间（unix 秒）
+    pub expiration: u64,
+    /// uint48，Permit2 中 (owner, token, spender) 的当前 nonce
+    pub nonce: u64,
+    pub spender: Address,
+    pub sig_deadline: u64,
+    pub signature: Signature,
+}
+
+/// Permit2 `PermitSingle` 的 EIP-712 结构化数据
+#[allow(clippy::too_many_arguments)]
+pub fn permit_single_typed_data(
+    chain_id: u64,
+    permit2: Address,
+    token: Address,
+    amount: U256,
+    expiration: u64,
+    nonce: u64,
+    spender: Address,
+    sig_deadline: u64,
+) -> Result<TypedData> {
+    serde_json::from_value(json!({
+        "types": {
+            "EIP712Domain": [
+                {"name": "name", "type": "string"},
+                {"name": "chainId", "type": "uint256"},
+                {"name": "verifyingContract", "type": "address"}
+            ],
+            "PermitDetails": [
+                {"name": "token", "type": "address"},
+                {"name": "amount", "type": "uint160"},
+                {"name": "expiration", "type": "uint48"},
+                {"name": "nonce", "type": "uint48"}
+            ],
+            "PermitSingle": [
+                {"name": "details", "type": "PermitDetails"},
+                {"name": "spender", "type": "address"},
+                {"name": "sigDeadline", "type": "uint256"}
+            ]
+        },
+        "primaryType": "PermitSingle",
+        "domain": {
+            "name": "Permit2",
+            "chainId": chain_id,
+            "verifyingContract": permit2
+        },
+        "message": {
+            "details": {
+                "token": token,
+                "amount": amount.to_string(),
+                "expiration": expiration,
+                "nonce": nonce
+            },
+            "spender": spender,
+            "sigDeadline": sig_deadline.to_string()
+        }
+    }))
+    .context("构造 Permit2 EIP-712 数据失败")
+}
+
+/// 把路由腿编排成 Universal Router 的 `(commands, inputs)`
+///
+/// 输入为原生代币时先 WRAP_ETH 到路由合约、各腿由路由合约付款；
+/// 输出为原生代币时各腿把 WETH 留在路由合约，最后 UNWRAP_WETH 给 `recipient`。
+/// 各腿的最少输出按报价比例分摊 `amount_out_min`。
+/// V4 池子直接以原生代币结算，不需要 wrap / unwrap，且只能作为唯一的一条腿。
+pub fn encode_swap_commands(
+    legs: &[RouteLeg],
+    native_in: bool,
+    native_out: bool,
+    amount_out_min: U256,
+    recipient: Address,
+    permit: Option<&PermitSingle>,
+) -> Result<(Bytes, Vec<Bytes>)> {
+    let total_out = legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_out);
+    if total_out.is_zero() {
+        bail!("Universal Router 路由没有有效的腿");
+    }
+    let v4 = legs.iter().any(|leg| leg.protocol == SwapProtocol::UniswapV4);
+    if v4 && legs.len() > 1 {
+        bail!("V4 池子只能作为单条路由执行");
+    }
+    let (wrap_in, unwrap_out) = (native_in && !v4, native_out && !v4);
+
+    let mut commands = Vec::with_capacity(legs.len() + 2);
+    let mut inputs = Vec::with_capacity(legs.len() + 2);
+    let router_itself = Address::from_low_u64_be(ADDRESS_THIS);
+
+    if let Some(permit) = permit.filter(|_| !native_in) {
+        commands.push(PERMIT2_PERMIT);
+        inputs.push(encode(&[
+            Token::Tuple(vec![
+                Token::Tuple(vec![
+                    Token::Address(permit.token),
+                    Token::Uint(permit.amount),
+                    Token::Uint(permit.expiration.into()),
+                    Token::Uint(permit.nonce.into()),
+                ]),
+                Token::Address(permit.spender),
+                Token::Uint(permit.sig_deadline.into()),
+            ]),
+            Token::Bytes(permit.signature.to_vec()),
+        ]));
+    }
+
+    if wrap_in {
+        let amount_in = legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_in);
+        commands.push(WRAP_ETH);
+        inputs.push(encode(&[Token::Address(router_itself), Token::Uint(amount_in)]));
+    }
+
+    let leg_recipient = if unwrap_out { router_itself } else { recipient };
+    for leg in legs {
+        let leg_min = leg.amount_out * amount_out_min / total_out;
+        match leg.protocol {
+            SwapProtocol::UniswapV2 => {
+                commands.push(V2_SWAP_EXACT_IN);
+                inputs.push(encode(&[
+                    Token::Address(leg_recipient),
+                    Token::Uint(leg.amount_in),
+                    Token::Uint(leg_min),
+                    Token::Array(leg.path.iter().copied().map(Token::Address).collect()),
+                    Token::Bool(!native_in),
+                ]));
+            }
+            SwapProtocol::UniswapV3 => {
+                commands.push(V3_SWAP_EXACT_IN);
+                inputs.push(encode(&[
+                    Token::Address(leg_recipient),
+                    Token::Uint(leg.amount_in),
+                    Token::Uint(leg_min),
+                    Token::Bytes(encode_v3_path(&leg.path, &leg.fees)?.to_vec()),
+                    Token::Bool(!native_in),
+                ]));
+            }
+            SwapProtocol::UniswapV4 => {
+                commands.push(V4_SWAP);
+                inputs.push(encode_v4_exact_in_single(leg, leg_min, leg_recipient)?);
+            }
+            other => bail!("Universal Router 不支持 {} 腿", other.as_str()),
+        }
+    }
+
+    if unwrap_out {
+        commands.push(UNWRAP_WETH);
+        inputs.push(encode(&[Token::Address(recipient), Token::Uint(amount_out_min)]));
+    }
+
+    Ok((
+        Bytes::from(commands),
+        inputs.into_iter().map(Bytes::from).collect(),
+    ))
+}
+
+/// V4_SWAP 命令参数：单池固定输入兑换，随后 SETTLE_ALL 支付输入、TAKE 把全部输出转给 `recipient`
+fn encode_v4_exact_in_single(leg: &RouteLeg, amount_out_min: U256, recipient: Address) -> Result<Vec<u8>> {
+    let key = leg
+        .pool_key()
+        .cloned()
+        .ok_or_else(|| anyhow!("V4 腿缺少 PoolKey"))?;
+    let (currency_in, currency_out) = (leg.path[0], leg.path[leg.path.len() - 1]);
+    let zero_for_one = currency_in == key.currency_0;
+
+    let params = [
+        encode(&[Token::Tuple(vec![
+            key.into_token(),
+            Token::Bool(zero_for_one),
+            Token::Uint(leg.amount_in),
+            Token::Uint(amount_out_min),
+            Token::Bytes(Vec::new()),
+        ])]),
+        encode(&[Token::Address(currency_in), Token::Uint(leg.amount_in)]),
+        // 数量为 0 表示取走全部未结清的输出
+        encode(&[
+            Token::Address(currency_out),
+            Token::Address(recipient),
+            Token::Uint(U256::zero()),
+        ]),
+    ];
+
+    Ok(encode(&[
+        Token::Bytes(vec![V4_SWAP_EXACT_IN_SINGLE, V4_SETTLE_ALL, V4_TAKE]),
+        Token::Array(params.into_iter().map(Token::Bytes).collect()),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::client::{v4_pool_key, PoolRef};
+    use crate::ethereum::signer::{LocalSigner, TransactionSigner};
+    use ethers::abi::{decode, ParamType};
+    use ethers::types::{transaction::eip712::Eip712, H256};
+    use std::str::FromStr;
+
+    fn leg(protocol: SwapProtocol, fees: Vec<u32>, amount_out: u64) -> RouteLeg {
+        RouteLeg {
+            protocol,
+            path: vec![Address::from_low_u64_be(10), Address::from_low_u64_be(11)],
+            fees,
+            amount_in: U256::from(1000),
+            amount_out: U256::from(amount_out),
+            pool: None,
+        }
+    }
+
+    #[test]
+    fn native_output_unwraps_after_swaps() {
+        let legs = vec![
+            leg(SwapProtocol::UniswapV3, vec![500], 600),
+            leg(SwapProtocol::UniswapV2, vec![], 400),
+        ];
+        let (commands, inputs) = encode_swap_commands(
+            &legs,
+            false,
+            true,
+            U256::from(900),
+            Address::from_low_u64_be(99),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(commands.to_vec(), vec![V3_SWAP_EXACT_IN, V2_SWAP_EXACT_IN, UNWRAP_WETH]);
+        assert_eq!(inputs.len(), 3);
+        // 第一条腿的 recipient 是路由合约自身，最少输出按 600 / 1000 分摊
+        assert_eq!(&inputs[0][12..32], Address::from_low_u64_be(ADDRESS_THIS).as_bytes());
+        assert_eq!(U256::from_big_endian(&inputs[0][64..96]), U256::from(540));
+    }
+
+    #[test]
+    fn native_v4_swap_settles_without_wrapping() {
+        let token = Address::from_low_u64_be(11);
+        let mut v4_leg = leg(SwapProtocol::UniswapV4, vec![3000], 500);
+        v4_leg.path = vec![Address::zero(), token];
+        v4_leg.pool = Some(PoolRef::UniswapV4(v4_pool_key(
+            token,
+            Address::zero(),
+            3000,
+            60,
+            Address::zero(),
+        )));
+
+        let (commands, inputs) = encode_swap_commands(
+            &[v4_leg.clone()],
+            true,
+            false,
+            U256::from(450),
+            Address::from_low_u64_be(99),
+            None,
+        )
+        .unwrap();
+        assert_eq!(commands.to_vec(), vec![V4_SWAP]);
+
+        let decoded = decode(
+            &[ParamType::Bytes, ParamType::Array(Box::new(ParamType::Bytes))],
+            &inputs[0],
+        )
+        .unwrap();
+        assert_eq!(
+            decoded[0],
+            Token::Bytes(vec![V4_SWAP_EXACT_IN_SINGLE, V4_SETTLE_ALL, V4_TAKE])
+        );
+        // 原生代币地址最小，总是 currency0，卖出 ETH 即 zeroForOne
+        let Token::Array(params) = &decoded[1] else {
+            panic!("V4_SWAP 参数格式错误");
+        };
+        let Token::Bytes(swap) = &params[0] else {
+            panic!("V4_SWAP 参数格式错误");
+        };
+        assert_eq!(&swap[32 + 12..64], Address::zero().as_bytes());
+        assert_eq!(U256::from_big_endian(&swap[32 * 6..32 * 7]), U256::one());
+
+        assert!(encode_swap_commands(
+            &[v4_leg.clone(), leg(SwapProtocol::UniswapV2, vec![], 100)],
+            false,
+            false,
+            U256::zero(),
+            Address::from_low_u64_be(99),
+            None,
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn permit_signature_recovers_to_wallet() {
+        let signer = LocalSigner::from_private_key(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap();
+        let permit2 = Address::from_str(crate::ethereum::chains::PERMIT2).unwrap();
+        let data = permit_single_typed_data(
+            1,
+            permit2,
+            Address::from_low_u64_be(10),
+            U256::from(1_000_000),
+            1_700_000_000,
+            0,
+            Address::from_low_u64_be(20),
+            1_700_000_900,
+        )
+        .unwrap();
+
+        let signature = signer.sign_typed_data(&data).await.unwrap();
+        let digest = H256::from(data.encode_eip712().unwrap());
+        assert_eq!(signature.recover(digest).unwrap(), signer.address());
+    }
+}
@@ -0,0 +1,481 @@
+//! Uniswap V3 池子的本地兑换模拟
+//!
+//! 移植 v3-core 的 TickMath / SqrtPriceMath / SwapMath 和 `swap` 主循环，整数运算与合约逐位一致，
+//! 报价和价格影响可以在本地由池子状态计算，不必每次调用 Quoter。
+
+use ethers::types::{Address, U256, U512};
+use std::collections::BTreeMap;
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+
+/// `getSqrtRatioAtTick(MIN_TICK)`
+pub const MIN_SQRT_RATIO: U256 = U256([4295128739, 0, 0, 0]);
+/// `getSqrtRatioAtTick(MAX_TICK)`
+pub const MAX_SQRT_RATIO: U256 = U256([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+/// 手续费以百万分之一为单位
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// TickMath 中 sqrt(1.0001)^(-2^i) 的 Q128 定点数，i = 0..19
+const TICK_RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// `TickMath.getSqrtRatioAtTick`：tick 对应的 sqrt(price) Q64.96
+pub fn sqrt_ratio_at_tick(tick: i32) -> Option<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK.unsigned_abs() {
+        return None;
+    }
+
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(TICK_RATIOS[0])
+    } else {
+        U256::one() << 128
+    };
+    for (bit, factor) in TICK_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128.128 转为 Q64.96 时向上取整
+    let round_up = !(ratio & U256::from(u32::MAX)).is_zero();
+    Some((ratio >> 32) + U256::from(round_up as u8))
+}
+
+/// `FullMath.mulDiv`：以 512 位中间值计算 floor(a * b / denominator)
+fn mul_div(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    U256::try_from(a.full_mul(b) / U512::from(denominator)).ok()
+}
+
+/// `FullMath.mulDivRoundingUp`
+fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let product = a.full_mul(b);
+    let denominator = U512::from(denominator);
+    let mut result = product / denominator;
+    if !(product % denominator).is_zero() {
+        result += U512::one();
+    }
+    U256::try_from(result).ok()
+}
+
+/// `UnsafeMath.divRoundingUp`
+fn div_rounding_up(a: U256, b: U256) -> U256 {
+    let quotient = a / b;
+    if (a % b).is_zero() {
+        quotient
+    } else {
+        quotient + 1
+    }
+}
+
+/// `SqrtPriceMath.getAmount0Delta`：两个价格之间的 token0 数量
+fn amount0_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    let (lower, upper) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+    if lower.is_zero() {
+        return None;
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = upper - lower;
+    if round_up {
+        Some(div_rounding_up(mul_div_rounding_up(numerator1, numerator2, upper)?, lower))
+    } else {
+        Some(mul_div(numerator1, numerator2, upper)? / lower)
+    }
+}
+
+/// `SqrtPriceMath.getAmount1Delta`：两个价格之间的 token1 数量
+fn amount1_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    let (lower, upper) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+    let q96 = U256::one() << 96;
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), upper - lower, q96)
+    } else {
+        mul_div(U256::from(liquidity), upper - lower, q96)
+    }
+}
+
+/// `SqrtPriceMath.getNextSqrtPriceFromInput`
+fn next_sqrt_price_from_input(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Option<U256> {
+    if sqrt_price.is_zero() || liquidity == 0 {
+        return None;
+    }
+    if amount_in.is_zero() {
+        return Some(sqrt_price);
+    }
+
+    let liquidity = U256::from(liquidity);
+    if zero_for_one {
+        // getNextSqrtPriceFromAmount0RoundingUp(add = true)
+        let numerator1 = liquidity << 96;
+        if let Some(denominator) = amount_in
+            .checked_mul(sqrt_price)
+            .and_then(|product| numerator1.checked_add(product))
+        {
+            return mul_div_rounding_up(numerator1, sqrt_price, denominator);
+        }
+        Some(div_rounding_up(numerator1, (numerator1 / sqrt_price).checked_add(amount_in)?))
+    } else {
+        // getNextSqrtPriceFromAmount1RoundingDown(add = true)
+        let quotient = if amount_in < U256::one() << 160 {
+            (amount_in << 96) / liquidity
+        } else {
+            mul_div(amount_in, U256::one() << 96, liquidity)?
+        };
+        let next = sqrt_price.checked_add(quotient)?;
+        (next < U256::one() << 160).then_some(next)
+    }
+}
+
+/// `sqrtPriceX96` 对应的中间价：1 单位（最小单位）输入代币可换得的输出代币数量，不含手续费
+///
+/// V4 池子的 slot0 使用相同的定点格式。
+pub fn sqrt_price_to_price(sqrt_price_x96: U256, zero_for_one: bool) -> f64 {
+    let sqrt_price =
+        sqrt_price_x96.to_string().parse::<f64>().unwrap_or(f64::NAN) / 2f64.powi(96);
+    let price = sqrt_price * sqrt_price;
+    if zero_for_one {
+        price
+    } else {
+        1.0 / price
+    }
+}
+
+/// 单步兑换的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// `SwapMath.computeSwapStep` 的固定输入分支：在 `[sqrt_current, sqrt_target]` 区间内尽量消耗剩余输入
+pub fn compute_swap_step(
+    sqrt_current: U256,
+    sqrt_target: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    fee_pips: u32,
+) -> Option<SwapStep> {
+    let zero_for_one = sqrt_current >= sqrt_target;
+    let amount_remaining_less_fee = mul_div(
+        amount_remaining,
+        U256::from(FEE_DENOMINATOR - fee_pips),
+        U256::from(FEE_DENOMINATOR),
+    )?;
+
+    let amount_to_target = if zero_for_one {
+        amount0_delta(sqrt_target, sqrt_current, liquidity, true)?
+    } else {
+        amount1_delta(sqrt_current, sqrt_target, liquidity, true)?
+    };
+    let sqrt_price_next = if amount_remaining_less_fee >= amount_to_target {
+        sqrt_target
+    } else {
+        next_sqrt_price_from_input(sqrt_current, liquidity, amount_remaining_less_fee, zero_for_one)?
+    };
+    let reached_target = sqrt_price_next == sqrt_target;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        (
+            if reached_target {
+                amount_to_target
+            } else {
+                amount0_delta(sqrt_price_next, sqrt_current, liquidity, true)?
+            },
+            amount1_delta(sqrt_price_next, sqrt_current, liquidity, false)?,
+        )
+    } else {
+        (
+            if reached_target {
+                amount_to_target
+            } else {
+                amount1_delta(sqrt_current, sqrt_price_next, liquidity, true)?
+            },
+            amount0_delta(sqrt_current, sqrt_price_next, liquidity, false)?,
+        )
+    };
+
+    // 没走到目标价格说明输入已耗尽，剩余部分全部计为手续费
+    let fee_amount = if reached_target {
+        mul_div_rounding_up(
+            amount_in,
+            U256::from(fee_pips),
+            U256::from(FEE_DENOMINATOR - fee_pips),
+        )?
+    } else {
+        amount_remaining.checked_sub(amount_in)?
+    };
+
+    Some(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// 模拟时缺少的池子状态，读取后重新模拟
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingState {
+    /// `tickBitmap(word)`
+    BitmapWord(i16),
+    /// 已初始化 tick 的 `ticks(tick).liquidityNet`
+    Tick(i32),
+}
+
+/// V3 池子的链上状态：slot0 价格、活跃流动性，以及按需读取的 bitmap 字和 tick 净流动性
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct V3Pool {
+    pub address: Address,
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    pub bitmap: BTreeMap<i16, U256>,
+    pub liquidity_net: BTreeMap<i32, i128>,
+}
+
+impl V3Pool {
+    /// 与池子 `swap` 相同的固定输入模拟（价格限制取 Quoter 的默认极值）
+    ///
+    /// 流动性不足以成交全部输入时返回 `Ok(None)`，缺少 bitmap 字或 tick 时返回需要补充读取的状态。
+    pub fn swap_exact_input(
+        &self,
+        zero_for_one: bool,
+        amount_in: U256,
+    ) -> Result<Option<U256>, MissingState> {
+        let price_limit = if zero_for_one {
+            MIN_SQRT_RATIO + 1
+        } else {
+            MAX_SQRT_RATIO - 1
+        };
+        let mut sqrt_price = self.sqrt_price_x96;
+        let mut tick = self.tick;
+        let mut liquidity = self.liquidity;
+        let mut amount_remaining = amount_in;
+        let mut amount_out = U256::zero();
+
+        while !amount_remaining.is_zero() {
+            if sqrt_price == price_limit {
+                return Ok(None);
+            }
+
+            let (tick_next, initialized) = self.next_initialized_tick(tick, zero_for_one)?;
+            let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let Some(sqrt_price_at_next) = sqrt_ratio_at_tick(tick_next) else {
+                return Ok(None);
+            };
+            let sqrt_target = if (zero_for_one && sqrt_price_at_next < price_limit)
+                || (!zero_for_one && sqrt_price_at_next > price_limit)
+            {
+                price_limit
+            } else {
+                sqrt_price_at_next
+            };
+
+            let Some(step) =
+                compute_swap_step(sqrt_price, sqrt_target, liquidity, amount_remaining, self.fee)
+            else {
+                return Ok(None);
+            };
+            sqrt_price = step.sqrt_price_next;
+            amount_remaining -= step.amount_in + step.fee_amount;
+            amount_out += step.amount_out;
+
+            if sqrt_price == sqrt_price_at_next {
+                if initialized {
+                    let net = *self
+                        .liquidity_net
+                        .get(&tick_next)
+                        .ok_or(MissingState::Tick(tick_next))?;
+                    let net = if zero_for_one { -net } else { net };
+                    let Some(next_liquidity) = liquidity.checked_add_signed(net) else {
+                        return Ok(None);
+                    };
+                    liquidity = next_liquidity;
+                }
+                tick = if zero_for_one { tick_next - 1 } else { tick_next };
+            }
+        }
+
+        Ok(Some(amount_out).filter(|amount| !amount.is_zero()))
+    }
+
+    /// 当前价格下的中间价，见 [`sqrt_price_to_price`]
+    pub fn mid_price(&self, zero_for_one: bool) -> f64 {
+        sqrt_price_to_price(self.sqrt_price_x96, zero_for_one)
+    }
+
+    /// `TickBitmap.nextInitializedTickWithinOneWord`：在当前 bitmap 字内查找下一个已初始化 tick
+    fn next_initialized_tick(&self, tick: i32, lte: bool) -> Result<(i32, bool), MissingState> {
+        let compressed = tick.div_euclid(self.tick_spacing);
+        let compressed = if lte { compressed } else { compressed + 1 };
+        let word = (compressed >> 8) as i16;
+        let bit_pos = compressed & 0xff;
+        let bitmap = *self.bitmap.get(&word).ok_or(MissingState::BitmapWord(word))?;
+
+        if lte {
+            let mask = (U256::one() << bit_pos as usize) - 1 + (U256::one() << bit_pos as usize);
+            let masked = bitmap & mask;
+            if masked.is_zero() {
+                Ok(((compressed - bit_pos) * self.tick_spacing, false))
+            } else {
+                let most_significant = masked.bits() as i32 - 1;
+                Ok(((compressed - (bit_pos - most_significant)) * self.tick_spacing, true))
+            }
+        } else {
+            let mask = !((U256::one() << bit_pos as usize) - 1);
+            let masked = bitmap & mask;
+            if masked.is_zero() {
+                Ok(((compressed + (255 - bit_pos)) * self.tick_spacing, false))
+            } else {
+                let least_significant = masked.trailing_zeros() as i32;
+                Ok(((compressed + (least_significant - bit_pos)) * self.tick_spacing, true))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `encodePriceSqrt(reserve1, reserve0)` 的结果（v3-core 测试中的写法）
+    fn encode_price_sqrt(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    fn pool(liquidity: u128, ticks: &[(i32, i128)]) -> V3Pool {
+        let mut bitmap = BTreeMap::new();
+        for (tick, _) in ticks {
+            let compressed = tick.div_euclid(60);
+            *bitmap.entry((compressed >> 8) as i16).or_insert_with(U256::zero) |=
+                U256::one() << (compressed & 0xff) as usize;
+        }
+        // 当前 tick 0 所在的字（以及向下的一个字）即使没有已初始化 tick 也要读取
+        bitmap.entry(0).or_insert_with(U256::zero);
+        bitmap.entry(-1).or_insert_with(U256::zero);
+        V3Pool {
+            address: Address::zero(),
+            fee: 3000,
+            tick_spacing: 60,
+            sqrt_price_x96: U256::one() << 96,
+            tick: 0,
+            liquidity,
+            bitmap,
+            liquidity_net: ticks.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn sqrt_ratio_matches_tick_math() {
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK), Some(MIN_SQRT_RATIO));
+        assert_eq!(sqrt_ratio_at_tick(MAX_TICK), Some(MAX_SQRT_RATIO));
+        assert_eq!(sqrt_ratio_at_tick(0), Some(U256::one() << 96));
+        assert_eq!(
+            sqrt_ratio_at_tick(50),
+            Some(encode_price_sqrt("79426470787362580746886972461"))
+        );
+        assert_eq!(
+            sqrt_ratio_at_tick(-1),
+            Some(encode_price_sqrt("79224201403219477170569942574"))
+        );
+        assert_eq!(sqrt_ratio_at_tick(MAX_TICK + 1), None);
+    }
+
+    #[test]
+    fn swap_step_matches_v3_core_vectors() {
+        // SwapMath.spec.ts：exact amount in that gets capped at price target in one for zero
+        let one = encode_price_sqrt("79228162514264337593543950336");
+        let target = encode_price_sqrt("79623317895830914510639640423");
+        let step = compute_swap_step(one, target, 2 * 10u128.pow(18), U256::exp10(18), 600).unwrap();
+        assert_eq!(step.sqrt_price_next, target);
+        assert_eq!(step.amount_in, U256::from(9_975_124_224_178_055u64));
+        assert_eq!(step.fee_amount, U256::from(5_988_667_735_148u64));
+        assert_eq!(step.amount_out, U256::from(9_925_619_580_021_728u64));
+
+        // exact amount in that is fully spent in one for zero
+        let target = encode_price_sqrt("250541448375047931186413801569");
+        let step = compute_swap_step(one, target, 2 * 10u128.pow(18), U256::exp10(18), 600).unwrap();
+        assert!(step.sqrt_price_next < target);
+        assert_eq!(step.amount_in, U256::from(999_400_000_000_000_000u64));
+        assert_eq!(step.fee_amount, U256::from(600_000_000_000_000u64));
+        assert_eq!(step.amount_out, U256::from(666_399_946_655_997_866u64));
+    }
+
+    #[test]
+    fn swap_crosses_initialized_ticks() {
+        let liquidity = 10u128.pow(21);
+        // 价格上移穿过 tick 60 后流动性减半：结果等于两段分别计算之和
+        let pool = pool(liquidity, &[(60, -(liquidity as i128) / 2)]);
+        let amount_in = U256::exp10(19);
+        let out = pool.swap_exact_input(false, amount_in).unwrap().unwrap();
+
+        let boundary = sqrt_ratio_at_tick(60).unwrap();
+        let first = compute_swap_step(pool.sqrt_price_x96, boundary, liquidity, amount_in, 3000).unwrap();
+        assert_eq!(first.sqrt_price_next, boundary);
+        let remaining = amount_in - first.amount_in - first.fee_amount;
+        let far = sqrt_ratio_at_tick(255 * 60).unwrap();
+        let second = compute_swap_step(boundary, far, liquidity / 2, remaining, 3000).unwrap();
+        assert_eq!(out, first.amount_out + second.amount_out);
+
+        // 不穿过 tick 的小额兑换只有一步
+        let small = U256::exp10(15);
+        let single = compute_swap_step(pool.sqrt_price_x96, boundary, liquidity, small, 3000).unwrap();
+        assert_eq!(pool.swap_exact_input(false, small), Ok(Some(single.amount_out)));
+    }
+
+    #[test]
+    fn swap_reports_missing_state() {
+        let mut pool = pool(10u128.pow(18), &[(-120, 5)]);
+        pool.liquidity_net.clear();
+        assert_eq!(
+            pool.swap_exact_input(true, U256::exp10(18)),
+            Err(MissingState::Tick(-120))
+        );
+
+        pool.bitmap.remove(&-1);
+        assert_eq!(
+            pool.swap_exact_input(true, U256::exp10(18)),
+            Err(MissingState::BitmapWord(-1))
+        );
+    }
+}
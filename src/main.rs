@@ -1,56 +1,132 @@
-use anyhow::{Context, Result};
-use std::env;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::sync::Arc;
 use tracing::{info, warn, Level};
 
+mod config;
 mod ethereum;
 mod mcp;
 mod tools;
 
-use mcp::server::MCPServer;
-use ethereum::rpc::get_best_rpc_url;
+use config::{Cli, Config, TransportKind};
+use mcp::server::{MCPServer, Transport};
+use ethereum::client::EthereumClient;
+use ethereum::rpc::{get_recommended_providers, RpcPool, RpcProvider};
+use ethereum::signer::{build_signer, TransactionSigner};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
 
-    info!("Starting Ethereum MCP server...");
-
-    // Get configuration from environment variables with better error handling
-    let rpc_url = if env::var("ETHEREUM_RPC_URL").is_ok() {
-        env::var("ETHEREUM_RPC_URL")
-            .context("请设置 ETHEREUM_RPC_URL 环境变量")?
-    } else {
-        info!("未设置 ETHEREUM_RPC_URL，自动选择最佳 RPC...");
-        get_best_rpc_url().await.context("无法找到可用的 RPC 提供商")?
-    };
+    // Initialize logging (stderr only, stdout carries the MCP stream)
+    tracing_subscriber::fmt()
+        .with_max_level(Level::from(config.logging.level))
+        .with_writer(std::io::stderr)
+        .init();
 
-    let private_key = env::var("PRIVATE_KEY")
-        .unwrap_or_else(|_| "0xee47965684a23f4c2c4447ad7ff164cc0f7539cfcd313700fb353d25ea479e1a".to_string());
+    info!("Starting Ethereum MCP server...");
 
-    // Validate private key format
-    if !private_key.starts_with("0x") || private_key.len() != 66 {
-        warn!("⚠️  私钥格式错误，使用默认测试私钥");
-        let private_key = "0xee47965684a23f4c2c4447ad7ff164cc0f7539cfcd313700fb353d25ea479e1a".to_string();
+    let signer = build_signer(&config.ethereum, config.signer.as_ref()).await?;
+    if signer.is_none() {
+        warn!("未配置 PRIVATE_KEY 或 [signer]，以只读模式启动（仅提供查询类工具）");
     }
 
-    info!("使用 RPC: {}", rpc_url);
-    info!("钱包地址: 0x{}", &private_key[2..10]); // 只显示前几个字符用于确认
-
     // Create MCP server
-    let mut server = MCPServer::new(rpc_url, private_key).await?;
+    let clients = connect_chains(&config, signer).await?;
+    let server = MCPServer::new(clients, config.execution, config.swap)?;
+
+    let transport = match config.mcp.transport {
+        TransportKind::Stdio => Transport::Stdio,
+        TransportKind::Http => Transport::Http(config.mcp.socket_addr()?),
+    };
 
     // Start server
-    server.run().await?;
+    server.run(transport).await?;
 
     Ok(())
 }
 
+/// 连接 [ethereum] 和每个填写了 rpc_url 的 [chains.<name>]，第一个连接的链作为默认链
+///
+/// 每条链使用一个 RPC 连接池：配置的 rpc_url 和 fallback_rpc_urls 优先，链 ID 由它们确定；
+/// 确认是主网后才在 public_fallback 开启时追加内置公共 RPC。都没有配置 RPC 时只使用公共 RPC。
+async fn connect_chains(
+    config: &Config,
+    signer: Option<Arc<dyn TransactionSigner>>,
+) -> Result<Vec<EthereumClient>> {
+    let configured = |primary: &url::Url, fallbacks: &[url::Url]| -> Vec<RpcProvider> {
+        std::iter::once(primary)
+            .chain(fallbacks)
+            .map(|url| RpcProvider::configured(url.as_str()))
+            .collect()
+    };
+    // 链 ID 未知（没有配置任何 RPC）时按主网处理，内置公共 RPC 都是主网节点
+    let public = |chain_id: Option<u64>| -> Vec<RpcProvider> {
+        if config.ethereum.public_fallback && chain_id.unwrap_or(1) == 1 {
+            get_recommended_providers()
+        } else {
+            Vec::new()
+        }
+    };
+
+    let mut pools: Vec<(Vec<RpcProvider>, Option<u64>)> = Vec::new();
+    if let Some(url) = &config.ethereum.rpc_url {
+        pools.push((configured(url, &config.ethereum.fallback_rpc_urls), None));
+    }
+    for chain in config.chains.values() {
+        if let Some(url) = &chain.rpc_url {
+            pools.push((
+                configured(url, &chain.fallback_rpc_urls),
+                Some(chain.chain_id),
+            ));
+        }
+    }
+    if pools.is_empty() {
+        if !config.ethereum.public_fallback {
+            bail!("未配置 RPC URL，且已关闭 public_fallback");
+        }
+        info!("未配置 RPC URL，使用内置公共 RPC...");
+        pools.push((Vec::new(), None));
+    }
+
+    let mut clients: Vec<EthereumClient> = Vec::new();
+    for (configured, expected_chain_id) in pools {
+        let mut pool = RpcPool::connect(configured, &public, expected_chain_id)
+            .await
+            .context("无法找到可用的 RPC 提供商")?;
+        if config.quorum.enabled {
+            pool = pool.with_quorum(config.quorum);
+        }
+        let client = EthereumClient::with_pool(pool, signer.clone(), &config.chains)?;
+
+        if clients.iter().any(|existing| existing.chain_id() == client.chain_id()) {
+            warn!(
+                "链 {} ({}) 已连接，忽略重复的 RPC 配置",
+                client.chain().name,
+                client.chain_id()
+            );
+            continue;
+        }
+        clients.push(client);
+    }
+
+    Ok(clients)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ethereum::client::EthereumClient;
-    use crate::tools::{get_balance, get_token_price, swap_tokens};
+    use crate::ethereum::signer::LocalSigner;
+    use crate::config::{ApprovalMode, GasSpeed, SwapRouterKind};
+    use crate::ethereum::client::SwapMode;
+    use crate::tools::{get_balance, get_token_price, swap_tokens, SwapRequest};
+    use ethers::providers::Middleware;
+    use ethers::types::{Address, U256};
+    use std::collections::BTreeMap;
     use std::env;
+    use std::str::FromStr;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test_get_eth_balance() {
@@ -63,7 +139,13 @@ mod tests {
 
         let client = EthereumClient::new(
             "https://eth.llamarpc.com".to_string(),
-            "0x1234567890123456789012345678901234567890123456789012345678901234".to_string(),
+            Some(Arc::new(
+                LocalSigner::from_private_key(
+                    "0x1234567890123456789012345678901234567890123456789012345678901234",
+                )
+                .unwrap(),
+            )),
+            &BTreeMap::new(),
         )
         .await
         .unwrap();
@@ -87,7 +169,13 @@ mod tests {
 
         let client = EthereumClient::new(
             "https://eth.llamarpc.com".to_string(),
-            "0x1234567890123456789012345678901234567890123456789012345678901234".to_string(),
+            Some(Arc::new(
+                LocalSigner::from_private_key(
+                    "0x1234567890123456789012345678901234567890123456789012345678901234",
+                )
+                .unwrap(),
+            )),
+            &BTreeMap::new(),
         )
         .await
         .unwrap();
@@ -115,20 +203,29 @@ mod tests {
 
         let client = EthereumClient::new(
             "https://eth.llamarpc.com".to_string(),
-            "0x1234567890123456789012345678901234567890123456789012345678901234".to_string(),
+            Some(Arc::new(
+                LocalSigner::from_private_key(
+                    "0x1234567890123456789012345678901234567890123456789012345678901234",
+                )
+                .unwrap(),
+            )),
+            &BTreeMap::new(),
         )
         .await
         .unwrap();
 
         // Test token swap simulation
-        let result = swap_tokens(
-            &client,
-            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", // USDC (correct address)
-            "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",  // WETH
-            "1000000",                                     // 1 USDC (6 decimals)
-            0.5,                                           // 0.5% slippage
-        )
-        .await;
+        let request = SwapRequest {
+            from_token: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), // USDC (correct address)
+            to_token: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(),   // WETH
+            amount: "1000000".to_string(),                                        // 1 USDC (6 decimals)
+            slippage_tolerance: 0.5,                                              // 0.5% slippage
+            approval: ApprovalMode::Exact,
+            mode: SwapMode::ExactInput,
+            gas_speed: GasSpeed::Normal,
+            router: SwapRouterKind::Legacy,
+        };
+        let result = swap_tokens(&client, &request).await;
 
         if let Err(e) = &result {
             println!("Swap simulation error: {}", e);
@@ -148,4 +245,48 @@ mod tests {
             "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
         );
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_v3_local_quote_matches_quoter() {
+        let client = match EthereumClient::new(
+            "https://eth.llamarpc.com".to_string(),
+            None,
+            &BTreeMap::new(),
+        )
+        .await
+        {
+            Ok(client) => client,
+            Err(e) => {
+                println!("⚠️  无法连接 RPC，跳过 V3 本地报价与 Quoter 的对比: {}", e);
+                return;
+            }
+        };
+
+        let usdc = Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap();
+        let weth = Address::from_str("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2").unwrap();
+        let amount_in = U256::from(250_000_000_000u64); // 250,000 USDC，足以穿过若干 tick
+
+        let provider = client.provider();
+        let block = provider.get_block_number().await.unwrap();
+        let routes = client.uniswap_v3_routes(usdc, weth, amount_in).await;
+        let mut quoted = Vec::new();
+        for route in routes.iter().filter(|route| route.pool.is_some()) {
+            quoted.push(client.quote_v3_path(&route.path, &route.fees, amount_in).await);
+        }
+
+        // 期间出块时池子状态可能已变化，不做比较
+        if provider.get_block_number().await.unwrap() != block {
+            println!("⚠️  报价期间出块，跳过 V3 本地报价与 Quoter 的对比");
+            return;
+        }
+        for (route, quoted) in routes.iter().filter(|route| route.pool.is_some()).zip(quoted) {
+            assert_eq!(
+                quoted,
+                Some(route.amount_out),
+                "路径 {:?} 费率 {:?}",
+                route.path,
+                route.fees
+            );
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use crate::mcp::server::{shutdown_signal, MCPServer};
+use crate::mcp::session::Session;
+use crate::mcp::types::{MCPResponse, ProtocolVersion};
+use anyhow::{Context, Result};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::post,
+    Json, Router,
+};
+use futures::{future::join_all, stream::StreamExt};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Streamable HTTP 传输的唯一端点
+pub const MCP_ENDPOINT: &str = "/mcp";
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// 会话超过这段时间没有请求（且没有打开的 SSE 流）即过期，客户端需要重新 initialize
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 清理过期会话的间隔
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// HTTP 传输下的单个客户端会话
+pub struct HttpSession {
+    session: Session,
+    /// 最近一次请求的时间，用于判断会话是否空闲过期
+    last_seen: Mutex<Instant>,
+    /// GET 打开的 SSE 流，用于服务器主动推送消息
+    events: Mutex<Option<mpsc::UnboundedSender<serde_json::Value>>>,
+}
+
+#[allow(dead_code)]
+impl HttpSession {
+    fn new() -> Self {
+        Self {
+            session: Session::default(),
+            last_seen: Mutex::new(Instant::now()),
+            events: Mutex::new(None),
+        }
+    }
+
+    /// 通过 SSE 流向客户端推送一条 JSON-RPC 消息，客户端未打开流时返回 false
+    pub fn notify(&self, message: serde_json::Value) -> bool {
+        let events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events
+            .as_ref()
+            .map(|sender| sender.send(message).is_ok())
+            .unwrap_or(false)
+    }
+
+    /// 客户端仍连着 SSE 流
+    fn has_open_stream(&self) -> bool {
+        let events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.as_ref().is_some_and(|sender| !sender.is_closed())
+    }
+
+    fn touch(&self, now: Instant) {
+        *self.last_seen.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    fn is_expired(&self, now: Instant, idle_timeout: Duration) -> bool {
+        let last_seen = *self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+        now.duration_since(last_seen) >= idle_timeout && !self.has_open_stream()
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    server: Arc<MCPServer>,
+    sessions: Arc<RwLock<HashMap<String, Arc<HttpSession>>>>,
+    bind_host: String,
+    idle_timeout: Duration,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl AppState {
+    fn new(
+        server: Arc<MCPServer>,
+        bind_host: String,
+        idle_timeout: Duration,
+        shutdown: watch::Receiver<bool>,
+    ) -> Self {
+        Self {
+            server,
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            bind_host,
+            idle_timeout,
+            shutdown,
+        }
+    }
+
+    /// 查找会话并刷新其活跃时间；已空闲过期的会话会被移除
+    fn session(&self, id: &str) -> Option<Arc<HttpSession>> {
+        let now = Instant::now();
+        let session = self
+            .sessions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()?;
+        if session.is_expired(now, self.idle_timeout) {
+            self.sessions
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(id);
+            info!(session = %id, "HTTP 会话已空闲过期");
+            return None;
+        }
+        session.touch(now);
+        Some(session)
+    }
+
+    /// 移除所有空闲过期的会话，返回移除的数量
+    fn expire_idle_sessions(&self) -> usize {
+        let now = Instant::now();
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        let before = sessions.len();
+        sessions.retain(|_, session| !session.is_expired(now, self.idle_timeout));
+        before - sessions.len()
+    }
+}
+
+/// 基于 Streamable HTTP 的 MCP 传输
+///
+/// - `POST /mcp`：提交 JSON-RPC 消息（支持批量），响应以 JSON 返回
+/// - `GET /mcp`：打开 SSE 流，接收服务器主动推送的消息
+/// - `DELETE /mcp`：结束会话
+///
+/// 会话空闲 30 分钟后过期。所有会话共享同一个 `MCPServer` 以及其中各条链的 `EthereumClient`。
+pub async fn serve(server: Arc<MCPServer>, addr: SocketAddr) -> Result<()> {
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let state = AppState::new(
+        server,
+        addr.ip().to_string(),
+        SESSION_IDLE_TIMEOUT,
+        shutdown_rx,
+    );
+
+    let sweeper = {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SESSION_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let expired = state.expire_idle_sessions();
+                if expired > 0 {
+                    info!("清理 {} 个空闲过期的 HTTP 会话", expired);
+                }
+            }
+        })
+    };
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("监听 {} 失败", addr))?;
+    info!(
+        "MCP 服务器已启动 (HTTP)，监听 http://{}{}",
+        addr, MCP_ENDPOINT
+    );
+
+    let served = axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            info!("收到终止信号，准备退出");
+            // 通知所有 SSE 流结束，否则优雅退出会一直等待长连接
+            let _ = shutdown_tx.send(true);
+        })
+        .await;
+    sweeper.abort();
+    served.context("HTTP 服务异常退出")?;
+
+    info!("MCP 服务器已停止");
+    Ok(())
+}
+
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route(
+            MCP_ENDPOINT,
+            post(handle_post).get(handle_get).delete(handle_delete),
+        )
+        .with_state(state)
+}
+
+async fn handle_post(State(state): State<AppState>, headers: HeaderMap, body: String) -> Response {
+    if let Err(response) = validate_headers(&state, &headers) {
+        return response;
+    }
+
+    let value: serde_json::Value = match serde_json::from_str(&body) {
+        Ok(value) => value,
+        Err(e) => {
+            let error =
+                MCPResponse::error(serde_json::Value::Null, -32700, format!("解析错误: {}", e));
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let is_batch = value.is_array();
+    let messages = match value {
+        serde_json::Value::Array(items) => items,
+        other => vec![other],
+    };
+    if messages.is_empty() {
+        let error = MCPResponse::error(serde_json::Value::Null, -32600, "空的批量请求".to_string());
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let requests: Vec<_> = messages.into_iter().map(MCPServer::parse_value).collect();
+
+    let is_initialize = requests
+        .iter()
+        .any(|request| matches!(request, Ok(request) if request.method == "initialize"));
+
+    let (session_id, session) = if is_initialize {
+        if requests.len() > 1 {
+            let error = MCPResponse::error(
+                serde_json::Value::Null,
+                -32600,
+                "initialize 请求不能与其他消息批量发送".to_string(),
+            );
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+        (Uuid::new_v4().to_string(), Arc::new(HttpSession::new()))
+    } else {
+        match lookup_session(&state, &headers) {
+            Ok(found) => found,
+            Err(response) => return response,
+        }
+    };
+
+    let responses: Vec<MCPResponse> = join_all(requests.into_iter().map(|request| {
+        let server = state.server.clone();
+        let session = session.clone();
+        async move {
+            match request {
+                Ok(request) => server.handle_request(&session.session, request).await,
+                Err(response) => Some(*response),
+            }
+        }
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let mut extra_headers = HeaderMap::new();
+    if is_initialize && session.session.is_initialized() {
+        state
+            .sessions
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_id.clone(), session);
+        info!(session = %session_id, "创建 HTTP 会话");
+        if let Ok(value) = HeaderValue::from_str(&session_id) {
+            extra_headers.insert(SESSION_HEADER, value);
+        }
+    }
+
+    if responses.is_empty() {
+        // 只包含通知或响应时返回 202，无响应体
+        return (StatusCode::ACCEPTED, extra_headers).into_response();
+    }
+
+    let body = if is_batch {
+        serde_json::to_value(&responses)
+    } else {
+        serde_json::to_value(&responses[0])
+    };
+    match body {
+        Ok(body) => (StatusCode::OK, extra_headers, Json(body)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("序列化响应失败: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+async fn handle_get(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(response) = validate_headers(&state, &headers) {
+        return response;
+    }
+
+    let accepts_sse = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    if !accepts_sse {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "GET 仅用于 text/event-stream",
+        )
+            .into_response();
+    }
+
+    let (session_id, session) = match lookup_session(&state, &headers) {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel::<serde_json::Value>();
+    // 每个会话只保留一条 SSE 流，新流会替换旧流；会话结束或过期时发送端随之释放，流也会结束
+    *session.events.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
+    info!(session = %session_id, "打开 SSE 流");
+
+    let mut shutdown = state.shutdown.clone();
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|message| (message, rx))
+    })
+    .map(|message| Ok::<_, Infallible>(Event::default().event("message").data(message.to_string())))
+    .take_until(async move {
+        // 发送端已释放说明不会再有终止通知
+        if shutdown.wait_for(|stopped| *stopped).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn handle_delete(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(response) = validate_headers(&state, &headers) {
+        return response;
+    }
+
+    let Some(session_id) = session_id_header(&headers) else {
+        return (StatusCode::BAD_REQUEST, "缺少 Mcp-Session-Id 请求头").into_response();
+    };
+
+    let removed = state
+        .sessions
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&session_id);
+    match removed {
+        Some(_) => {
+            info!(session = %session_id, "结束 HTTP 会话");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => (StatusCode::NOT_FOUND, "会话不存在或已过期").into_response(),
+    }
+}
+
+fn session_id_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+#[allow(clippy::result_large_err)]
+fn lookup_session(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> std::result::Result<(String, Arc<HttpSession>), Response> {
+    let Some(session_id) = session_id_header(headers) else {
+        return Err((StatusCode::BAD_REQUEST, "缺少 Mcp-Session-Id 请求头").into_response());
+    };
+    match state.session(&session_id) {
+        Some(session) => Ok((session_id, session)),
+        None => Err((StatusCode::NOT_FOUND, "会话不存在或已过期").into_response()),
+    }
+}
+
+/// 校验 Origin（防止 DNS rebinding）和 MCP-Protocol-Version 请求头
+#[allow(clippy::result_large_err)]
+fn validate_headers(state: &AppState, headers: &HeaderMap) -> std::result::Result<(), Response> {
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        let host = origin
+            .to_str()
+            .ok()
+            .and_then(|origin| url::Url::parse(origin).ok())
+            .and_then(|url| url.host_str().map(|host| host.to_string()));
+        let allowed = match host.as_deref() {
+            Some("localhost" | "127.0.0.1" | "[::1]") => true,
+            Some(host) => host == state.bind_host,
+            None => false,
+        };
+        if !allowed {
+            warn!("拒绝来自 {:?} 的请求", origin);
+            return Err((StatusCode::FORBIDDEN, "不允许的 Origin").into_response());
+        }
+    }
+
+    if let Some(version) = headers.get(PROTOCOL_VERSION_HEADER) {
+        let supported = version
+            .to_str()
+            .ok()
+            .and_then(ProtocolVersion::parse)
+            .is_some();
+        if !supported {
+            return Err((StatusCode::BAD_REQUEST, "不支持的 MCP-Protocol-Version").into_response());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::server::test_server;
+    use serde_json::json;
+
+    async fn spawn_app(idle_timeout: Duration) -> (String, AppState) {
+        let (_, shutdown) = watch::channel(false);
+        let state = AppState::new(
+            Arc::new(test_server().await),
+            "127.0.0.1".to_string(),
+            idle_timeout,
+            shutdown,
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}{}", addr, MCP_ENDPOINT), state)
+    }
+
+    fn initialize(id: u64) -> serde_json::Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0.0.0"}
+            }
+        })
+    }
+
+    fn tools_list(id: u64) -> serde_json::Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": "tools/list"})
+    }
+
+    async fn post(
+        url: &str,
+        session: Option<&str>,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        let mut request = reqwest::Client::new().post(url).json(body);
+        if let Some(session) = session {
+            request = request.header(SESSION_HEADER, session);
+        }
+        request.send().await.unwrap()
+    }
+
+    async fn open_session(url: &str) -> String {
+        let response = post(url, None, &initialize(1)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        response.headers()[SESSION_HEADER].to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn initialize_returns_session_id() {
+        let (url, state) = spawn_app(SESSION_IDLE_TIMEOUT).await;
+
+        let response = post(&url, None, &initialize(1)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["id"], 1);
+        assert!(body["result"]["protocolVersion"].is_string(), "{body}");
+        assert!(state.session(&session).is_some());
+
+        let response = post(&url, Some(&session), &tools_list(2)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["result"]["tools"].is_array(), "{body}");
+    }
+
+    #[tokio::test]
+    async fn unknown_or_missing_session_is_rejected() {
+        let (url, _) = spawn_app(SESSION_IDLE_TIMEOUT).await;
+
+        let response = post(&url, Some("no-such-session"), &tools_list(1)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let response = post(&url, None, &tools_list(1)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn initialize_cannot_be_batched_with_other_messages() {
+        let (url, state) = spawn_app(SESSION_IDLE_TIMEOUT).await;
+
+        let response = post(&url, None, &json!([initialize(1), tools_list(2)])).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        assert!(response.headers().get(SESSION_HEADER).is_none());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], -32600);
+        assert!(state.sessions.read().unwrap().is_empty());
+
+        // 只含 initialize 的批量请求按批量格式返回
+        let response = post(&url, None, &json!([initialize(3)])).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(response.headers().get(SESSION_HEADER).is_some());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body[0]["id"], 3);
+    }
+
+    #[tokio::test]
+    async fn delete_ends_session() {
+        let (url, _) = spawn_app(SESSION_IDLE_TIMEOUT).await;
+        let session = open_session(&url).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .delete(&url)
+            .header(SESSION_HEADER, &session)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+        let response = post(&url, Some(&session), &tools_list(2)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        let response = client
+            .delete(&url)
+            .header(SESSION_HEADER, &session)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_expire() {
+        let (url, state) = spawn_app(Duration::ZERO).await;
+
+        let session = open_session(&url).await;
+        let response = post(&url, Some(&session), &tools_list(2)).await;
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        assert!(state.sessions.read().unwrap().is_empty());
+
+        open_session(&url).await;
+        open_session(&url).await;
+        assert_eq!(state.expire_idle_sessions(), 2);
+    }
+
+    #[tokio::test]
+    async fn get_opens_sse_stream_for_session() {
+        let (url, state) = spawn_app(SESSION_IDLE_TIMEOUT).await;
+        let session = open_session(&url).await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(&url).header(SESSION_HEADER, &session).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+
+        let mut response = client
+            .get(&url)
+            .header(header::ACCEPT.as_str(), "text/event-stream")
+            .header(SESSION_HEADER, &session)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE.as_str()]
+            .to_str()
+            .unwrap()
+            .starts_with("text/event-stream"));
+
+        let message = json!({"jsonrpc": "2.0", "method": "notifications/message", "params": {"level": "info"}});
+        assert!(state.session(&session).unwrap().notify(message.clone()));
+        let mut received = String::new();
+        while !received.contains("\n\n") {
+            let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+                .await
+                .unwrap()
+                .unwrap()
+                .expect("SSE 流提前结束");
+            received.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(received.contains("event: message"), "{received}");
+        assert!(received.contains(&format!("data: {}", message)), "{received}");
+
+        // 打开 SSE 流的会话不会因空闲过期
+        let later = Instant::now() + SESSION_IDLE_TIMEOUT;
+        assert!(!state.session(&session).unwrap().is_expired(later, SESSION_IDLE_TIMEOUT));
+
+        // 结束会话后 SSE 流随之关闭
+        client.delete(&url).header(SESSION_HEADER, &session).send().await.unwrap();
+        let closed = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(closed.is_none());
+    }
+}
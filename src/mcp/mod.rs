@@ -1,2 +1,5 @@
+pub mod http;
 pub mod server;
+pub mod session;
+pub mod stdio;
 pub mod types;
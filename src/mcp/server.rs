@@ -1,22 +1,74 @@
+use crate::config::{ExecutionConfig, GasSpeed, SwapConfig};
 use crate::ethereum::client::EthereumClient;
-use crate::mcp::types::{Content, MCPRequest, MCPResponse, Tool, ToolCall, ToolResult};
-use crate::tools::{get_balance, get_token_price, swap_tokens};
-use anyhow::Result;
+use crate::mcp::session::Session;
+use crate::mcp::{http, stdio};
+use crate::mcp::types::{
+    Content, Implementation, InitializeParams, InitializeResult, MCPRequest, MCPResponse,
+    ProtocolVersion, ServerCapabilities, Tool, ToolCall, ToolResult, ToolsCapability,
+};
+use crate::tools::{
+    cancel_transaction, execute_swap, get_balance, get_token_price, list_chains,
+    speed_up_transaction, swap_tokens, unwrap_weth, wrap_eth, SwapRequest,
+};
+use anyhow::{anyhow, bail, Result};
 use serde_json::json;
-use tracing::{error, info};
+use std::{collections::BTreeMap, net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+
+/// 需要签名器的工具，只读模式下不会出现在 tools/list 中
+const SIGNING_TOOLS: &[&str] = &[
+    "swap_tokens",
+    "execute_swap",
+    "wrap_eth",
+    "unwrap_weth",
+    "speed_up_transaction",
+    "cancel_transaction",
+];
+
+/// MCP 传输方式
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub enum Transport {
+    /// 逐行 JSON-RPC，适合由 MCP 宿主以子进程方式启动
+    Stdio,
+    /// Streamable HTTP，多个客户端可共享同一个服务进程
+    Http(SocketAddr),
+}
 
 #[allow(dead_code)]
 pub struct MCPServer {
-    ethereum_client: EthereumClient,
+    /// 按 chain ID 索引，每条链一个客户端
+    clients: BTreeMap<u64, EthereumClient>,
+    /// 工具调用未指定 chain 参数时使用的链
+    default_chain: u64,
+    /// 交易广播设置，未开启时不提供 execute_swap 和替换交易的工具
+    execution: ExecutionConfig,
+    /// 兑换默认设置（授权方式等）
+    swap: SwapConfig,
     tools: Vec<Tool>,
 }
 
 #[allow(dead_code)]
 impl MCPServer {
-    pub async fn new(rpc_url: String, private_key: String) -> Result<Self> {
-        let ethereum_client = EthereumClient::new(rpc_url, private_key).await?;
+    /// 用已连接的客户端构造服务器，第一个客户端所在的链为默认链
+    pub fn new(
+        ethereum_clients: Vec<EthereumClient>,
+        execution: ExecutionConfig,
+        swap: SwapConfig,
+    ) -> Result<Self> {
+        let Some(default_chain) = ethereum_clients.first().map(|client| client.chain_id()) else {
+            bail!("至少需要连接一条链");
+        };
 
-        let tools = vec![
+        let mut clients = BTreeMap::new();
+        for client in ethereum_clients {
+            let chain_id = client.chain_id();
+            if clients.insert(chain_id, client).is_some() {
+                bail!("链 ID {} 重复连接", chain_id);
+            }
+        }
+
+        let mut tools = vec![
             Tool {
                 name: "get_balance".to_string(),
                 description: "Query ETH and ERC20 token balances for Ethereum addresses"
@@ -67,20 +119,44 @@ impl MCPServer {
                     "properties": {
                         "from_token": {
                             "type": "string",
-                            "description": "Source token address"
+                            "description": "Source token address (0x0000000000000000000000000000000000000000 for the native token)"
                         },
                         "to_token": {
                             "type": "string",
-                            "description": "Destination token address"
+                            "description": "Destination token address (0x0000000000000000000000000000000000000000 for the native token)"
                         },
                         "amount": {
                             "type": "string",
-                            "description": "Swap amount (in source token's smallest unit)"
+                            "description": "Swap amount (in source token's smallest unit). With mode exact_output this is the amount of destination token to receive"
+                        },
+                        "mode": {
+                            "type": "string",
+                            "enum": ["exact_input", "exact_output"],
+                            "description": "exact_input spends exactly `amount` of the source token; exact_output receives exactly `amount` of the destination token and applies slippage as a maximum input",
+                            "default": "exact_input"
                         },
                         "slippage_tolerance": {
                             "type": "number",
                             "description": "Slippage tolerance (percentage, e.g., 0.5 means 0.5%)",
                             "default": 0.5
+                        },
+                        "approval": {
+                            "type": "string",
+                            "enum": ["exact", "unlimited"],
+                            "description": "Allowance to grant when the router's allowance is insufficient: exactly the input amount or unlimited",
+                            "default": swap.approval.as_str()
+                        },
+                        "router": {
+                            "type": "string",
+                            "enum": ["legacy", "universal"],
+                            "description": "Execution contract: legacy Uniswap routers with ERC20 approve, or the Universal Router with a signed Permit2 allowance (exact_input only)",
+                            "default": swap.router.as_str()
+                        },
+                        "gas_speed": {
+                            "type": "string",
+                            "enum": ["slow", "normal", "fast"],
+                            "description": "EIP-1559 fee preset: priority fee from the 10th / 50th / 90th percentile of recent eth_feeHistory rewards",
+                            "default": swap.gas_speed.as_str()
                         }
                     },
                     "required": ["from_token", "to_token", "amount"]
@@ -88,30 +164,213 @@ impl MCPServer {
             },
         ];
 
+        for (name, description) in [
+            ("wrap_eth", "Wrap the chain's native token into its wrapped ERC20 (e.g. ETH to WETH). Returns a signed deposit transaction"),
+            ("unwrap_weth", "Unwrap the wrapped native ERC20 back into the native token (e.g. WETH to ETH). Returns a signed withdraw transaction"),
+        ] {
+            tools.push(Tool {
+                name: name.to_string(),
+                description: description.to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "amount": {
+                            "type": "string",
+                            "description": "Amount in whole tokens (e.g., 0.5)"
+                        },
+                        "gas_speed": {
+                            "type": "string",
+                            "enum": ["slow", "normal", "fast"],
+                            "description": "EIP-1559 fee preset",
+                            "default": swap.gas_speed.as_str()
+                        }
+                    },
+                    "required": ["amount"]
+                }),
+            });
+        }
+
+        if execution.enabled {
+            let mut execute = tools
+                .iter()
+                .find(|tool| tool.name == "swap_tokens")
+                .cloned()
+                .ok_or_else(|| anyhow!("缺少 swap_tokens 工具定义"))?;
+            execute.name = "execute_swap".to_string();
+            execute.description = "Execute a token swap on-chain: sign, broadcast via eth_sendRawTransaction and wait for confirmations. Returns the receipt with the actual output amount".to_string();
+            execute.input_schema["properties"]["confirmations"] = json!({
+                "type": "integer",
+                "description": "Number of confirmations to wait for",
+                "default": execution.confirmations
+            });
+            tools.push(execute);
+
+            for (name, description) in [
+                ("speed_up_transaction", "Re-broadcast a pending transaction from the server wallet with the same nonce and bumped EIP-1559 fees (at least +12%, or the current preset if higher)"),
+                ("cancel_transaction", "Cancel a pending transaction from the server wallet by broadcasting a zero-value self-transfer with the same nonce and bumped EIP-1559 fees"),
+            ] {
+                tools.push(Tool {
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    input_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "transaction_hash": {
+                                "type": "string",
+                                "description": "Hash of the pending transaction to replace"
+                            },
+                            "gas_speed": {
+                                "type": "string",
+                                "enum": ["slow", "normal", "fast"],
+                                "description": "EIP-1559 fee preset used as the floor for the replacement fees",
+                                "default": "fast"
+                            }
+                        },
+                        "required": ["transaction_hash"]
+                    }),
+                });
+            }
+        }
+
+        let read_only = clients.values().all(EthereumClient::is_read_only);
+        if read_only {
+            tools.retain(|tool| !SIGNING_TOOLS.contains(&tool.name.as_str()));
+        }
+
+        let available: Vec<String> = clients
+            .values()
+            .map(|client| format!("{} ({})", client.chain().name, client.chain_id()))
+            .collect();
+        let chain_property = json!({
+            "type": ["string", "integer"],
+            "description": format!(
+                "Chain name or chain ID (optional, defaults to {}). Available: {}",
+                clients[&default_chain].chain().name,
+                available.join(", ")
+            )
+        });
+        for tool in &mut tools {
+            if let Some(properties) = tool.input_schema["properties"].as_object_mut() {
+                properties.insert("chain".to_string(), chain_property.clone());
+            }
+        }
+
+        tools.push(Tool {
+            name: "list_chains".to_string(),
+            description: "List the chains this server is connected to, with their supported protocols and known token symbols".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        });
+
         Ok(Self {
-            ethereum_client,
+            clients,
+            default_chain,
+            execution,
+            swap,
             tools,
         })
     }
 
-    pub async fn run(&mut self) -> Result<()> {
-        info!("MCP 服务器已启动，等待请求...");
+    /// 按工具参数中的 chain（名称或 chain ID）选择客户端，未指定时使用默认链
+    fn client_for(&self, chain: Option<&serde_json::Value>) -> Result<&EthereumClient> {
+        let client = match chain {
+            None | Some(serde_json::Value::Null) => self.clients.get(&self.default_chain),
+            Some(serde_json::Value::Number(number)) => {
+                number.as_u64().and_then(|chain_id| self.clients.get(&chain_id))
+            }
+            Some(serde_json::Value::String(chain)) => {
+                let chain = chain.trim();
+                match chain.parse::<u64>() {
+                    Ok(chain_id) => self.clients.get(&chain_id),
+                    Err(_) => self
+                        .clients
+                        .values()
+                        .find(|client| client.chain().name.eq_ignore_ascii_case(chain)),
+                }
+            }
+            Some(other) => bail!("chain 参数应为链名称或 chain ID，收到: {}", other),
+        };
+
+        client.ok_or_else(|| {
+            let available: Vec<String> = self
+                .clients
+                .values()
+                .map(|client| format!("{} ({})", client.chain().name, client.chain_id()))
+                .collect();
+            anyhow!(
+                "未连接链 {}，可用: {}",
+                chain.cloned().unwrap_or_default(),
+                available.join(", ")
+            )
+        })
+    }
+
+    /// 在指定传输上提供服务，直到连接关闭或收到终止信号
+    pub async fn run(self, transport: Transport) -> Result<()> {
+        let server = Arc::new(self);
+        match transport {
+            Transport::Stdio => stdio::serve(server).await,
+            Transport::Http(addr) => http::serve(server, addr).await,
+        }
+    }
 
-        // 这里应该实现实际的 MCP 协议处理
-        // 为了演示，我们使用简单的 JSON-RPC 处理
-        // 在实际实现中，这里应该从 stdin 或网络连接读取 MCP 请求
-        // 现在我们先实现工具调用逻辑
+    /// 解析一条原始 JSON-RPC 消息并分发处理，通知消息返回 None
+    pub async fn handle_message(&self, session: &Session, raw: &str) -> Option<MCPResponse> {
+        match Self::parse_message(raw) {
+            Ok(request) => self.handle_request(session, request).await,
+            Err(response) => Some(*response),
+        }
+    }
+
+    pub fn parse_message(raw: &str) -> std::result::Result<MCPRequest, Box<MCPResponse>> {
+        let value: serde_json::Value = serde_json::from_str(raw).map_err(|e| {
+            warn!("无法解析 JSON-RPC 消息: {}", e);
+            Box::new(MCPResponse::error(
+                serde_json::Value::Null,
+                -32700,
+                format!("解析错误: {}", e),
+            ))
+        })?;
+
+        Self::parse_value(value)
+    }
 
-        Ok(())
+    pub fn parse_value(value: serde_json::Value) -> std::result::Result<MCPRequest, Box<MCPResponse>> {
+        let id = value.get("id").cloned().unwrap_or(serde_json::Value::Null);
+        serde_json::from_value::<MCPRequest>(value)
+            .map_err(|e| Box::new(MCPResponse::error(id, -32600, format!("无效的请求: {}", e))))
     }
 
-    pub async fn handle_request(&self, request: MCPRequest) -> MCPResponse {
-        match request.method.as_str() {
+    pub async fn handle_request(
+        &self,
+        session: &Session,
+        request: MCPRequest,
+    ) -> Option<MCPResponse> {
+        let Some(id) = request.id.clone() else {
+            // 通知消息不需要回复
+            self.handle_notification(session, &request);
+            return None;
+        };
+
+        // 握手完成前只接受 initialize 和 ping
+        if !session.is_initialized() && !matches!(request.method.as_str(), "initialize" | "ping") {
+            return Some(MCPResponse::error(
+                id,
+                -32002,
+                "服务器尚未初始化，请先发送 initialize 请求".to_string(),
+            ));
+        }
+
+        let response = match request.method.as_str() {
+            "initialize" => self.handle_initialize(session, id, request.params),
+            "ping" => MCPResponse::success(id, json!({})),
             "tools/list" => {
                 let result = json!({
                     "tools": self.tools
                 });
-                MCPResponse::success(request.id, result)
+                MCPResponse::success(id, result)
             }
             "tools/call" => {
                 if let Some(params) = request.params {
@@ -122,29 +381,105 @@ impl MCPServer {
                                     "content": result.content,
                                     "isError": result.is_error
                                 });
-                                MCPResponse::success(request.id, result_json)
+                                MCPResponse::success(id, result_json)
                             }
                             Err(e) => {
                                 error!("工具调用错误: {}", e);
                                 MCPResponse::error(
-                                    request.id,
+                                    id,
                                     -32603,
                                     format!("工具调用失败: {}", e),
                                 )
                             }
                         }
                     } else {
-                        MCPResponse::error(request.id, -32602, "无效的参数格式".to_string())
+                        MCPResponse::error(id, -32602, "无效的参数格式".to_string())
                     }
                 } else {
-                    MCPResponse::error(request.id, -32602, "缺少参数".to_string())
+                    MCPResponse::error(id, -32602, "缺少参数".to_string())
+                }
+            }
+            _ => MCPResponse::error(id, -32601, "未知方法".to_string()),
+        };
+
+        Some(response)
+    }
+
+    fn handle_initialize(
+        &self,
+        session: &Session,
+        id: serde_json::Value,
+        params: Option<serde_json::Value>,
+    ) -> MCPResponse {
+        let params = match params.map(serde_json::from_value::<InitializeParams>) {
+            Some(Ok(params)) => params,
+            Some(Err(e)) => {
+                return MCPResponse::error(id, -32602, format!("无效的 initialize 参数: {}", e))
+            }
+            None => return MCPResponse::error(id, -32602, "缺少参数".to_string()),
+        };
+
+        let version = ProtocolVersion::negotiate(&params.protocol_version);
+        if !session.begin_initialize(version) {
+            return MCPResponse::error(id, -32600, "会话已初始化".to_string());
+        }
+
+        info!(
+            client = %params.client_info.name,
+            client_version = %params.client_info.version,
+            requested = %params.protocol_version,
+            negotiated = version.as_str(),
+            "MCP 会话初始化"
+        );
+
+        let result = InitializeResult {
+            protocol_version: version,
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: false,
+                }),
+            },
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: None,
+        };
+
+        match serde_json::to_value(result) {
+            Ok(result) => MCPResponse::success(id, result),
+            Err(e) => MCPResponse::error(id, -32603, format!("序列化 initialize 结果失败: {}", e)),
+        }
+    }
+
+    fn handle_notification(&self, session: &Session, notification: &MCPRequest) {
+        match notification.method.as_str() {
+            "notifications/initialized" => {
+                if !session.mark_ready() {
+                    warn!("收到 initialized 通知，但会话未处于初始化阶段");
                 }
             }
-            _ => MCPResponse::error(request.id, -32601, "未知方法".to_string()),
+            other => debug!("忽略通知: {}", other),
         }
     }
 
     async fn handle_tool_call(&self, tool_call: ToolCall) -> Result<ToolResult> {
+        if tool_call.name == "list_chains" {
+            let chains = list_chains(self.clients.values(), self.default_chain)?;
+            return Ok(ToolResult {
+                content: vec![Content {
+                    content_type: "text".to_string(),
+                    text: format!("已连接的链: {}", chains),
+                }],
+                is_error: false,
+            });
+        }
+
+        let client = self.client_for(tool_call.arguments.get("chain"))?;
+        if client.is_read_only() && SIGNING_TOOLS.contains(&tool_call.name.as_str()) {
+            bail!("{} 需要签名器，当前为只读模式", tool_call.name);
+        }
+
         match tool_call.name.as_str() {
             "get_balance" => {
                 let address = tool_call
@@ -158,12 +493,12 @@ impl MCPServer {
                     .get("token_address")
                     .and_then(|v| v.as_str());
 
-                let balance = get_balance(&self.ethereum_client, address, token_address).await?;
+                let balance = get_balance(client, address, token_address).await?;
 
                 Ok(ToolResult {
                     content: vec![Content {
                         content_type: "text".to_string(),
-                        text: format!("余额查询结果: {}", balance),
+                        text: format!("余额查询结果 ({}): {}", client.chain().name, balance),
                     }],
                     is_error: false,
                 })
@@ -181,52 +516,113 @@ impl MCPServer {
                     .unwrap_or("USD");
 
                 let price =
-                    get_token_price(&self.ethereum_client, token_address, symbol, quote_currency)
+                    get_token_price(client, token_address, symbol, quote_currency)
                         .await?;
 
                 Ok(ToolResult {
                     content: vec![Content {
                         content_type: "text".to_string(),
-                        text: format!("代币价格: {}", price),
+                        text: format!("代币价格 ({}): {}", client.chain().name, price),
                     }],
                     is_error: false,
                 })
             }
             "swap_tokens" => {
-                let from_token = tool_call
-                    .arguments
-                    .get("from_token")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow::anyhow!("缺少 from_token 参数"))?;
-                let to_token = tool_call
-                    .arguments
-                    .get("to_token")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| anyhow::anyhow!("缺少 to_token 参数"))?;
+                let request = SwapRequest::from_arguments(&tool_call.arguments, &self.swap)?;
+                let swap_result = swap_tokens(client, &request).await?;
+
+                Ok(ToolResult {
+                    content: vec![Content {
+                        content_type: "text".to_string(),
+                        text: format!("交换模拟结果 ({}): {}", client.chain().name, swap_result),
+                    }],
+                    is_error: false,
+                })
+            }
+            "wrap_eth" | "unwrap_weth" => {
                 let amount = tool_call
                     .arguments
                     .get("amount")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow::anyhow!("缺少 amount 参数"))?;
-                let slippage_tolerance = tool_call
+                let speed = match tool_call.arguments.get("gas_speed").and_then(|v| v.as_str()) {
+                    Some(speed) => GasSpeed::from_str(speed)?,
+                    None => self.swap.gas_speed,
+                };
+                let result = if tool_call.name == "wrap_eth" {
+                    wrap_eth(client, amount, speed).await?
+                } else {
+                    unwrap_weth(client, amount, speed).await?
+                };
+
+                Ok(ToolResult {
+                    content: vec![Content {
+                        content_type: "text".to_string(),
+                        text: format!("包装交易 ({}): {}", client.chain().name, result),
+                    }],
+                    is_error: false,
+                })
+            }
+            "execute_swap" => {
+                if !self.execution.enabled {
+                    bail!("execute_swap 未开启，请在配置文件中设置 [execution] enabled = true");
+                }
+                let request = SwapRequest::from_arguments(&tool_call.arguments, &self.swap)?;
+                let confirmations = tool_call
                     .arguments
-                    .get("slippage_tolerance")
-                    .and_then(|v| v.as_f64())
-                    .unwrap_or(0.5);
-
-                let swap_result = swap_tokens(
-                    &self.ethereum_client,
-                    from_token,
-                    to_token,
-                    amount,
-                    slippage_tolerance,
+                    .get("confirmations")
+                    .and_then(|v| v.as_u64())
+                    .map(|v| v as usize)
+                    .unwrap_or(self.execution.confirmations);
+
+                let execution = execute_swap(
+                    client,
+                    &request,
+                    confirmations,
+                    Duration::from_secs(self.execution.timeout_secs),
                 )
                 .await?;
+                let succeeded = execution.status == "success";
 
                 Ok(ToolResult {
                     content: vec![Content {
                         content_type: "text".to_string(),
-                        text: format!("交换模拟结果: {}", swap_result),
+                        text: format!(
+                            "{} ({}): {}",
+                            if succeeded { "交换已执行" } else { "交换交易已回滚" },
+                            client.chain().name,
+                            serde_json::to_string_pretty(&execution)?
+                        ),
+                    }],
+                    is_error: !succeeded,
+                })
+            }
+            "speed_up_transaction" | "cancel_transaction" => {
+                if !self.execution.enabled {
+                    bail!(
+                        "{} 未开启，请在配置文件中设置 [execution] enabled = true",
+                        tool_call.name
+                    );
+                }
+                let tx_hash = tool_call
+                    .arguments
+                    .get("transaction_hash")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("缺少 transaction_hash 参数"))?;
+                let speed = match tool_call.arguments.get("gas_speed").and_then(|v| v.as_str()) {
+                    Some(speed) => GasSpeed::from_str(speed)?,
+                    None => GasSpeed::Fast,
+                };
+                let result = if tool_call.name == "speed_up_transaction" {
+                    speed_up_transaction(client, tx_hash, speed).await?
+                } else {
+                    cancel_transaction(client, tx_hash, speed).await?
+                };
+
+                Ok(ToolResult {
+                    content: vec![Content {
+                        content_type: "text".to_string(),
+                        text: format!("替换交易已广播 ({}): {}", client.chain().name, result),
                     }],
                     is_error: false,
                 })
@@ -235,3 +631,122 @@ impl MCPServer {
         }
     }
 }
+
+/// 等待 SIGTERM 或 Ctrl+C
+#[cfg(unix)]
+pub(crate) async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(e) => {
+            warn!("无法注册 SIGTERM 处理: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = sigterm.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// 连接本地桩 RPC（链 ID 1）的只读服务器，供传输层测试使用
+#[cfg(test)]
+pub(crate) async fn test_server() -> MCPServer {
+    let url = crate::ethereum::rpc::spawn_stub_rpc(1, "0x0").await;
+    let client = EthereumClient::new(url, None, &BTreeMap::new())
+        .await
+        .unwrap();
+    MCPServer::new(vec![client], ExecutionConfig::default(), SwapConfig::default()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::session::SessionState;
+
+    fn request(message: serde_json::Value) -> MCPRequest {
+        MCPServer::parse_value(message).unwrap()
+    }
+
+    fn initialize(id: u64) -> MCPRequest {
+        request(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0.0.0"}
+            }
+        }))
+    }
+
+    async fn respond(server: &MCPServer, session: &Session, message: MCPRequest) -> serde_json::Value {
+        let response = server.handle_request(session, message).await.unwrap();
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn tool_call_before_initialize_is_rejected() {
+        let server = test_server().await;
+        let session = Session::default();
+
+        let call = request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "list_chains", "arguments": {}}
+        }));
+        let response = respond(&server, &session, call).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], -32002, "{response}");
+        assert!(response.get("result").is_none());
+    }
+
+    #[tokio::test]
+    async fn ping_is_allowed_before_initialize() {
+        let server = test_server().await;
+        let session = Session::default();
+
+        let ping = request(json!({"jsonrpc": "2.0", "id": "p", "method": "ping"}));
+        let response = respond(&server, &session, ping).await;
+        assert_eq!(response["id"], "p");
+        assert_eq!(response["result"], json!({}), "{response}");
+        assert_eq!(session.state(), SessionState::Uninitialized);
+    }
+
+    #[tokio::test]
+    async fn initialized_notification_has_no_response() {
+        let server = test_server().await;
+        let session = Session::default();
+
+        let response = respond(&server, &session, initialize(1)).await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26", "{response}");
+        assert_eq!(
+            session.state(),
+            SessionState::Initializing(ProtocolVersion::V2025_03_26)
+        );
+
+        let initialized = request(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}));
+        assert!(server.handle_request(&session, initialized).await.is_none());
+        assert_eq!(session.state(), SessionState::Ready(ProtocolVersion::V2025_03_26));
+
+        // 握手完成后可以调用工具
+        let call = request(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "list_chains", "arguments": {}}
+        }));
+        let response = respond(&server, &session, call).await;
+        assert_eq!(response["result"]["isError"], false, "{response}");
+    }
+}
@@ -0,0 +1,92 @@
+use crate::mcp::types::ProtocolVersion;
+use std::sync::RwLock;
+
+/// MCP 会话生命周期阶段
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// 尚未收到 initialize 请求
+    Uninitialized,
+    /// 已回复 initialize，等待 notifications/initialized
+    Initializing(ProtocolVersion),
+    /// 握手完成
+    Ready(ProtocolVersion),
+}
+
+/// 单个客户端连接的会话状态
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct Session {
+    state: RwLock<SessionState>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            state: RwLock::new(SessionState::Uninitialized),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Session {
+    pub fn state(&self) -> SessionState {
+        *self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 记录 initialize 协商结果，重复初始化时返回 false
+    pub fn begin_initialize(&self, version: ProtocolVersion) -> bool {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        if *state != SessionState::Uninitialized {
+            return false;
+        }
+        *state = SessionState::Initializing(version);
+        true
+    }
+
+    /// 收到 notifications/initialized 后进入就绪状态
+    pub fn mark_ready(&self) -> bool {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        match *state {
+            SessionState::Initializing(version) => {
+                *state = SessionState::Ready(version);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// initialize 已完成协商即可接受工具调用
+    pub fn is_initialized(&self) -> bool {
+        self.state() != SessionState::Uninitialized
+    }
+
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        match self.state() {
+            SessionState::Uninitialized => None,
+            SessionState::Initializing(version) | SessionState::Ready(version) => Some(version),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_moves_through_states_once() {
+        let session = Session::default();
+        assert!(!session.is_initialized());
+        // 未收到 initialize 时的 initialized 通知被忽略
+        assert!(!session.mark_ready());
+
+        assert!(session.begin_initialize(ProtocolVersion::V2024_11_05));
+        assert!(session.is_initialized());
+        assert!(!session.begin_initialize(ProtocolVersion::V2025_06_18));
+        assert_eq!(session.protocol_version(), Some(ProtocolVersion::V2024_11_05));
+
+        assert!(session.mark_ready());
+        assert!(!session.mark_ready());
+        assert_eq!(session.state(), SessionState::Ready(ProtocolVersion::V2024_11_05));
+    }
+}
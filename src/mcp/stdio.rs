@@ -0,0 +1,219 @@
+use crate::mcp::server::{shutdown_signal, MCPServer};
+use crate::mcp::session::Session;
+use crate::mcp::types::MCPResponse;
+use anyhow::{Context, Result};
+use std::{future::Future, io::BufRead, sync::Arc, thread, time::Duration};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+    task::JoinSet,
+    time::timeout,
+};
+use tracing::{error, info, warn};
+
+/// 关闭时等待进行中请求完成的最长时间
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// 基于 stdio 的 MCP 传输：stdin 逐行读取 JSON-RPC 消息，响应逐行写入 stdout
+///
+/// 日志必须输出到 stderr，否则会破坏 stdout 上的消息流。
+pub async fn serve(server: Arc<MCPServer>) -> Result<()> {
+    info!("MCP 服务器已启动 (stdio)，等待请求...");
+
+    run(
+        server,
+        spawn_stdin_reader(),
+        tokio::io::stdout(),
+        shutdown_signal(),
+    )
+    .await?;
+
+    info!("MCP 服务器已停止");
+    Ok(())
+}
+
+/// 消息循环：从 `lines` 读取请求直到输入结束或 `shutdown` 触发，响应逐行写入 `output`；
+/// 退出前等待进行中的请求完成并写出它们的响应
+async fn run<W>(
+    server: Arc<MCPServer>,
+    mut lines: mpsc::UnboundedReceiver<std::io::Result<String>>,
+    output: W,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded_channel::<MCPResponse>();
+    let writer = tokio::spawn(write_responses(rx, output));
+
+    // stdio 传输只服务一个客户端，对应一个会话
+    let session = Arc::new(Session::default());
+    let mut in_flight = JoinSet::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            line = lines.recv() => {
+                let line = match line {
+                    Some(Ok(line)) => line,
+                    None => {
+                        info!("stdin 已关闭，准备退出");
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        error!("读取 stdin 失败: {}", e);
+                        break;
+                    }
+                };
+
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let request = match MCPServer::parse_message(line) {
+                    Ok(request) => request,
+                    Err(response) => {
+                        let _ = tx.send(*response);
+                        continue;
+                    }
+                };
+
+                // 工具调用可能涉及多次 RPC 往返，并发执行；其余请求按到达顺序处理
+                if request.method == "tools/call" {
+                    let server = server.clone();
+                    let session = session.clone();
+                    let tx = tx.clone();
+                    in_flight.spawn(async move {
+                        if let Some(response) = server.handle_request(&session, request).await {
+                            let _ = tx.send(response);
+                        }
+                    });
+                } else if let Some(response) = server.handle_request(&session, request).await {
+                    let _ = tx.send(response);
+                }
+            }
+            Some(joined) = in_flight.join_next(), if !in_flight.is_empty() => {
+                if let Err(e) = joined {
+                    error!("请求处理任务异常退出: {}", e);
+                }
+            }
+            _ = &mut shutdown => {
+                info!("收到终止信号，准备退出");
+                break;
+            }
+        }
+    }
+
+    if !in_flight.is_empty() {
+        info!("等待 {} 个进行中的请求完成...", in_flight.len());
+        let drained = timeout(SHUTDOWN_GRACE_PERIOD, async {
+            while in_flight.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!("等待超时，放弃剩余 {} 个请求", in_flight.len());
+            in_flight.abort_all();
+        }
+    }
+
+    drop(tx);
+    writer.await.context("stdout 写入任务异常退出")?
+}
+
+/// 在独立线程中阻塞读取 stdin
+///
+/// tokio 的 stdin 读取占用阻塞线程池，未完成的读取会阻止运行时退出，
+/// 因此改用独立线程，进程结束时无需等待它。
+fn spawn_stdin_reader() -> mpsc::UnboundedReceiver<std::io::Result<String>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let failed = line.is_err();
+            if tx.send(line).is_err() || failed {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+async fn write_responses<W>(mut rx: mpsc::UnboundedReceiver<MCPResponse>, mut stdout: W) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(response) = rx.recv().await {
+        let mut line = serde_json::to_string(&response).context("序列化响应失败")?;
+        line.push('\n');
+        stdout
+            .write_all(line.as_bytes())
+            .await
+            .context("写入 stdout 失败")?;
+        stdout.flush().await.context("刷新 stdout 失败")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::server::test_server;
+    use serde_json::json;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn in_flight_tool_calls_are_answered_before_exit() {
+        let server = Arc::new(test_server().await);
+        let (line_tx, lines) = mpsc::unbounded_channel();
+        let messages = [
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "test", "version": "0.0.0"}
+                }
+            }),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {
+                    "name": "get_balance",
+                    "arguments": {"address": "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6"}
+                }
+            }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": {"name": "list_chains", "arguments": {}}
+            }),
+        ];
+        for message in messages {
+            line_tx.send(Ok(message.to_string())).unwrap();
+        }
+        // 关闭发送端即输入结束，此时两个工具调用仍在执行
+        drop(line_tx);
+
+        let (output, mut reader) = tokio::io::duplex(64 * 1024);
+        run(server, lines, output, std::future::pending()).await.unwrap();
+
+        let mut written = String::new();
+        reader.read_to_string(&mut written).await.unwrap();
+        let responses: Vec<serde_json::Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let mut ids: Vec<_> = responses.iter().map(|response| response["id"].clone()).collect();
+        ids.sort_by_key(|id| id.as_u64());
+        assert_eq!(ids, vec![json!(1), json!(2), json!(3)], "{written}");
+        for response in &responses {
+            assert!(response.get("error").is_none(), "{response}");
+            assert_ne!(response["result"]["isError"], json!(true), "{response}");
+        }
+    }
+}
@@ -5,8 +5,11 @@ use std::collections::HashMap;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MCPRequest {
     pub jsonrpc: String,
-    pub id: u64,
+    /// 通知消息没有 id，无需回复
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
     pub method: String,
+    #[serde(default)]
     pub params: Option<serde_json::Value>,
 }
 
@@ -14,8 +17,10 @@ pub struct MCPRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MCPResponse {
     pub jsonrpc: String,
-    pub id: u64,
+    pub id: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<MCPError>,
 }
 
@@ -24,14 +29,16 @@ pub struct MCPResponse {
 pub struct MCPError {
     pub code: i32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
     pub description: String,
+    #[serde(rename = "inputSchema")]
     pub input_schema: serde_json::Value,
 }
 
@@ -39,6 +46,7 @@ pub struct Tool {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ToolCall {
     pub name: String,
+    #[serde(default)]
     pub arguments: HashMap<String, serde_json::Value>,
 }
 
@@ -57,9 +65,16 @@ pub struct Content {
     pub text: String,
 }
 
+#[allow(dead_code)]
+impl MCPRequest {
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
 #[allow(dead_code)]
 impl MCPResponse {
-    pub fn success(id: u64, result: serde_json::Value) -> Self {
+    pub fn success(id: serde_json::Value, result: serde_json::Value) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             id,
@@ -68,7 +83,7 @@ impl MCPResponse {
         }
     }
 
-    pub fn error(id: u64, code: i32, message: String) -> Self {
+    pub fn error(id: serde_json::Value, code: i32, message: String) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             id,
@@ -81,3 +96,113 @@ impl MCPResponse {
         }
     }
 }
+
+/// 服务器支持的 MCP 协议版本，按从旧到新排列
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ProtocolVersion {
+    #[serde(rename = "2024-11-05")]
+    V2024_11_05,
+    #[serde(rename = "2025-03-26")]
+    V2025_03_26,
+    #[serde(rename = "2025-06-18")]
+    V2025_06_18,
+}
+
+#[allow(dead_code)]
+impl ProtocolVersion {
+    pub const SUPPORTED: &'static [ProtocolVersion] = &[
+        ProtocolVersion::V2024_11_05,
+        ProtocolVersion::V2025_03_26,
+        ProtocolVersion::V2025_06_18,
+    ];
+
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2025_06_18;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+
+    pub fn parse(version: &str) -> Option<Self> {
+        Self::SUPPORTED
+            .iter()
+            .copied()
+            .find(|supported| supported.as_str() == version)
+    }
+
+    /// 客户端请求的版本受支持时原样返回，否则返回服务器支持的最新版本
+    pub fn negotiate(requested: &str) -> Self {
+        Self::parse(requested).unwrap_or(Self::LATEST)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Implementation {
+    pub name: String,
+    pub version: String,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeParams {
+    pub protocol_version: String,
+    #[serde(default)]
+    pub capabilities: serde_json::Value,
+    pub client_info: Implementation,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeResult {
+    pub protocol_version: ProtocolVersion,
+    pub capabilities: ServerCapabilities,
+    pub server_info: Implementation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ToolsCapability>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolsCapability {
+    pub list_changed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProtocolVersion;
+
+    #[test]
+    fn negotiate_keeps_supported_version() {
+        assert_eq!(
+            ProtocolVersion::negotiate("2024-11-05"),
+            ProtocolVersion::V2024_11_05
+        );
+        assert_eq!(
+            serde_json::to_value(ProtocolVersion::V2025_03_26).unwrap(),
+            "2025-03-26"
+        );
+    }
+
+    #[test]
+    fn negotiate_falls_back_to_latest() {
+        assert_eq!(
+            ProtocolVersion::negotiate("1999-01-01"),
+            ProtocolVersion::LATEST
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use crate::ethereum::client::{format_address, EthereumClient};
+use crate::ethereum::dex::adapters_for;
+use crate::ethereum::types::ChainSummary;
+use anyhow::Result;
+use serde_json;
+use tracing::info;
+
+#[allow(dead_code)]
+pub fn list_chains<'a>(
+    clients: impl IntoIterator<Item = &'a EthereumClient>,
+    default_chain: u64,
+) -> Result<String> {
+    info!("查询已配置的链");
+
+    let chains: Vec<ChainSummary> = clients
+        .into_iter()
+        .map(|client| {
+            let chain = client.chain();
+            let protocols: Vec<String> = adapters_for(chain)
+                .iter()
+                .map(|adapter| adapter.protocol().as_str().to_string())
+                .collect();
+            let mut tokens: Vec<String> = chain.tokens.keys().cloned().collect();
+            tokens.sort();
+
+            ChainSummary {
+                chain_id: chain.chain_id,
+                name: chain.name.clone(),
+                native_symbol: chain.native_symbol.clone(),
+                wrapped_native: format_address(chain.wrapped_native),
+                protocols,
+                tokens,
+                is_default: chain.chain_id == default_chain,
+            }
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&chains)?)
+}
@@ -1,7 +1,13 @@
 pub mod balance;
+pub mod chains;
 pub mod price;
+pub mod replace;
 pub mod swap;
+pub mod wrap;
 
 pub use balance::get_balance;
+pub use chains::list_chains;
 pub use price::get_token_price;
-pub use swap::swap_tokens;
+pub use replace::{cancel_transaction, speed_up_transaction};
+pub use swap::{execute_swap, swap_tokens, SwapRequest};
+pub use wrap::{unwrap_weth, wrap_eth};
@@ -0,0 +1,198 @@
+use crate::config::GasSpeed;
+use crate::ethereum::client::EthereumClient;
+use crate::ethereum::gas::{apply_fees, replacement_fees, FeeSuggestion};
+use crate::ethereum::types::ReplacementResult;
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{
+    transaction::eip2718::TypedTransaction, Address, Eip1559TransactionRequest, Transaction, H256,
+    U256,
+};
+use serde_json;
+use std::str::FromStr;
+use tracing::info;
+
+/// 以更高的费用重发同一笔 pending 交易
+pub async fn speed_up_transaction(
+    client: &EthereumClient,
+    tx_hash: &str,
+    speed: GasSpeed,
+) -> Result<String> {
+    info!("加速交易 - hash: {}, gas_speed: {}", tx_hash, speed.as_str());
+    replace_transaction(client, tx_hash, speed, false).await
+}
+
+/// 用同一 nonce 的 0 值自转账替换 pending 交易，使原交易失效
+pub async fn cancel_transaction(
+    client: &EthereumClient,
+    tx_hash: &str,
+    speed: GasSpeed,
+) -> Result<String> {
+    info!("取消交易 - hash: {}, gas_speed: {}", tx_hash, speed.as_str());
+    replace_transaction(client, tx_hash, speed, true).await
+}
+
+async fn replace_transaction(
+    client: &EthereumClient,
+    tx_hash: &str,
+    speed: GasSpeed,
+    cancel: bool,
+) -> Result<String> {
+    let tx_hash = H256::from_str(tx_hash).context("解析交易哈希失败")?;
+    let original = client.pending_transaction(tx_hash).await?;
+
+    // legacy 交易没有 EIP-1559 费用字段，按 gasPrice 计
+    let previous_max_fee = original
+        .max_fee_per_gas
+        .or(original.gas_price)
+        .ok_or_else(|| anyhow!("交易 {:?} 缺少费用字段", tx_hash))?;
+    let previous_priority_fee = original
+        .max_priority_fee_per_gas
+        .or(original.gas_price)
+        .unwrap_or_default();
+    let fees = replacement_fees(
+        previous_max_fee,
+        previous_priority_fee,
+        &client.suggest_fees(speed).await?,
+    );
+
+    let wallet = client
+        .wallet_address()
+        .ok_or_else(|| anyhow!("只读模式下无法替换交易，请配置 PRIVATE_KEY"))?;
+    let tx = build_replacement_tx(&original, wallet, client.chain_id(), cancel, &fees);
+    let gas_limit = tx.gas().copied().unwrap_or_default().as_u64();
+    let signed = client.sign_transaction(tx).await?;
+    let transaction_data = format!("0x{}", hex::encode(&signed));
+    let replacement_hash = client.broadcast_raw_transaction(signed).await?;
+
+    let result = ReplacementResult {
+        action: if cancel { "cancel" } else { "speed_up" }.to_string(),
+        original_transaction_hash: format!("{:?}", tx_hash),
+        transaction_hash: format!("{:?}", replacement_hash),
+        nonce: original.nonce.as_u64(),
+        gas_limit,
+        previous_max_fee: crate::ethereum::client::units_to_decimal(previous_max_fee, 9)?,
+        previous_priority_fee: crate::ethereum::client::units_to_decimal(previous_priority_fee, 9)?,
+        max_fee: crate::ethereum::client::units_to_decimal(fees.max_fee_per_gas, 9)?,
+        priority_fee: crate::ethereum::client::units_to_decimal(fees.max_priority_fee_per_gas, 9)?,
+        transaction_data,
+    };
+    let result = serde_json::to_string_pretty(&result)?;
+    info!("替换交易已广播: {}", result);
+
+    Ok(result)
+}
+
+/// 构造使用同一 nonce 的替换交易：加速时原样重发（含 access list），取消时改为向自己转账 0
+fn build_replacement_tx(
+    original: &Transaction,
+    wallet: Address,
+    chain_id: u64,
+    cancel: bool,
+    fees: &FeeSuggestion,
+) -> TypedTransaction {
+    let request = if cancel {
+        Eip1559TransactionRequest::new()
+            .from(wallet)
+            .to(wallet)
+            .value(U256::zero())
+            .gas(21_000u64)
+    } else {
+        let request = Eip1559TransactionRequest::new()
+            .from(wallet)
+            .data(original.input.clone())
+            .value(original.value)
+            .gas(original.gas);
+        let request = match &original.access_list {
+            Some(access_list) => request.access_list(access_list.clone()),
+            None => request,
+        };
+        match original.to {
+            Some(to) => request.to(to),
+            None => request,
+        }
+    };
+
+    let mut tx: TypedTransaction = request.nonce(original.nonce).chain_id(chain_id).into();
+    apply_fees(&mut tx, fees);
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{
+        transaction::eip2930::{AccessList, AccessListItem},
+        Bytes, NameOrAddress,
+    };
+
+    fn gwei(value: u64) -> U256 {
+        U256::from(value) * U256::exp10(9)
+    }
+
+    fn fees() -> FeeSuggestion {
+        FeeSuggestion {
+            speed: GasSpeed::Fast,
+            base_fee: gwei(20),
+            max_priority_fee_per_gas: gwei(3),
+            max_fee_per_gas: gwei(43),
+        }
+    }
+
+    fn pending_swap(wallet: Address) -> Transaction {
+        Transaction {
+            from: wallet,
+            nonce: U256::from(42),
+            to: Some(Address::from_low_u64_be(0xbeef)),
+            value: U256::exp10(17),
+            gas: U256::from(180_000),
+            input: Bytes::from(vec![0x38, 0xed, 0x17, 0x39, 0x01]),
+            max_fee_per_gas: Some(gwei(30)),
+            max_priority_fee_per_gas: Some(gwei(1)),
+            access_list: Some(AccessList(vec![AccessListItem {
+                address: Address::from_low_u64_be(0xcafe),
+                storage_keys: vec![H256::from_low_u64_be(1)],
+            }])),
+            ..Default::default()
+        }
+    }
+
+    fn eip1559(tx: TypedTransaction) -> Eip1559TransactionRequest {
+        match tx {
+            TypedTransaction::Eip1559(inner) => inner,
+            other => panic!("expected a type-2 transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cancel_is_zero_value_self_transfer_with_same_nonce() {
+        let wallet = Address::from_low_u64_be(0xaa);
+        let original = pending_swap(wallet);
+
+        let tx = eip1559(build_replacement_tx(&original, wallet, 1, true, &fees()));
+        assert_eq!(tx.nonce, Some(U256::from(42)));
+        assert_eq!(tx.to, Some(NameOrAddress::Address(wallet)));
+        assert_eq!(tx.value, Some(U256::zero()));
+        assert_eq!(tx.gas, Some(U256::from(21_000)));
+        assert!(tx.data.is_none());
+        assert!(tx.access_list.0.is_empty());
+        assert_eq!(tx.chain_id, Some(1u64.into()));
+        assert_eq!(tx.max_fee_per_gas, Some(gwei(43)));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(gwei(3)));
+    }
+
+    #[test]
+    fn speed_up_keeps_destination_data_value_and_access_list() {
+        let wallet = Address::from_low_u64_be(0xaa);
+        let original = pending_swap(wallet);
+
+        let tx = eip1559(build_replacement_tx(&original, wallet, 1, false, &fees()));
+        assert_eq!(tx.nonce, Some(U256::from(42)));
+        assert_eq!(tx.from, Some(wallet));
+        assert_eq!(tx.to, original.to.map(NameOrAddress::Address));
+        assert_eq!(tx.data, Some(original.input.clone()));
+        assert_eq!(tx.value, Some(original.value));
+        assert_eq!(tx.gas, Some(original.gas));
+        assert_eq!(Some(tx.access_list), original.access_list);
+        assert_eq!(tx.max_fee_per_gas, Some(gwei(43)));
+    }
+}
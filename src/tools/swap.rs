@@ -1,115 +1,414 @@
-use crate::ethereum::client::EthereumClient;
-use crate::ethereum::types::SwapResult;
+use crate::config::{ApprovalMode, GasSpeed, SwapConfig, SwapRouterKind};
+use crate::ethereum::client::{
+    modelled_swap_gas, EthereumClient, PoolRef, SwapMode, SwapProtocol, SwapQuote,
+};
+use crate::ethereum::gas::{apply_fees, FeeSuggestion};
+use crate::ethereum::types::{
+    ApprovalTransaction, Permit2Authorization, SwapExecution, SwapLeg, SwapResult, TokenInfo,
+};
+use crate::ethereum::universal_router::PermitSingle;
 use anyhow::{anyhow, bail, Context, Result};
 use ethers::{
     middleware::Middleware,
-    types::Address,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, U256},
 };
 use rust_decimal::{prelude::FromPrimitive, Decimal};
 use rust_decimal_macros::dec;
 use serde_json;
-use std::str::FromStr;
-use tracing::info;
+use std::{collections::HashMap, str::FromStr, time::Duration};
+use tracing::{info, warn};
 
 #[allow(dead_code)]
 const DEFAULT_DEADLINE_SECS: u64 = 15 * 60;
 
+/// 尚未授权时 swap 无法模拟，gas 上限取 gas 模型估算的倍数，留出跨 tick 等额外消耗的余量
+const UNAPPROVED_SWAP_GAS_MULTIPLIER: u64 = 2;
+
+/// swap_tokens / execute_swap 共用的参数
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct SwapRequest {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: String,
+    pub slippage_tolerance: f64,
+    pub approval: ApprovalMode,
+    /// exact_input 时 amount 为输入数量，exact_output 时为期望的输出数量
+    pub mode: SwapMode,
+    pub router: SwapRouterKind,
+    pub gas_speed: GasSpeed,
+}
+
+impl SwapRequest {
+    /// 从工具参数解析，未指定 approval / router / gas_speed 时使用配置中的默认值
+    pub fn from_arguments(
+        arguments: &HashMap<String, serde_json::Value>,
+        defaults: &SwapConfig,
+    ) -> Result<Self> {
+        let required = |key: &str| -> Result<String> {
+            arguments
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+                .ok_or_else(|| anyhow!("缺少 {} 参数", key))
+        };
+        let approval = match arguments.get("approval").and_then(|v| v.as_str()) {
+            Some(mode) => ApprovalMode::from_str(mode)?,
+            None => defaults.approval,
+        };
+        let router = match arguments.get("router").and_then(|v| v.as_str()) {
+            Some(router) => SwapRouterKind::from_str(router)?,
+            None => defaults.router,
+        };
+        let gas_speed = match arguments.get("gas_speed").and_then(|v| v.as_str()) {
+            Some(speed) => GasSpeed::from_str(speed)?,
+            None => defaults.gas_speed,
+        };
+        let mode = match arguments.get("mode").and_then(|v| v.as_str()) {
+            Some(mode) => SwapMode::from_str(mode)?,
+            None => SwapMode::ExactInput,
+        };
+
+        Ok(Self {
+            from_token: required("from_token")?,
+            to_token: required("to_token")?,
+            amount: required("amount")?,
+            slippage_tolerance: arguments
+                .get("slippage_tolerance")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.5),
+            approval,
+            mode,
+            router,
+            gas_speed,
+        })
+    }
+}
+
+#[allow(dead_code)]
+pub async fn swap_tokens(client: &EthereumClient, request: &SwapRequest) -> Result<String> {
+    info!(
+        "模拟代币兑换 - from: {} to: {}, amount: {}, mode: {}, slippage: {}%",
+        request.from_token,
+        request.to_token,
+        request.amount,
+        request.mode.as_str(),
+        request.slippage_tolerance
+    );
+
+    let swap_result = build_swap(client, request, false).await?;
+    let result = serde_json::to_string_pretty(&swap_result)?;
+    info!("兑换模拟完成: {}", result);
+
+    Ok(result)
+}
+
+/// 构造并签名兑换交易，广播上链并等待确认；需要授权时先广播 approve 交易
 #[allow(dead_code)]
-pub async fn swap_tokens(
+pub async fn execute_swap(
     client: &EthereumClient,
-    from_token: &str,
-    to_token: &str,
-    amount: &str,
-    slippage_tolerance: f64,
-) -> Result<String> {
+    request: &SwapRequest,
+    confirmations: usize,
+    wait_timeout: Duration,
+) -> Result<SwapExecution> {
     info!(
-        "模拟代币兑换 - from: {} to: {}, amount: {}, slippage: {}%",
-        from_token, to_token, amount, slippage_tolerance
+        "执行代币兑换 - from: {} to: {}, amount: {}, slippage: {}%, confirmations: {}",
+        request.from_token,
+        request.to_token,
+        request.amount,
+        request.slippage_tolerance,
+        confirmations
     );
 
-    let from_token = Address::from_str(from_token).context("解析 from_token 失败")?;
-    let to_token = Address::from_str(to_token).context("解析 to_token 失败")?;
+    let swap = build_swap(client, request, true).await?;
+
+    let approval_transaction_hash = match &swap.approval {
+        Some(approval) => {
+            let raw = Bytes::from_str(&approval.transaction_data)
+                .context("解析已签名的 approve 交易失败")?;
+            // swap 交易的 nonce 紧跟在 approve 之后，必须等授权上链后再发送
+            let receipt = client
+                .send_raw_transaction(raw, confirmations.max(1), wait_timeout)
+                .await?;
+            if receipt.status.map(|status| status.as_u64()) != Some(1) {
+                bail!("approve 交易 {:?} 已回滚", receipt.transaction_hash);
+            }
+            info!(tx = ?receipt.transaction_hash, "授权交易已确认");
+            Some(format!("{:?}", receipt.transaction_hash))
+        }
+        None => None,
+    };
+
+    let raw = Bytes::from_str(&swap.transaction_data).context("解析已签名交易失败")?;
+    let receipt = client
+        .send_raw_transaction(raw, confirmations, wait_timeout)
+        .await?;
+
+    let wallet = client
+        .wallet_address()
+        .ok_or_else(|| anyhow!("只读模式下无法执行兑换"))?;
+    let to_address = Address::from_str(&swap.to_token)?;
+    let to_info = client.get_token_info(to_address).await?;
+    // 原生代币没有 Transfer 日志，改为统计路由合约收到、随后解包的 WETH；
+    // V4 池子直接支付原生代币，只能按余额变化统计
+    let native_v4 = swap
+        .legs
+        .iter()
+        .any(|leg| leg.protocol == SwapProtocol::UniswapV4.as_str());
+    let received = if to_info.is_native && native_v4 {
+        client.native_received(&receipt, wallet).await?
+    } else if to_info.is_native {
+        let router = Address::from_str(&swap.router_address)?;
+        EthereumClient::transferred_amount(&receipt, client.chain().wrapped_native, router)
+    } else {
+        EthereumClient::transferred_amount(&receipt, to_address, wallet)
+    };
+    let actual_output = crate::ethereum::client::units_to_decimal(received, to_info.decimals)?;
+
+    let gas_used = receipt.gas_used.map(|gas| gas.as_u64());
+    let effective_gas_price = receipt
+        .effective_gas_price
+        .map(|price| crate::ethereum::client::units_to_decimal(price, 9))
+        .transpose()?;
+    let gas_cost = match (receipt.gas_used, receipt.effective_gas_price) {
+        (Some(gas), Some(price)) => Some(crate::ethereum::client::units_to_decimal(gas * price, 18)?),
+        _ => None,
+    };
+    let status = match receipt.status.map(|status| status.as_u64()) {
+        Some(1) => "success",
+        _ => "reverted",
+    };
+
+    let execution = SwapExecution {
+        transaction_hash: format!("{:?}", receipt.transaction_hash),
+        approval_transaction_hash,
+        status: status.to_string(),
+        block_number: receipt.block_number.map(|block| block.as_u64()),
+        confirmations,
+        gas_used,
+        effective_gas_price,
+        gas_cost,
+        actual_output,
+        swap,
+    };
+    info!(
+        tx = %execution.transaction_hash,
+        status = %execution.status,
+        actual_output = %execution.actual_output,
+        "兑换交易已确认"
+    );
+
+    Ok(execution)
+}
+
+/// 报价、选择路由并签名兑换交易（以及所需的 approve 交易），不广播；
+/// 只有随后会广播（`reserve_nonces` 为 true）时才从 nonce 管理器占用 nonce
+async fn build_swap(
+    client: &EthereumClient,
+    request: &SwapRequest,
+    reserve_nonces: bool,
+) -> Result<SwapResult> {
+    let wallet = client
+        .wallet_address()
+        .ok_or_else(|| anyhow!("只读模式下无法构造兑换交易，请配置 PRIVATE_KEY"))?;
+
+    let from_token = Address::from_str(&request.from_token).context("解析 from_token 失败")?;
+    let to_token = Address::from_str(&request.to_token).context("解析 to_token 失败")?;
 
-    let input_amount = Decimal::from_str(amount).context("解析兑换数量失败")?;
-    if input_amount <= Decimal::ZERO {
+    let amount = Decimal::from_str(&request.amount).context("解析兑换数量失败")?;
+    if amount <= Decimal::ZERO {
         bail!("兑换数量必须大于 0");
     }
 
     let from_info = client.get_token_info(from_token).await?;
     let to_info = client.get_token_info(to_token).await?;
 
-    let amount_in = crate::ethereum::client::decimal_to_units(input_amount, from_info.decimals)?;
-    let quote = client
-        .quote_best_swap(
-            from_token,
-            from_info.decimals,
-            to_token,
-            to_info.decimals,
-            amount_in,
-        )
-        .await?;
+    let fees = client.suggest_fees(request.gas_speed).await?;
+    // 固定输入时把 gas 折算为输出代币，按扣除 gas 后的净输出比较；固定输出时折算为输入代币，
+    // 按计入 gas 后的总输入比较。折算失败时不计 gas
+    let priced = match request.mode {
+        SwapMode::ExactInput => (to_token, &to_info),
+        SwapMode::ExactOutput => (from_token, &from_info),
+    };
+    let gas_pricing = match client.gas_pricing(&fees, priced.0).await {
+        Ok(pricing) => Some(pricing),
+        Err(err) => {
+            warn!("无法把 gas 折算为 {}，路由不计 gas: {:#}", priced.1.symbol, err);
+            None
+        }
+    };
+
+    let quote = match request.mode {
+        SwapMode::ExactInput => {
+            let amount_in = crate::ethereum::client::decimal_to_units(amount, from_info.decimals)?;
+            client
+                .quote_best_swap(from_token, from_info.decimals, to_token, amount_in, gas_pricing)
+                .await?
+        }
+        SwapMode::ExactOutput => {
+            let amount_out = crate::ethereum::client::decimal_to_units(amount, to_info.decimals)?;
+            client
+                .quote_best_swap_exact_output(from_token, to_token, amount_out, gas_pricing)
+                .await?
+        }
+    };
+    let quote = match (request.router, request.mode, quote.protocol) {
+        // V4 池子只能经 Universal Router 执行
+        (_, _, SwapProtocol::UniswapV4) => client.to_universal_router(quote)?,
+        (
+            SwapRouterKind::Universal,
+            SwapMode::ExactInput,
+            SwapProtocol::UniswapV2 | SwapProtocol::UniswapV3 | SwapProtocol::Split,
+        ) => client.to_universal_router(quote)?,
+        (SwapRouterKind::Universal, SwapMode::ExactInput, protocol) => {
+            warn!(
+                "Universal Router 不支持 {} 路由，直接调用其合约执行",
+                protocol.as_str()
+            );
+            quote
+        }
+        (SwapRouterKind::Universal, SwapMode::ExactOutput, _) => {
+            warn!("Universal Router 暂不支持固定输出兑换，改用 legacy 路由");
+            quote
+        }
+        (SwapRouterKind::Legacy, _, _) => quote,
+    };
 
+    let input_amount =
+        crate::ethereum::client::units_to_decimal(quote.amount_in, from_info.decimals)?;
     let output_amount =
         crate::ethereum::client::units_to_decimal(quote.amount_out, to_info.decimals)?;
-    let slippage = Decimal::from_f64(slippage_tolerance).ok_or_else(|| anyhow!("解析滑点失败"))?;
+    let slippage =
+        Decimal::from_f64(request.slippage_tolerance).ok_or_else(|| anyhow!("解析滑点失败"))?;
     if slippage < Decimal::ZERO {
         bail!("滑点不能为负");
     }
 
-    let gas_estimate = estimate_gas(client, &quote).await?;
-    let gas_price_raw = client
-        .provider()
-        .get_gas_price()
-        .await
-        .context("获取 gas price 失败")?;
-    let gas_price = crate::ethereum::client::units_to_decimal(gas_price_raw, 9)?; // Convert from wei to gwei
-    let total_cost = gas_price * Decimal::from_u64(gas_estimate).unwrap_or(dec!(0));
+    let (minimum_output, maximum_input) =
+        slippage_limits(request.mode, input_amount, output_amount, slippage);
+    let min_out_units = crate::ethereum::client::decimal_to_units(minimum_output, to_info.decimals)?;
+    let max_in_units = match maximum_input {
+        Some(maximum) => crate::ethereum::client::decimal_to_units(maximum, from_info.decimals)?,
+        None => quote.amount_in,
+    };
 
-    let minimum_output = output_amount * (Decimal::ONE - slippage / dec!(100));
+    let provider = client.provider();
+    // Universal Router 从 Permit2 扣款：ERC20 授权给 Permit2，路由合约的额度由签名授予
+    let universal = quote.protocol == SwapProtocol::UniversalRouter;
+    let spender = if universal {
+        client.permit2_address()
+    } else {
+        quote.router
+    };
+    let approval = build_approval(
+        client,
+        &from_info,
+        from_token,
+        spender,
+        max_in_units,
+        request.approval,
+        &fees,
+    )
+    .await?;
 
-    let (protocol, router_address, fee_tier, transaction_data) = match quote.protocol {
-        crate::ethereum::client::SwapProtocol::UniswapV2 => {
-            let min_out_units =
-                crate::ethereum::client::decimal_to_units(minimum_output, to_info.decimals)?;
-            let tx = client
-                .build_uniswap_v2_swap_tx(
-                    amount_in,
-                    min_out_units,
-                    quote.path.clone(),
-                    client.wallet_address(),
-                    DEFAULT_DEADLINE_SECS,
-                )
-                .await?;
-            let signed = client.sign_transaction(tx).await?;
-            (
-                quote.protocol.as_str().to_string(),
-                quote.router,
-                None,
-                format!("0x{}", hex::encode(signed)),
-            )
+    let permit = if universal && !from_info.is_native {
+        let amount = match request.approval {
+            ApprovalMode::Exact => max_in_units,
+            ApprovalMode::Unlimited => (U256::one() << 160) - 1,
+        };
+        client
+            .sign_permit2(from_token, amount, quote.router, DEFAULT_DEADLINE_SECS)
+            .await?
+    } else {
+        None
+    };
+
+    let mut tx = build_swap_tx(
+        client,
+        &quote,
+        min_out_units,
+        max_in_units,
+        permit.as_ref(),
+        wallet,
+    )
+    .await?;
+
+    // 授权上链前 swap 会 revert，无法用 eth_estimateGas 模拟，改按路由的跳数和协议估算
+    let (gas_estimate, gas_limit) = match &approval {
+        None => {
+            let estimate = provider
+                .estimate_gas(&tx, None)
+                .await
+                .context("估算 gas 失败")?
+                .as_u64();
+            (estimate, estimate)
         }
-        crate::ethereum::client::SwapProtocol::UniswapV3 => {
-            let fee = quote.fee.ok_or_else(|| anyhow!("V3 报价缺少费率信息"))?;
-            let min_out_units =
-                crate::ethereum::client::decimal_to_units(minimum_output, to_info.decimals)?;
-            let tx = client
-                .build_uniswap_v3_swap_tx(
-                    from_token,
-                    to_token,
-                    fee,
-                    amount_in,
-                    min_out_units,
-                    client.wallet_address(),
-                    DEFAULT_DEADLINE_SECS,
-                )
-                .await?;
-            let signed = client.sign_transaction(tx).await?;
-            (
-                quote.protocol.as_str().to_string(),
-                quote.router,
-                Some(fee),
-                format!("0x{}", hex::encode(signed)),
+        Some(_) => unapproved_swap_gas(&quote),
+    };
+    let approval_gas = approval
+        .as_ref()
+        .map(|(_, approval)| approval.gas_estimate)
+        .unwrap_or(0);
+    let total_gas = gas_estimate + approval_gas;
+    let gas_cost =
+        crate::ethereum::client::units_to_decimal(fees.expected_fee_per_gas() * total_gas, 18)?;
+    let max_cost = crate::ethereum::client::units_to_decimal(
+        fees.max_fee_per_gas * (gas_limit + approval_gas),
+        18,
+    )?;
+    let eth_price_usd = match client.get_eth_price_in_usd().await {
+        Ok(price) => Some(price),
+        Err(err) => {
+            warn!("获取原生代币 USD 价格失败，gas 花费只以原生代币计: {:#}", err);
+            None
+        }
+    };
+    // 固定输出时 gas 折算的是输入代币，只用于选择路由
+    let gas_cost_in_output = gas_pricing
+        .filter(|_| request.mode == SwapMode::ExactInput)
+        .map(|pricing| {
+            crate::ethereum::client::units_to_decimal(
+                pricing.cost_in_output(total_gas),
+                to_info.decimals,
             )
+        })
+        .transpose()?;
+    let net_output = match (request.mode, gas_cost_in_output) {
+        (SwapMode::ExactInput, Some(cost)) => Some(output_amount - cost),
+        _ => None,
+    };
+
+    tx.set_gas(gas_limit);
+    apply_fees(&mut tx, &fees);
+
+    // approve 与 swap 使用连续的 nonce，签名失败时归还；模拟只读取下一个 nonce，不占用
+    let nonce_count = if approval.is_some() { 2 } else { 1 };
+    let first_nonce = if reserve_nonces {
+        client.reserve_nonces(nonce_count).await?
+    } else {
+        client.next_nonce().await?
+    };
+    let signed = async {
+        let approval = match approval {
+            Some((mut approve_tx, mut approval)) => {
+                approve_tx.set_nonce(first_nonce);
+                let signed = client.sign_transaction(approve_tx).await?;
+                approval.transaction_data = format!("0x{}", hex::encode(signed));
+                Some(approval)
+            }
+            None => None,
+        };
+        tx.set_nonce(first_nonce + (nonce_count - 1));
+        Ok::<_, anyhow::Error>((approval, client.sign_transaction(tx).await?))
+    }
+    .await;
+    let (approval, signed) = match signed {
+        Ok(signed) => signed,
+        Err(err) => {
+            if reserve_nonces {
+                client.release_nonces(first_nonce, nonce_count).await;
+            }
+            return Err(err);
         }
     };
 
@@ -119,26 +418,157 @@ pub async fn swap_tokens(
         input_amount,
         output_amount,
         price_impact: quote.price_impact_pct,
+        lp_fee_pct: quote.lp_fee_pct,
         gas_estimate,
-        gas_price,
-        total_cost,
+        gas_limit,
+        gas_speed: fees.speed.as_str().to_string(),
+        base_fee: crate::ethereum::client::units_to_decimal(fees.base_fee, 9)?,
+        priority_fee: crate::ethereum::client::units_to_decimal(fees.max_priority_fee_per_gas, 9)?,
+        max_fee: crate::ethereum::client::units_to_decimal(fees.max_fee_per_gas, 9)?,
+        gas_cost_eth: gas_cost,
+        gas_cost_usd: eth_price_usd.map(|price| (gas_cost * price).round_dp(2)),
+        gas_cost_in_output,
+        net_output,
+        max_cost_eth: max_cost,
+        max_cost_usd: eth_price_usd.map(|price| (max_cost * price).round_dp(2)),
         slippage_tolerance: slippage,
+        mode: request.mode.as_str().to_string(),
         minimum_output,
-        protocol,
-        fee_tier,
-        router_address: format_address(router_address),
+        maximum_input,
+        protocol: quote.protocol.as_str().to_string(),
+        fee_tier: quote.fees.clone(),
+        router_address: format_address(quote.router),
         path: quote
             .path
             .iter()
             .map(|addr| format_address(*addr))
             .collect(),
-        transaction_data,
+        transaction_data: format!("0x{}", hex::encode(signed)),
+        approval,
+        legs: quote
+            .legs
+            .iter()
+            .map(|leg| {
+                let leg_input =
+                    crate::ethereum::client::units_to_decimal(leg.amount_in, from_info.decimals)?;
+                Ok(SwapLeg {
+                    protocol: leg.protocol.as_str().to_string(),
+                    path: leg.path.iter().map(|addr| format_address(*addr)).collect(),
+                    fee_tier: leg.fees.clone(),
+                    share: (leg_input / input_amount * dec!(100)).round_dp(2),
+                    input_amount: leg_input,
+                    output_amount: crate::ethereum::client::units_to_decimal(
+                        leg.amount_out,
+                        to_info.decimals,
+                    )?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?,
+        permit2: permit
+            .map(|permit| -> Result<Permit2Authorization> {
+                Ok(Permit2Authorization {
+                    token: format_address(permit.token),
+                    spender: format_address(permit.spender),
+                    amount: match request.approval {
+                        ApprovalMode::Exact => crate::ethereum::client::units_to_decimal(
+                            permit.amount,
+                            from_info.decimals,
+                        )?
+                        .to_string(),
+                        ApprovalMode::Unlimited => "unlimited".to_string(),
+                    },
+                    expiration: permit.expiration,
+                    nonce: permit.nonce,
+                    sig_deadline: permit.sig_deadline,
+                })
+            })
+            .transpose()?,
     };
 
-    let result = serde_json::to_string_pretty(&swap_result)?;
-    info!("兑换模拟完成: {}", result);
+    Ok(swap_result)
+}
 
-    Ok(result)
+/// 授权额度不足时构造 approve 交易（尚未分配 nonce 和签名，`transaction_data` 为空），
+/// 额度足够（或输入为原生代币）时返回 None
+#[allow(clippy::too_many_arguments)]
+async fn build_approval(
+    client: &EthereumClient,
+    token_info: &TokenInfo,
+    token: Address,
+    spender: Address,
+    amount_in: U256,
+    mode: ApprovalMode,
+    fees: &FeeSuggestion,
+) -> Result<Option<(TypedTransaction, ApprovalTransaction)>> {
+    if token_info.is_native {
+        return Ok(None);
+    }
+
+    let wallet = client
+        .wallet_address()
+        .ok_or_else(|| anyhow!("只读模式下无法构造授权交易"))?;
+    let current = client.allowance(token, wallet, spender).await?;
+    if current >= amount_in {
+        return Ok(None);
+    }
+
+    let amount = match mode {
+        ApprovalMode::Exact => amount_in,
+        ApprovalMode::Unlimited => U256::MAX,
+    };
+    let mut tx = client.build_approve_tx(token, spender, amount)?;
+    let gas = client
+        .provider()
+        .estimate_gas(&tx, None)
+        .await
+        .context("估算 approve gas 失败")?;
+    tx.set_gas(gas);
+    apply_fees(&mut tx, fees);
+
+    info!(
+        token = %format_address(token),
+        spender = %format_address(spender),
+        "授权额度不足，需要先发送 approve 交易"
+    );
+
+    let approval = ApprovalTransaction {
+        token: format_address(token),
+        spender: format_address(spender),
+        current_allowance: crate::ethereum::client::units_to_decimal(current, token_info.decimals)?,
+        amount: match mode {
+            ApprovalMode::Exact => {
+                crate::ethereum::client::units_to_decimal(amount, token_info.decimals)?.to_string()
+            }
+            ApprovalMode::Unlimited => "unlimited".to_string(),
+        },
+        unlimited: mode == ApprovalMode::Unlimited,
+        gas_estimate: gas.as_u64(),
+        transaction_data: String::new(),
+    };
+    Ok(Some((tx, approval)))
+}
+
+/// 尚未授权时 swap 的 gas：按 gas 模型估算实际消耗，上限再乘以余量倍数，
+/// 多跳或多条腿的路由相应提高，避免 approve 已付费后 swap 因 gas 不足失败
+fn unapproved_swap_gas(quote: &SwapQuote) -> (u64, u64) {
+    let modelled = modelled_swap_gas(&quote.route_legs());
+    (modelled, modelled * UNAPPROVED_SWAP_GAS_MULTIPLIER)
+}
+
+/// 按滑点（百分比）计算成交限制：固定输入时限制最少输出，固定输出时限制最多输入（即 `amountInMax`）
+fn slippage_limits(
+    mode: SwapMode,
+    input_amount: Decimal,
+    output_amount: Decimal,
+    slippage: Decimal,
+) -> (Decimal, Option<Decimal>) {
+    match mode {
+        SwapMode::ExactInput => (output_amount * (Decimal::ONE - slippage / dec!(100)), None),
+        SwapMode::ExactOutput => (
+            output_amount,
+            Some(input_amount * (Decimal::ONE + slippage / dec!(100))),
+        ),
+    }
 }
 
 #[allow(dead_code)]
@@ -146,44 +576,252 @@ fn format_address(addr: Address) -> String {
     format!("0x{:x}", addr)
 }
 
-#[allow(dead_code)]
-async fn estimate_gas(
+/// 按报价的协议和兑换方向构造未签名的 swap 交易
+async fn build_swap_tx(
     client: &EthereumClient,
-    quote: &crate::ethereum::client::SwapQuote,
-) -> Result<u64> {
-    let tx = match quote.protocol {
-        crate::ethereum::client::SwapProtocol::UniswapV2 => {
+    quote: &SwapQuote,
+    amount_out_min: U256,
+    amount_in_max: U256,
+    permit: Option<&PermitSingle>,
+    recipient: Address,
+) -> Result<TypedTransaction> {
+    match (quote.protocol, quote.mode) {
+        (SwapProtocol::UniswapV2 | SwapProtocol::SushiSwap, SwapMode::ExactInput) => {
             client
                 .build_uniswap_v2_swap_tx(
+                    quote.protocol,
+                    quote.token_in,
+                    quote.token_out,
                     quote.amount_in,
+                    amount_out_min,
+                    quote.path.clone(),
+                    recipient,
+                    DEFAULT_DEADLINE_SECS,
+                )
+                .await
+        }
+        (SwapProtocol::UniswapV2, SwapMode::ExactOutput) => {
+            client
+                .build_uniswap_v2_exact_output_tx(
+                    quote.token_in,
+                    quote.token_out,
                     quote.amount_out,
+                    amount_in_max,
                     quote.path.clone(),
-                    client.wallet_address(),
+                    recipient,
                     DEFAULT_DEADLINE_SECS,
                 )
-                .await?
+                .await
         }
-        crate::ethereum::client::SwapProtocol::UniswapV3 => {
-            let fee = quote.fee.ok_or_else(|| anyhow!("V3 报价缺少费率信息"))?;
+        (SwapProtocol::UniswapV3, SwapMode::ExactInput) => {
             client
                 .build_uniswap_v3_swap_tx(
                     quote.token_in,
                     quote.token_out,
-                    fee,
+                    &quote.path,
+                    &quote.fees,
                     quote.amount_in,
+                    amount_out_min,
+                    recipient,
+                    DEFAULT_DEADLINE_SECS,
+                )
+                .await
+        }
+        (SwapProtocol::Split, SwapMode::ExactInput) => {
+            client
+                .build_split_swap_tx(
+                    quote.token_in,
+                    quote.token_out,
+                    &quote.legs,
+                    amount_out_min,
+                    recipient,
+                    DEFAULT_DEADLINE_SECS,
+                )
+                .await
+        }
+        (SwapProtocol::UniversalRouter, SwapMode::ExactInput) => {
+            client
+                .build_universal_router_swap_tx(
+                    quote.token_in,
+                    quote.token_out,
+                    &quote.legs,
+                    amount_out_min,
+                    permit,
+                    recipient,
+                    DEFAULT_DEADLINE_SECS,
+                )
+                .await
+        }
+        (SwapProtocol::Split, SwapMode::ExactOutput) => bail!("固定输出兑换不支持拆单路由"),
+        (SwapProtocol::UniversalRouter, SwapMode::ExactOutput) => {
+            bail!("Universal Router 暂不支持固定输出兑换")
+        }
+        (SwapProtocol::UniswapV4, _) => bail!("V4 路由需经 Universal Router 执行"),
+        (SwapProtocol::Curve, SwapMode::ExactInput) => match quote.pool {
+            Some(PoolRef::Curve { pool, i, j }) => {
+                client.build_curve_swap_tx(pool, i, j, quote.amount_in, amount_out_min)
+            }
+            _ => bail!("Curve 路由缺少池子信息"),
+        },
+        (SwapProtocol::BalancerV2, SwapMode::ExactInput) => match quote.pool {
+            Some(PoolRef::Balancer { pool_id }) => client.build_balancer_swap_tx(
+                pool_id,
+                quote.token_in,
+                quote.token_out,
+                quote.amount_in,
+                amount_out_min,
+                recipient,
+                DEFAULT_DEADLINE_SECS,
+            ),
+            _ => bail!("Balancer 路由缺少池子信息"),
+        },
+        (
+            protocol @ (SwapProtocol::SushiSwap | SwapProtocol::Curve | SwapProtocol::BalancerV2),
+            SwapMode::ExactOutput,
+        ) => bail!("{} 暂不支持固定输出兑换", protocol.as_str()),
+        (SwapProtocol::UniswapV3, SwapMode::ExactOutput) => {
+            let fee = match quote.fees.as_slice() {
+                [fee] => *fee,
+                _ => bail!("V3 固定输出兑换只支持单跳路由"),
+            };
+            client
+                .build_uniswap_v3_exact_output_tx(
+                    quote.token_in,
+                    quote.token_out,
+                    fee,
                     quote.amount_out,
-                    client.wallet_address(),
+                    amount_in_max,
+                    recipient,
                     DEFAULT_DEADLINE_SECS,
                 )
-                .await?
+                .await
         }
-    };
+    }
+}
 
-    let gas = client
-        .provider()
-        .estimate_gas(&tx, None)
-        .await
-        .context("估算 gas 失败")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn swap_request_parses_mode_and_defaults() {
+        let arguments: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+            "from_token": "0x0000000000000000000000000000000000000000",
+            "to_token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            "amount": "1000",
+            "mode": "exact_output"
+        }))
+        .unwrap();
+        let defaults = SwapConfig {
+            approval: ApprovalMode::Unlimited,
+            router: SwapRouterKind::Universal,
+            gas_speed: GasSpeed::Fast,
+        };
+        let request = SwapRequest::from_arguments(&arguments, &defaults).unwrap();
+        assert_eq!(request.mode, SwapMode::ExactOutput);
+        assert_eq!(request.approval, ApprovalMode::Unlimited);
+        assert_eq!(request.router, SwapRouterKind::Universal);
+        assert_eq!(request.gas_speed, GasSpeed::Fast);
+        assert_eq!(request.slippage_tolerance, 0.5);
+
+        let mut invalid = arguments.clone();
+        invalid.insert("mode".to_string(), json!("exact_both"));
+        assert!(SwapRequest::from_arguments(&invalid, &SwapConfig::default()).is_err());
+    }
+
+    #[test]
+    fn slippage_limits_output_or_input() {
+        let (minimum_output, maximum_input) =
+            slippage_limits(SwapMode::ExactInput, dec!(1), dec!(2000), dec!(0.5));
+        assert_eq!(minimum_output, dec!(1990));
+        assert_eq!(maximum_input, None);
 
-    Ok(gas.as_u64())
+        let (minimum_output, maximum_input) =
+            slippage_limits(SwapMode::ExactOutput, dec!(1), dec!(2000), dec!(0.5));
+        assert_eq!(minimum_output, dec!(2000));
+        assert_eq!(maximum_input, Some(dec!(1.005)));
+    }
+
+    #[tokio::test]
+    async fn exact_output_swap_caps_input_at_slippage_limit() {
+        use crate::ethereum::client::{test_signing_client, uniswap_v2_router};
+        use ethers::abi::AbiDecode;
+
+        let client = test_signing_client().await;
+        let weth = client.chain().wrapped_native;
+        let usdc = Address::from_low_u64_be(0x05dc);
+        let recipient = Address::from_low_u64_be(0xaa);
+
+        // 报价需要 1 ETH 换 2000 USDC，0.5% 滑点下最多付出 1.005 ETH
+        let (_, maximum_input) =
+            slippage_limits(SwapMode::ExactOutput, dec!(1), dec!(2000), dec!(0.5));
+        let amount_in_max =
+            crate::ethereum::client::decimal_to_units(maximum_input.unwrap(), 18).unwrap();
+        let quote = SwapQuote {
+            protocol: SwapProtocol::UniswapV2,
+            mode: SwapMode::ExactOutput,
+            router: client.chain().uniswap_v2().unwrap().router,
+            token_in: Address::zero(),
+            token_out: usdc,
+            amount_in: U256::exp10(18),
+            amount_out: U256::from(2_000_000_000u64),
+            path: vec![weth, usdc],
+            fees: vec![],
+            price_impact_pct: Decimal::ZERO,
+            lp_fee_pct: dec!(0.3),
+            legs: vec![],
+            pool: None,
+        };
+
+        let tx = build_swap_tx(&client, &quote, quote.amount_out, amount_in_max, None, recipient)
+            .await
+            .unwrap();
+        assert_eq!(tx.value(), Some(&U256::from(1_005_000_000_000_000_000u64)));
+        let call =
+            uniswap_v2_router::SwapETHForExactTokensCall::decode(tx.data().unwrap()).unwrap();
+        assert_eq!(call.amount_out, quote.amount_out);
+        assert_eq!(call.to, recipient);
+    }
+
+    #[test]
+    fn unapproved_swap_gas_scales_with_hops() {
+        let token = |n: u64| Address::from_low_u64_be(n);
+        let quote = |protocol, path: Vec<Address>, fees: Vec<u32>| SwapQuote {
+            protocol,
+            mode: SwapMode::ExactInput,
+            router: token(0xff),
+            token_in: path[0],
+            token_out: *path.last().unwrap(),
+            amount_in: U256::exp10(18),
+            amount_out: U256::exp10(9),
+            path,
+            fees,
+            price_impact_pct: Decimal::ZERO,
+            lp_fee_pct: Decimal::ZERO,
+            legs: vec![],
+            pool: None,
+        };
+
+        let single = quote(SwapProtocol::UniswapV3, vec![token(1), token(2)], vec![500]);
+        let three_hops = quote(
+            SwapProtocol::UniswapV3,
+            vec![token(1), token(2), token(3), token(4)],
+            vec![500, 3000, 500],
+        );
+        let (single_estimate, single_limit) = unapproved_swap_gas(&single);
+        let (estimate, limit) = unapproved_swap_gas(&three_hops);
+        assert_eq!(single_estimate, modelled_swap_gas(&single.route_legs()));
+        assert_eq!(single_limit, single_estimate * UNAPPROVED_SWAP_GAS_MULTIPLIER);
+        assert!(estimate > single_estimate);
+        assert_eq!(limit, estimate * UNAPPROVED_SWAP_GAS_MULTIPLIER);
+        // 旧的固定上限 250k 不足以覆盖三跳 exactInput
+        assert!(limit > 500_000, "{limit}");
+
+        // 拆单路由按各条腿合计
+        let mut split = quote(SwapProtocol::Split, vec![token(1), token(4)], vec![]);
+        split.legs = vec![single.route_legs()[0].clone(), three_hops.route_legs()[0].clone()];
+        let (split_estimate, _) = unapproved_swap_gas(&split);
+        assert!(split_estimate > estimate);
+    }
 }
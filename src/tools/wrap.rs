@@ -0,0 +1,71 @@
+use crate::config::GasSpeed;
+use crate::ethereum::client::{format_address, EthereumClient};
+use crate::ethereum::gas::apply_fees;
+use crate::ethereum::types::WrapResult;
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::middleware::Middleware;
+use rust_decimal::Decimal;
+use serde_json;
+use std::str::FromStr;
+use tracing::info;
+
+/// 把原生代币包装为 WETH，返回已签名的 `deposit()` 交易
+#[allow(dead_code)]
+pub async fn wrap_eth(client: &EthereumClient, amount: &str, speed: GasSpeed) -> Result<String> {
+    info!("包装原生代币 - amount: {}", amount);
+    build_wrap(client, amount, true, speed).await
+}
+
+/// 把 WETH 解包为原生代币，返回已签名的 `withdraw()` 交易
+#[allow(dead_code)]
+pub async fn unwrap_weth(client: &EthereumClient, amount: &str, speed: GasSpeed) -> Result<String> {
+    info!("解包 WETH - amount: {}", amount);
+    build_wrap(client, amount, false, speed).await
+}
+
+async fn build_wrap(
+    client: &EthereumClient,
+    amount: &str,
+    wrap: bool,
+    speed: GasSpeed,
+) -> Result<String> {
+    let amount = Decimal::from_str(amount).context("解析数量失败")?;
+    if amount <= Decimal::ZERO {
+        bail!("数量必须大于 0");
+    }
+    if client.is_read_only() {
+        return Err(anyhow!("只读模式下无法构造交易，请配置 PRIVATE_KEY"));
+    }
+
+    // WETH 与原生代币都是 18 位小数
+    let amount_units = crate::ethereum::client::decimal_to_units(amount, 18)?;
+    let mut tx = if wrap {
+        client.build_wrap_tx(amount_units)?
+    } else {
+        client.build_unwrap_tx(amount_units)?
+    };
+
+    let provider = client.provider();
+    let gas_estimate = provider
+        .estimate_gas(&tx, None)
+        .await
+        .context("估算 gas 失败，请检查余额是否足够")?;
+    let fees = client.suggest_fees(speed).await?;
+    tx.set_gas(gas_estimate);
+    apply_fees(&mut tx, &fees);
+    let signed = client.sign_transaction(tx).await?;
+
+    let result = WrapResult {
+        action: if wrap { "wrap" } else { "unwrap" }.to_string(),
+        wrapped_token: format_address(client.chain().wrapped_native),
+        amount,
+        gas_estimate: gas_estimate.as_u64(),
+        priority_fee: crate::ethereum::client::units_to_decimal(fees.max_priority_fee_per_gas, 9)?,
+        max_fee: crate::ethereum::client::units_to_decimal(fees.max_fee_per_gas, 9)?,
+        transaction_data: format!("0x{}", hex::encode(signed)),
+    };
+    let result = serde_json::to_string_pretty(&result)?;
+    info!("包装交易构造完成: {}", result);
+
+    Ok(result)
+}